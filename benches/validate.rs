@@ -0,0 +1,101 @@
+//! Compares the full error output of `validate` with the short-circuiting `is_valid`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use json_schema::{compiled::CompiledSchema, context::Context, schema::JsonSchema};
+use serde_json::{json, Value};
+use url::Url;
+
+fn compile(schema: Value) -> CompiledSchema {
+    let url = Url::parse("file:///bench/schema.json").unwrap();
+    let mut schema: JsonSchema = serde_json::from_value(schema).unwrap();
+    let mut context = Context::new();
+    futures::executor::block_on(context.compile_schema(&mut schema, url.clone()));
+    CompiledSchema::new(context, url).unwrap()
+}
+
+fn order(i: usize, valid: bool) -> Value {
+    json!({
+        "id": if valid { json!(i) } else { json!(format!("{i}")) },
+        "status": if valid { "shipped" } else { "lost" },
+        "contact": if i % 2 == 0 { json!({ "email": "a@example.com" }) } else { json!({ "phone": "+1 555" }) },
+        "lines": (0..20).map(|j| json!({
+            "sku": format!("SKU-{j:04}"),
+            "quantity": if valid { j + 1 } else { 0 },
+            "price": 9.99,
+        })).collect::<Vec<_>>(),
+    })
+}
+
+fn bench(c: &mut Criterion) {
+    let schema = compile(json!({
+        "type": "object",
+        "required": ["id", "status", "lines"],
+        "properties": {
+            "id": { "type": "integer", "minimum": 0 },
+            "status": { "enum": ["pending", "shipped", "delivered"] },
+            "contact": {
+                "oneOf": [
+                    { "required": ["email"], "properties": { "email": { "type": "string" } } },
+                    { "required": ["phone"], "properties": { "phone": { "type": "string" } } }
+                ]
+            },
+            "lines": {
+                "type": "array",
+                "minItems": 1,
+                "items": {
+                    "type": "object",
+                    "required": ["sku", "quantity"],
+                    "properties": {
+                        "sku": { "type": "string", "pattern": "^SKU-[0-9]{4}$" },
+                        "quantity": { "type": "integer", "minimum": 1 },
+                        "price": { "anyOf": [{ "type": "integer" }, { "type": "number", "minimum": 0 }] }
+                    },
+                    "additionalProperties": false
+                }
+            }
+        }
+    }));
+    let valid: Vec<Value> = (0..100).map(|i| order(i, true)).collect();
+    let invalid: Vec<Value> = (0..100).map(|i| order(i, false)).collect();
+
+    let mut group = c.benchmark_group("valid");
+    group.bench_function("validate", |b| {
+        b.iter(|| {
+            valid
+                .iter()
+                .filter(|v| schema.validate(black_box(*v)).is_ok())
+                .count()
+        })
+    });
+    group.bench_function("is_valid", |b| {
+        b.iter(|| {
+            valid
+                .iter()
+                .filter(|v| schema.is_valid(black_box(*v)))
+                .count()
+        })
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("invalid");
+    group.bench_function("validate", |b| {
+        b.iter(|| {
+            invalid
+                .iter()
+                .filter(|v| schema.validate(black_box(*v)).is_ok())
+                .count()
+        })
+    });
+    group.bench_function("is_valid", |b| {
+        b.iter(|| {
+            invalid
+                .iter()
+                .filter(|v| schema.is_valid(black_box(*v)))
+                .count()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
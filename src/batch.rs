@@ -0,0 +1,163 @@
+//! Validation of JSON Lines (NDJSON) streams across a pool of worker threads.
+//!
+//! Lines are read in chunks and handed to the workers, results are reordered and passed on in
+//! line order. At most `workers * 2` chunks are in flight at any time, so memory stays bounded no
+//! matter how large the stream is.
+
+use crate::{compiled::CompiledSchema, validator::ValidationError};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead},
+    panic::{self, AssertUnwindSafe},
+    sync::{mpsc, Mutex},
+    thread,
+};
+
+/// The outcome of validating a single line of the stream.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LineResult {
+    /// 1-based line number in the stream.
+    pub line: usize,
+    pub valid: bool,
+    /// set when the line is not valid json, the line is then reported as invalid without errors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parse_error: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<ValidationError>,
+}
+
+impl LineResult {
+    fn new(schema: &CompiledSchema, line: usize, text: &str) -> Self {
+        match serde_json::from_str(text) {
+            Ok(value) => {
+                let errors = schema.validate(&value).err().unwrap_or_default();
+                Self {
+                    line,
+                    valid: errors.is_empty(),
+                    parse_error: None,
+                    errors,
+                }
+            }
+            Err(error) => Self {
+                line,
+                valid: false,
+                parse_error: Some(error.to_string()),
+                errors: vec![],
+            },
+        }
+    }
+}
+
+pub struct BatchOptions {
+    /// number of worker threads, defaults to the available parallelism.
+    pub workers: usize,
+    /// number of lines handed to a worker at once.
+    pub chunk_size: usize,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            workers: thread::available_parallelism().map_or(1, usize::from),
+            chunk_size: 256,
+        }
+    }
+}
+
+/// A chunk of consecutive lines, `(line number, text)` with blank lines already skipped.
+struct Chunk {
+    index: usize,
+    lines: Vec<(usize, String)>,
+}
+
+/// Validate every non blank line of `reader` against `schema`, calling `sink` with the results in
+/// line order. Stops at the first error returned by the reader or the sink.
+pub fn validate_lines(
+    schema: &CompiledSchema,
+    mut reader: impl BufRead,
+    options: &BatchOptions,
+    mut sink: impl FnMut(LineResult) -> io::Result<()>,
+) -> io::Result<()> {
+    let workers = options.workers.max(1);
+    let chunk_size = options.chunk_size.max(1);
+    let max_in_flight = workers * 2;
+
+    let (chunk_tx, chunk_rx) = mpsc::channel::<Chunk>();
+    let chunk_rx = Mutex::new(chunk_rx);
+    let chunk_rx = &chunk_rx;
+    // the sender is moved into the scope, dropping it on return stops the workers before the join
+    thread::scope(move |scope| {
+        let (result_tx, result_rx) = mpsc::channel::<(usize, thread::Result<Vec<LineResult>>)>();
+        for _ in 0..workers {
+            let result_tx = result_tx.clone();
+            scope.spawn(move || loop {
+                // the lock is released before validating so other workers can take the next chunk
+                let chunk = match chunk_rx.lock() {
+                    Ok(chunk_rx) => chunk_rx.recv(),
+                    Err(_) => return,
+                };
+                let Ok(chunk) = chunk else { return };
+                // a panic is handed to the reader so it does not wait for a chunk that never comes
+                let results = panic::catch_unwind(AssertUnwindSafe(|| {
+                    chunk
+                        .lines
+                        .iter()
+                        .map(|(line, text)| LineResult::new(schema, *line, text))
+                        .collect()
+                }));
+                if result_tx.send((chunk.index, results)).is_err() {
+                    return;
+                }
+            });
+        }
+        drop(result_tx);
+
+        let mut line = 0;
+        let mut eof = false;
+        let mut sent = 0;
+        let mut next = 0;
+        // results that arrived before the chunks preceding them
+        let mut pending = BTreeMap::new();
+        while !eof || next < sent {
+            while !eof && sent - next < max_in_flight {
+                let mut lines = Vec::with_capacity(chunk_size);
+                while lines.len() < chunk_size {
+                    let mut text = String::new();
+                    if reader.read_line(&mut text)? == 0 {
+                        eof = true;
+                        break;
+                    }
+                    line += 1;
+                    if !text.trim().is_empty() {
+                        lines.push((line, text));
+                    }
+                }
+                if lines.is_empty() {
+                    continue;
+                }
+                // the workers hold the receiver until the scope ends
+                let _ = chunk_tx.send(Chunk { index: sent, lines });
+                sent += 1;
+            }
+            if next == sent {
+                continue;
+            }
+            let Ok((index, results)) = result_rx.recv() else {
+                break;
+            };
+            pending.insert(
+                index,
+                results.unwrap_or_else(|payload| panic::resume_unwind(payload)),
+            );
+            while let Some(results) = pending.remove(&next) {
+                for result in results {
+                    sink(result)?;
+                }
+                next += 1;
+            }
+        }
+        Ok(())
+    })
+}
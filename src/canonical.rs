@@ -0,0 +1,244 @@
+//! Canonical forms of compiled schemas, to find schemas that are the same across documents.
+//!
+//! The canonical form is a json document of the schema with every reference inlined, only
+//! references back to a schema that is being expanded remain, as a `$ref` to the location of that
+//! schema in the canonical document. Equivalent spellings are normalized on the way: a `type`
+//! array of one type, an `enum` of one value, the order of `required` and `enum`, `{}` and
+//! `true`, keywords set to their default like `minLength: 0` and `1.0` and `1`. Identifiers such
+//! as `$id` and `$anchor` differ between documents by nature and are always dropped.
+
+use crate::{
+    context::Context,
+    migrate::{SUBSCHEMA, SUBSCHEMAS, SUBSCHEMA_MAPS},
+    schema::ResolvedJsonSchema,
+    validator::escape,
+};
+use serde_json::{Map, Number, Value};
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+use url::Url;
+
+/// Keywords that only annotate, removed with [`CanonicalOptions::strip_annotations`].
+const ANNOTATIONS: [&str; 7] = [
+    "title",
+    "description",
+    "default",
+    "examples",
+    "deprecated",
+    "readOnly",
+    "writeOnly",
+];
+
+/// Keywords that identify or hold schemas but do not constrain anything themselves.
+const IDENTIFIERS: [&str; 6] = [
+    "$id",
+    "$schema",
+    "$anchor",
+    "$comment",
+    "$defs",
+    "definitions",
+];
+
+/// Keywords with a subschema that has no effect when it is `true`.
+const TRUE_BY_DEFAULT: [&str; 7] = [
+    "additionalProperties",
+    "items",
+    "propertyNames",
+    "unevaluatedItems",
+    "unevaluatedProperties",
+    "then",
+    "else",
+];
+
+#[derive(Default)]
+pub struct CanonicalOptions {
+    /// also remove annotations like `title` and `description`, so schemas that only differ in
+    /// their documentation are the same.
+    pub strip_annotations: bool,
+}
+
+pub struct Canonicalizer<'c> {
+    context: &'c Context,
+    options: CanonicalOptions,
+}
+
+impl<'c> Canonicalizer<'c> {
+    pub fn new(context: &'c Context, options: CanonicalOptions) -> Self {
+        Self { context, options }
+    }
+
+    /// The canonical form of the schema compiled at `url`, `None` if there is none.
+    pub fn canonicalize(&self, url: &Url) -> Option<Value> {
+        self.context.schema(url)?;
+        let mut stack = vec![];
+        Some(self.schema(url, &mut String::new(), &mut stack))
+    }
+
+    /// A sha-256 hex digest of the canonical form, the same for schemas that are structurally
+    /// identical.
+    pub fn fingerprint(&self, url: &Url) -> Option<String> {
+        let canonical = self.canonicalize(url)?.to_string();
+        let digest = Sha256::digest(canonical.as_bytes());
+        Some(digest.iter().fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        }))
+    }
+
+    /// `stack` holds the schemas being expanded and their location in the canonical document.
+    fn schema(&self, url: &Url, pointer: &mut String, stack: &mut Vec<(Url, String)>) -> Value {
+        if let Some((_, location)) = stack.iter().find(|(entered, _)| entered == url) {
+            let mut reference = Map::new();
+            reference.insert("$ref".to_owned(), Value::String(format!("#{location}")));
+            return Value::Object(reference);
+        }
+        let keywords = match self.context.schema(url) {
+            Some(ResolvedJsonSchema::Object(keywords)) => keywords,
+            Some(ResolvedJsonSchema::Bool(bool)) => return Value::Bool(*bool),
+            // unresolved schemas fail every value
+            None => return Value::Bool(false),
+        };
+        let Ok(Value::Object(mut map)) = serde_json::to_value(keywords) else {
+            return Value::Bool(false);
+        };
+        for keyword in IDENTIFIERS {
+            map.remove(keyword);
+        }
+        if self.options.strip_annotations {
+            for keyword in ANNOTATIONS {
+                map.remove(keyword);
+            }
+        }
+
+        stack.push((url.clone(), pointer.clone()));
+        let truncate = pointer.len();
+        // subschemas are serialized as the url they were compiled at
+        for (keyword, value) in map.iter_mut() {
+            let keyword = keyword.as_str();
+            pointer.push('/');
+            pointer.push_str(&escape(keyword));
+            match value {
+                Value::Array(schemas) if SUBSCHEMAS.contains(&keyword) => {
+                    for (i, schema) in schemas.iter_mut().enumerate() {
+                        let truncate = pointer.len();
+                        let _ = write!(pointer, "/{i}");
+                        self.subschema(schema, pointer, stack);
+                        pointer.truncate(truncate);
+                    }
+                }
+                Value::Object(schemas) if SUBSCHEMA_MAPS.contains(&keyword) => {
+                    for (name, schema) in schemas.iter_mut() {
+                        let truncate = pointer.len();
+                        pointer.push('/');
+                        pointer.push_str(&escape(name));
+                        self.subschema(schema, pointer, stack);
+                        pointer.truncate(truncate);
+                    }
+                }
+                value if SUBSCHEMA.contains(&keyword) => self.subschema(value, pointer, stack),
+                _ => {}
+            }
+            pointer.truncate(truncate);
+        }
+        stack.pop();
+
+        normalize(&mut map);
+        match map.is_empty() {
+            true => Value::Bool(true),
+            false => Value::Object(map),
+        }
+    }
+
+    fn subschema(&self, schema: &mut Value, pointer: &mut String, stack: &mut Vec<(Url, String)>) {
+        if let Some(url) = schema.as_str().and_then(|url| Url::parse(url).ok()) {
+            *schema = self.schema(&url, pointer, stack);
+        }
+    }
+}
+
+/// Normalize the keywords of one schema, its subschemas are already canonical.
+fn normalize(map: &mut Map<String, Value>) {
+    let mut types: Vec<Value> = match map.remove("type") {
+        Some(Value::Array(types)) => types,
+        Some(ty) => vec![ty],
+        None => vec![],
+    };
+    // every integer is a number
+    if types.contains(&Value::String("number".to_owned())) {
+        types.retain(|ty| ty != "integer");
+    }
+    types.sort_by_key(|ty| ty.to_string());
+    types.dedup();
+    match types.len() {
+        0 => {}
+        1 => {
+            map.insert("type".to_owned(), types.remove(0));
+        }
+        _ => {
+            map.insert("type".to_owned(), Value::Array(types));
+        }
+    }
+
+    for value in map.values_mut() {
+        normalize_numbers(value);
+    }
+    if let Some(Value::Array(values)) = map.get_mut("enum") {
+        values.sort_by_key(|value| value.to_string());
+        values.dedup();
+        if values.len() == 1 && !map.contains_key("const") {
+            if let Some(Value::Array(mut values)) = map.remove("enum") {
+                map.insert("const".to_owned(), values.remove(0));
+            }
+        }
+    }
+    if let Some(Value::Array(required)) = map.get_mut("required") {
+        required.sort_by_key(|name| name.to_string());
+        required.dedup();
+        if required.is_empty() {
+            map.remove("required");
+        }
+    }
+    if let Some(Value::String(reference)) = map.get_mut("$dynamicRef") {
+        // only the anchor is the same across documents
+        if let Some((_, fragment)) = reference.split_once('#') {
+            *reference = format!("#{fragment}");
+        }
+    }
+
+    let defaults = [
+        ("minLength", Value::from(0)),
+        ("minItems", Value::from(0)),
+        ("minProperties", Value::from(0)),
+        ("uniqueItems", Value::Bool(false)),
+    ];
+    for (keyword, default) in defaults {
+        if map.get(keyword) == Some(&default) {
+            map.remove(keyword);
+        }
+    }
+    for keyword in TRUE_BY_DEFAULT {
+        if map.get(keyword) == Some(&Value::Bool(true)) {
+            map.remove(keyword);
+        }
+    }
+    // `if` without `then` and `else` has no effect
+    if !map.contains_key("then") && !map.contains_key("else") {
+        map.remove("if");
+    }
+}
+
+/// Numbers without a fraction are written as integers, `1.0` is the same as `1`.
+fn normalize_numbers(value: &mut Value) {
+    match value {
+        Value::Number(number) => {
+            if let Some(float) = number.as_f64().filter(|_| number.is_f64()) {
+                if float.fract() == 0.0 && float.abs() < i64::MAX as f64 {
+                    *number = Number::from(float as i64);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(normalize_numbers),
+        Value::Object(map) => map.values_mut().for_each(normalize_numbers),
+        _ => {}
+    }
+}
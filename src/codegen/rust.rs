@@ -5,22 +5,108 @@
 
 use crate::{
     context::Context,
-    keywords::{FormatKeyword, Keywords, TypeKeyword},
+    graph::{EdgeKind, ReferenceGraph},
+    keywords::{Data, FormatKeyword, Keywords, TypeKeyword},
+    lint::is_false,
     schema::{JsonSchema, ResolvedJsonSchema},
+    tree::TYPES,
 };
-use case_utils::Case;
-use serde_json::Value;
+use resolve_ident::{schema_name, try_resolve_ident, type_ident, ResolveIdentError, RESERVED};
+use serde_json::{Number, Value};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     io::{self, Write},
 };
 use url::Url;
 
 mod resolve_ident;
 
+/// Keywords of rust, fields with these names are written as raw identifiers.
+const KEYWORDS: [&str; 49] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield", "union",
+];
+
+/// Keywords that can't be raw identifiers, fields with these names get a `_` suffix.
+const PATH_KEYWORDS: [&str; 4] = ["crate", "self", "Self", "super"];
+
+const DERIVE: &str = "#[derive(Clone, Debug, ::serde::Deserialize, ::serde::Serialize)]";
+
+/// Compiles the `PATTERN` of a newtype once, the pattern is checked when generating.
+const COMPILE_PATTERN: [&str; 2] = [
+    "        static REGEX: ::std::sync::OnceLock<::regex::Regex> = ::std::sync::OnceLock::new();",
+    "        let regex = REGEX.get_or_init(|| ::regex::Regex::new(PATTERN).unwrap());",
+];
+
+const SPLIT_EMAIL: &str =
+    "        let (local, domain) = value.split_once('@').unwrap_or_default();";
+
+/// The error of validated code, written once before the types.
+const VALIDATION_ERROR: &str = r#"/// A value that breaks a constraint of its schema.
+#[derive(Clone, Debug)]
+pub struct ValidationError {
+    /// the url of the schema.
+    pub location: &'static str,
+    pub message: &'static str,
+}
+
+impl ValidationError {
+    pub fn new(location: &'static str, message: &'static str) -> Self {
+        Self { location, message }
+    }
+}
+
+impl ::std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+impl ::std::error::Error for ValidationError {}
+"#;
+
+/// Integer enums are (de)serialized as their discriminant, this needs the `serde_repr` crate.
+const DERIVE_REPR: &str =
+    "#[derive(Clone, Copy, Debug, ::serde_repr::Deserialize_repr, ::serde_repr::Serialize_repr)]";
+
 struct CodegenState<'a> {
     pub context: &'a Context,
     idents: HashMap<Url, String>,
+    /// type identifiers in use.
+    taken: HashSet<String>,
+    /// what the type being written can't represent, written as comments above it.
+    notes: Vec<String>,
+    /// the reference cycle each recursive schema is part of.
+    components: HashMap<Url, usize>,
+    /// the reference cycle of the type being written.
+    component: Option<usize>,
+    /// how many `Vec`s or maps deep the type being resolved is, these are already indirections.
+    indirect: usize,
+    /// schemas without a type of their own that are being resolved.
+    inline: HashSet<Url>,
+    /// branches of tagged enums mapped to the property that tags them.
+    tags: HashMap<Url, String>,
+    /// `oneOf`s whose branches are told apart by a property.
+    tagged: HashMap<Url, Tagged>,
+    /// constrained scalars are newtypes and structs can be validated, see [`RustOptions`].
+    validated: bool,
+    /// the lines of `validate` of the struct being written.
+    checks: Vec<String>,
+    /// the field identifiers of the struct being written.
+    fields: Vec<String>,
+    /// the defaults of the fields of the struct being written, as the field, type, expression
+    /// and whether serde fills in a missing field with it.
+    defaults: Vec<(String, String, String, bool)>,
+    /// how the fields of the struct being written are initialized by `Default`, `None` if one of
+    /// them has no default.
+    initializers: Option<Vec<String>>,
+    /// whether the type being written is deprecated.
+    deprecated: bool,
+    /// whether the type being written uses a deprecated field or type.
+    uses_deprecated: bool,
 }
 
 impl<'a> CodegenState<'a> {
@@ -28,63 +114,446 @@ impl<'a> CodegenState<'a> {
         Self {
             context,
             idents: HashMap::new(),
+            taken: HashSet::new(),
+            notes: vec![],
+            components: HashMap::new(),
+            component: None,
+            indirect: 0,
+            inline: HashSet::new(),
+            tags: HashMap::new(),
+            tagged: HashMap::new(),
+            validated: false,
+            checks: vec![],
+            fields: vec![],
+            defaults: vec![],
+            initializers: None,
+            deprecated: false,
+            uses_deprecated: false,
+        }
+    }
+
+    /// Note the use of a named type, generated code that uses a deprecated type allows it.
+    fn use_type(&mut self, url: &Url) {
+        if let Some(ResolvedJsonSchema::Object(keywords)) = self.context.schemas.get(url) {
+            self.uses_deprecated |= self.idents.contains_key(url) && is_deprecated(keywords);
+        }
+    }
+
+    /// The shape of a schema, scalars with constraints are newtypes in validated code.
+    fn shape(&self, keywords: &Keywords) -> Shape {
+        match shape(keywords) {
+            Shape::Inline if self.validated && is_constrained(keywords) => Shape::Newtype,
+            shape => shape,
+        }
+    }
+
+    fn note(&mut self, note: impl Into<String>) {
+        let note = note.into();
+        if !self.notes.contains(&note) {
+            self.notes.push(note);
+        }
+    }
+
+    /// A named type, boxed if it contains the type being written without an indirection.
+    fn named_type(&self, url: &Url, ident: &str) -> String {
+        let component = self.components.get(url);
+        match self.indirect == 0 && component.is_some() && component == self.component.as_ref() {
+            true => format!("Box<{ident}>"),
+            false => ident.to_owned(),
         }
     }
 }
 
-fn get_url(schema: &JsonSchema) -> &Url {
-    match schema {
-        JsonSchema::Resolved(url) => url,
-        _ => panic!("all json schemas should be resolved"),
+/// What a schema is generated as.
+enum Shape {
+    Struct,
+    Enum,
+    Constant,
+    /// a type written where the schema is used, like `String` or `Vec<T>`.
+    Inline,
+    /// a scalar that can only hold values that meet its constraints.
+    Newtype,
+}
+
+fn shape(keywords: &Keywords) -> Shape {
+    match keywords {
+        Keywords {
+            constant: Some(_), ..
+        } => Shape::Constant,
+        // enum, oneOf or multiple types must be represented as a rust enum
+        Keywords { enum_: Some(_), .. }
+        | Keywords {
+            one_of: Some(_), ..
+        }
+        | Keywords {
+            type_: Some(TypeKeyword::Multiple(_)),
+            ..
+        } => Shape::Enum,
+        Keywords {
+            type_: Some(TypeKeyword::Single(ty)),
+            ..
+        } if ty == "object" => Shape::Struct,
+        Keywords { type_: Some(_), .. } => Shape::Inline,
+        // without a type, properties or allOf can still be represented as a struct
+        Keywords { props: Some(_), .. }
+        | Keywords {
+            all_of: Some(_), ..
+        } => Shape::Struct,
+        _ => Shape::Inline,
     }
 }
 
-fn is_number(str: &str) -> bool {
-    str.chars().all(|c| c.is_numeric())
+fn get_url(schema: &JsonSchema) -> Option<&Url> {
+    match schema {
+        JsonSchema::Resolved(url) => Some(url),
+        _ => None,
+    }
 }
 
-fn resolve_url_file_path_ident(url: &Url) -> String {
-    let path = url.path();
-    let path = std::path::Path::new(path);
-    let ident = path
-        .file_stem()
-        .and_then(|os_str| os_str.to_str())
-        .expect("valid utf-8 path");
-    Case::Pascal.convert(ident)
+/// A `oneOf` of structs told apart by the value of one of their properties.
+struct Tagged {
+    property: String,
+    /// the branches and the value of the property in each.
+    values: Vec<(Url, String)>,
 }
 
-fn resolve_url_ident(url: &Url) -> String {
-    let path_ident = resolve_url_file_path_ident(url);
+/// The string a property of a struct is constant to, by `const` or an `enum` of one value.
+fn tag_value<'c>(context: &'c Context, keywords: &'c Keywords, property: &str) -> Option<&'c str> {
+    let schema = keywords.props.as_ref()?.map.get(property)?;
+    let Some(ResolvedJsonSchema::Object(keywords)) = context.schemas.get(get_url(schema)?) else {
+        return None;
+    };
+    match (&keywords.constant, &keywords.enum_) {
+        (Some(constant), _) => constant.0.value()?.as_str(),
+        (None, Some(enum_)) => match enum_.0.value()?.as_slice() {
+            [value] => value.as_str(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
 
-    if let Some(fragment) = url.fragment() {
-        if let Some(last) = fragment.split("/").last() {
-            if is_number(last) {
-                path_ident
-            } else {
-                Case::Pascal.convert(last)
+/// Find what tells the branches of a `oneOf` of structs apart. That is the property of an OpenAPI
+/// `discriminator`, with values from its mapping or the names of the schemas, or else the first
+/// property every branch requires to be a distinct constant string.
+fn discriminate(context: &Context, keywords: &Keywords, location: &Url) -> Option<Tagged> {
+    let one_of = keywords.one_of.as_ref()?;
+    let mut branches = vec![];
+    for schema in &one_of.0 {
+        let url = get_url(schema)?;
+        match context.schemas.get(url)? {
+            ResolvedJsonSchema::Bool(false) => continue,
+            ResolvedJsonSchema::Object(branch) if matches!(shape(branch), Shape::Struct) => {
+                branches.push((url, branch));
             }
-        } else {
-            path_ident
+            _ => return None,
         }
-    } else {
-        path_ident
     }
+    let distinct = |values: &[(Url, String)]| {
+        let unique: HashSet<&String> = values.iter().map(|(_, value)| value).collect();
+        !values.is_empty() && unique.len() == values.len()
+    };
+
+    if let Some(discriminator) = &keywords.discriminator {
+        let property = &discriminator.property_name;
+        let values: Vec<(Url, String)> = branches
+            .iter()
+            .map(|(url, branch)| {
+                let mapped = discriminator
+                    .mapping
+                    .iter()
+                    .find(|(_, reference)| location.join(reference).ok().as_ref() == Some(*url));
+                let value = match mapped {
+                    Some((value, _)) => value.clone(),
+                    None => tag_value(context, branch, property)
+                        .map(str::to_owned)
+                        .unwrap_or_else(|| schema_name(url)),
+                };
+                ((*url).clone(), value)
+            })
+            .collect();
+        return distinct(&values).then(|| Tagged {
+            property: property.clone(),
+            values,
+        });
+    }
+
+    let (_, first) = branches.first()?;
+    let mut properties: Vec<&String> = first.required.iter().flat_map(|r| &r.0).collect();
+    properties.sort();
+    properties.into_iter().find_map(|property| {
+        let values: Vec<(Url, String)> = branches
+            .iter()
+            .map(|(url, branch)| {
+                let required = branch.required.as_ref()?;
+                if !required.0.contains(property) {
+                    return None;
+                }
+                let value = tag_value(context, branch, property)?;
+                Some(((*url).clone(), value.to_owned()))
+            })
+            .collect::<Option<_>>()?;
+        distinct(&values).then(|| Tagged {
+            property: property.clone(),
+            values,
+        })
+    })
+}
+
+/// The identifier of a named type, unique among all generated types.
+fn resolve_rust_ident(state: &mut CodegenState, url: &Url) -> String {
+    let context = state.context;
+    let preferred = match try_resolve_ident(context, state, url) {
+        Ok(ident) => return ident.clone(),
+        Err(ResolveIdentError::SimilarNameExists(preferred)) => preferred,
+        Err(ResolveIdentError::NotEnoughInfo) | Err(ResolveIdentError::Unresolvable) => {
+            "Schema".to_owned()
+        }
+    };
+    let ident = (2..)
+        .map(|i| format!("{preferred}{i}"))
+        .find(|ident| !state.taken.contains(ident))
+        .unwrap_or(preferred);
+    state.taken.insert(ident.clone());
+    state.idents.insert(url.clone(), ident.clone());
+    ident
+}
+
+/// A snake_case field identifier unique among `fields`, raw if it is a keyword.
+fn field_ident(name: &str, fields: &mut HashSet<String>) -> String {
+    let mut ident = String::new();
+    let mut boundary = false;
+    let mut previous_lower = false;
+    for c in name.chars() {
+        if !c.is_ascii_alphanumeric() {
+            boundary = true;
+            previous_lower = false;
+            continue;
+        }
+        if (boundary || c.is_ascii_uppercase() && previous_lower) && !ident.is_empty() {
+            ident.push('_');
+        }
+        boundary = false;
+        previous_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        ident.push(c.to_ascii_lowercase());
+    }
+    match ident.chars().next() {
+        None => ident.push_str("field"),
+        Some('0'..='9') => ident.insert(0, '_'),
+        Some(_) if PATH_KEYWORDS.contains(&ident.as_str()) => ident.push('_'),
+        Some(_) => {}
+    }
+    let base = ident.clone();
+    let mut i = 2;
+    while !fields.insert(ident.clone()) {
+        ident = format!("{base}_{i}");
+        i += 1;
+    }
+    match KEYWORDS.contains(&ident.as_str()) {
+        true => format!("r#{ident}"),
+        false => ident,
+    }
+}
+
+/// Unique variant identifiers for `names`, `fallback` for those without anything to name them by.
+/// Reserved names like `Self` get a number like names that are taken.
+fn variant_idents<'n>(names: impl IntoIterator<Item = &'n str>, fallback: &str) -> Vec<String> {
+    let mut variants = HashSet::new();
+    names
+        .into_iter()
+        .map(|name| {
+            let base = type_ident(name).unwrap_or_else(|| fallback.to_owned());
+            let mut ident = base.clone();
+            let mut i = 2;
+            while RESERVED.contains(&ident.as_str()) || !variants.insert(ident.clone()) {
+                ident = format!("{base}{i}");
+                i += 1;
+            }
+            ident
+        })
+        .collect()
 }
 
-fn resolve_rust_ident<'a>(
-    state: &'a mut CodegenState,
+fn write_notes(w: &mut impl Write, state: &mut CodegenState, indent: &str) -> io::Result<()> {
+    for note in state.notes.drain(..) {
+        writeln!(w, "{indent}// {note}")?;
+    }
+    Ok(())
+}
+
+fn is_deprecated(keywords: &Keywords) -> bool {
+    keywords
+        .deprecated
+        .as_ref()
+        .is_some_and(|deprecated| deprecated.0)
+}
+
+/// Rustdoc from the title, description and `examples` of a schema, then the `location` of it.
+fn write_docs(
+    w: &mut impl Write,
     keywords: &Keywords,
-    url: &Url,
-) -> &'a String {
-    if !state.idents.contains_key(url) {
-        let title = &keywords.title;
-        let ident = match title {
-            Some(title) => Case::Pascal.convert(&title.0),
-            _ => resolve_url_ident(url),
-        };
-        state.idents.insert(url.clone(), ident);
+    location: Option<&Url>,
+    indent: &str,
+) -> io::Result<()> {
+    let mut paragraphs = vec![];
+    paragraphs.extend(
+        keywords
+            .title
+            .as_ref()
+            .map(|title| title.0.trim().to_owned()),
+    );
+    paragraphs.extend(
+        keywords
+            .description
+            .as_ref()
+            .map(|description| description.0.trim().to_owned()),
+    );
+    if let Some(examples) = keywords
+        .examples
+        .as_ref()
+        .filter(|examples| !examples.0.is_empty())
+    {
+        let mut section = "# Examples".to_owned();
+        for example in &examples.0 {
+            let json = serde_json::to_string_pretty(example).unwrap_or_default();
+            section.push_str(&format!("\n\n```json\n{json}\n```"));
+        }
+        paragraphs.push(section);
     }
-    state.idents.get(url).unwrap()
+    paragraphs.extend(location.map(Url::to_string));
+
+    let mut fenced = false;
+    for (i, paragraph) in paragraphs.iter().filter(|p| !p.is_empty()).enumerate() {
+        if i > 0 {
+            writeln!(w, "{indent}///")?;
+        }
+        for line in paragraph.lines().map(str::trim_end) {
+            match line {
+                "" => writeln!(w, "{indent}///")?,
+                // rustdoc would test a code block without a language as rust
+                "```" if !fenced => writeln!(w, "{indent}/// ```text")?,
+                line => writeln!(w, "{indent}/// {line}")?,
+            }
+            if line.trim_start().starts_with("```") {
+                fenced = !fenced;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The rustdoc of a named type and `#[deprecated]` if it is.
+fn write_type_docs(w: &mut impl Write, state: &CodegenState, location: &Url) -> io::Result<()> {
+    let Some(ResolvedJsonSchema::Object(keywords)) = state.context.schemas.get(location) else {
+        return writeln!(w, "/// {location}");
+    };
+    write_docs(w, keywords, Some(location), "")?;
+    if is_deprecated(keywords) {
+        writeln!(w, "#[deprecated]")?;
+    }
+    Ok(())
+}
+
+/// Generated code allows what is deprecated so only the users of the types are warned,
+/// `implementation` is whether an impl of the type being written follows.
+fn write_allow_deprecated(
+    w: &mut impl Write,
+    state: &CodegenState,
+    implementation: bool,
+) -> io::Result<()> {
+    if state.uses_deprecated || implementation && state.deprecated {
+        writeln!(w, "#[allow(deprecated)]")?;
+    }
+    Ok(())
+}
+
+/// `derive` with `Default` added, for enums with a `#[default]` variant. The traits are wrapped
+/// like rustfmt does once the line is too long.
+fn derive_default(derive: &str, default: bool) -> String {
+    if !default {
+        return derive.to_owned();
+    }
+    let derive = derive.replacen("Debug, ", "Debug, Default, ", 1);
+    match derive
+        .strip_prefix("#[derive(")
+        .and_then(|d| d.strip_suffix(")]"))
+    {
+        Some(traits) if derive.len() >= 100 => format!("#[derive(\n    {traits},\n)]"),
+        _ => derive,
+    }
+}
+
+/// A raw string literal of `text`.
+fn raw_literal(text: &str) -> String {
+    let mut hashes = "#".to_owned();
+    while text.contains(&format!("\"{hashes}")) {
+        hashes.push('#');
+    }
+    format!("r{hashes}\"{text}\"{hashes}")
+}
+
+/// `value` as an expression of the rust type `ty`, what isn't a string, boolean or number is
+/// deserialized.
+fn value_expr(ty: &str, value: &Value) -> String {
+    let integer = value
+        .as_i64()
+        .map(i128::from)
+        .or(value.as_u64().map(i128::from));
+    match (ty, value) {
+        ("String", Value::String(string)) => format!("{string:?}.to_owned()"),
+        ("bool", Value::Bool(boolean)) => boolean.to_string(),
+        ("f64", Value::Number(number)) => format!("{:?}", number.as_f64().unwrap_or_default()),
+        ("f32", Value::Number(number))
+            if number
+                .as_f64()
+                .is_some_and(|number| number.abs() <= f64::from(f32::MAX)) =>
+        {
+            format!("{:?}", number.as_f64().unwrap_or_default())
+        }
+        (ty, Value::Number(number))
+            if integer_range(ty)
+                .zip(integer)
+                .is_some_and(|((min, max), integer)| (min..=max).contains(&integer)) =>
+        {
+            number.to_string()
+        }
+        _ => format!(
+            "::serde_json::from_str({}).unwrap()",
+            raw_literal(&value.to_string())
+        ),
+    }
+}
+
+/// `impl Default` of the type being written.
+fn write_default_impl(
+    w: &mut impl Write,
+    state: &CodegenState,
+    ident: &str,
+    expr: &str,
+) -> io::Result<()> {
+    writeln!(w)?;
+    write_allow_deprecated(w, state, true)?;
+    writeln!(w, "impl Default for {ident} {{")?;
+    writeln!(w, "    fn default() -> Self {{")?;
+    writeln!(w, "        {expr}")?;
+    writeln!(w, "    }}")?;
+    writeln!(w, "}}")?;
+    Ok(())
+}
+
+/// The `default` of the schema at `location`.
+fn schema_default<'c>(state: &CodegenState<'c>, location: &Url) -> Option<&'c Value> {
+    match state.context.schemas.get(location) {
+        Some(ResolvedJsonSchema::Object(keywords)) => keywords.default.as_ref().map(|k| &k.0),
+        _ => None,
+    }
+}
+
+/// Resolve the type of a schema as a string, see [`resolve_rust_type`].
+fn render_rust_type(state: &mut CodegenState, schema: &JsonSchema) -> io::Result<String> {
+    let mut buffer = vec![];
+    resolve_rust_type(&mut buffer, state, schema)?;
+    Ok(String::from_utf8_lossy(&buffer).into_owned())
 }
 
 /// Resolve a rust ident from previously generated idents. Some scalar types like Uuid and number are resolved idiomatically while
@@ -94,173 +563,471 @@ fn resolve_rust_type(
     state: &mut CodegenState,
     schema: &JsonSchema,
 ) -> std::io::Result<()> {
-    let url = get_url(schema);
+    let Some(url) = get_url(schema) else {
+        state.note("unresolved schema");
+        return write!(w, "::serde_json::Value");
+    };
+    state.use_type(url);
     if let Some(ident) = state.idents.get(url) {
-        write!(w, "{ident}")?;
-    } else {
-        let schema = state.context.schemas.get(url);
-        match schema {
-            Some(ResolvedJsonSchema::Bool(bool)) => {
-                if *bool {
-                    write!(w, "serde_json::Value")?
-                } else {
-                    write!(w, "()")?
-                }
+        return write!(w, "{}", state.named_type(url, ident));
+    }
+    let keywords = match state.context.schemas.get(url) {
+        Some(ResolvedJsonSchema::Bool(true)) => return write!(w, "::serde_json::Value"),
+        Some(ResolvedJsonSchema::Bool(false)) => return write!(w, "()"),
+        Some(ResolvedJsonSchema::Object(keywords)) => keywords,
+        None => {
+            state.note(format!("{url} was not compiled"));
+            return write!(w, "::serde_json::Value");
+        }
+    };
+    // resolve scalar types (i.e schemas with a single type that isn't object)
+    let Some(TypeKeyword::Single(type_)) = &keywords.type_ else {
+        return write!(w, "::serde_json::Value");
+    };
+    if !state.inline.insert(url.clone()) {
+        state.note(format!("{url} contains itself without a type of its own"));
+        return write!(w, "::serde_json::Value");
+    }
+    resolve_type_name(w, state, keywords, type_)?;
+    state.inline.remove(url);
+    Ok(())
+}
+
+/// The rust type of a scalar, `format` is a hint to the most likely candidate.
+fn scalar_type(keywords: &Keywords, type_: &str) -> Option<&'static str> {
+    let format = keywords
+        .format
+        .as_ref()
+        .map(|FormatKeyword(format)| format.as_str());
+    let ty = match (type_, format) {
+        ("string", Some("uuid")) => "uuid::Uuid",
+        ("string", Some("uri" | "url")) => "url::Url",
+        // other formats like date-time or email have no type of their own
+        ("string", _) => "String",
+        ("number", Some("f64" | "double")) => "f64",
+        ("number", _) => "f32",
+        ("integer", Some(format)) => match format {
+            "i8" => "i8",
+            "u8" => "u8",
+            "i16" => "i16",
+            "u16" => "u16",
+            "u32" => "u32",
+            "i64" | "int64" => "i64",
+            "u64" => "u64",
+            _ => "i32",
+        },
+        ("integer", None) => "i32",
+        ("boolean", _) => "bool",
+        ("null", _) => "()",
+        _ => return None,
+    };
+    Some(ty)
+}
+
+/// The rust type of one of the types of a schema.
+fn resolve_type_name(
+    w: &mut impl Write,
+    state: &mut CodegenState,
+    keywords: &Keywords,
+    type_: &str,
+) -> io::Result<()> {
+    if let Some(ty) = scalar_type(keywords, type_) {
+        return write!(w, "{ty}");
+    }
+    match type_ {
+        "array" => match keywords {
+            Keywords {
+                items: Some(items),
+                prefix_items: None,
+                ..
+            } => {
+                write!(w, "Vec<")?;
+                state.indirect += 1;
+                resolve_rust_type(w, state, &items.0)?;
+                state.indirect -= 1;
+                write!(w, ">")?;
             }
-            Some(ResolvedJsonSchema::Object(keywords)) => match keywords {
-                // resolve scalar types (i.e schemas with a single type that isn't object)
-                Keywords {
-                    type_: Some(TypeKeyword::Single(type_)),
-                    format,
-                    ..
-                } => match (type_.as_str(), format) {
-                    ("string", Some(FormatKeyword(format))) => match format.as_str() {
-                        "date-time" => write!(w, "String")?,
-                        "time" => write!(w, "String")?,
-                        "date" => write!(w, "String")?,
-                        "duration" => write!(w, "String")?,
-                        "email" => write!(w, "String")?,
-                        "idn-email" => write!(w, "String")?,
-                        "host-name" => write!(w, "String")?,
-                        "idn-host-name" => write!(w, "String")?,
-                        "ipv4" => write!(w, "String")?,
-                        "ipv6" => write!(w, "String")?,
-                        "uuid" => write!(w, "uuid::Uuid")?,
-                        "uri" | "url" => write!(w, "url::Url")?,
-                        "uri-reference" => write!(w, "String")?,
-                        "iri" => write!(w, "String")?,
-                        "iri-reference" => write!(w, "String")?,
-                        "uri-template" => write!(w, "String")?,
-                        "json-pointer" => write!(w, "String")?,
-                        "relative-json-pointer" => write!(w, "String")?,
-                        "regex" => write!(w, "String")?,
-                        _ => panic!("unsupported string format"),
-                    },
-                    ("string", None) => write!(w, "String")?,
-                    ("number", Some(FormatKeyword(format))) => match format.as_str() {
-                        "f32" => write!(w, "f32")?,
-                        "f64" => write!(w, "f64")?,
-                        _ => panic!("unsupported number format"),
-                    },
-                    ("number", None) => write!(w, "f32")?,
-                    ("integer", Some(FormatKeyword(format))) => match format.as_str() {
-                        "i8" => write!(w, "i8")?,
-                        "u8" => write!(w, "u8")?,
-                        "i16" => write!(w, "i16")?,
-                        "u16" => write!(w, "u16")?,
-                        "i32" => write!(w, "i32")?,
-                        "u32" => write!(w, "u32")?,
-                        _ => panic!("unsupported integer format"),
-                    },
-                    ("integer", None) => write!(w, "i32")?,
-                    ("object", _) => {
-                        let ident = resolve_rust_ident(state, keywords, url);
-                        write!(w, "{ident}")?;
+            Keywords {
+                items: None,
+                prefix_items: Some(prefix_items),
+                ..
+            } => {
+                write!(w, "(")?;
+                for (i, schema) in prefix_items.0.iter().enumerate() {
+                    if i > 0 {
+                        write!(w, ", ")?;
                     }
-                    ("array", _) => match keywords {
-                        Keywords {
-                            items: Some(items),
-                            prefix_items: None,
-                            ..
-                        } => {
-                            write!(w, "Vec<")?;
-                            resolve_rust_type(w, state, &items.0)?;
-                            write!(w, ">")?;
-                        }
-                        Keywords {
-                            items: None,
-                            prefix_items: Some(prefix_items),
-                            ..
-                        } => {
-                            write!(w, "(")?;
-                            let schemas = &mut prefix_items.0.iter().peekable();
-                            while let Some(schema) = schemas.next() {
-                                resolve_rust_type(w, state, schema)?;
-                                if schemas.peek().is_some() {
-                                    write!(w, ", ")?;
-                                }
-                            }
-                            write!(w, ")")?;
-                        }
-                        Keywords {
-                            items: Some(items),
-                            prefix_items: Some(prefix_items),
-                            ..
-                        } => {
-                            write!(w, "Vec<()>")?;
-                        }
-                        _ => {
-                            write!(w, "Vec<()>")?;
-                        }
-                    },
-                    _ => panic!("unexpected type"),
-                },
-                _ => {
-                    let ident = resolve_rust_ident(state, keywords, url);
-                    write!(w, "{ident}")?;
+                    resolve_rust_type(w, state, schema)?;
+                }
+                // a tuple of one needs a trailing comma
+                if prefix_items.0.len() == 1 {
+                    write!(w, ",")?;
                 }
-            },
-            None => {
-                write!(w, "()")?;
+                write!(w, ")")?;
             }
+            Keywords {
+                items: Some(_),
+                prefix_items: Some(_),
+                ..
+            } => {
+                state.note("prefixItems followed by items are not supported");
+                write!(w, "Vec<::serde_json::Value>")?;
+            }
+            _ => write!(w, "Vec<::serde_json::Value>")?,
+        },
+        // objects only get here as one of several types
+        "object" => write!(w, "::serde_json::Map<String, ::serde_json::Value>")?,
+        type_ => {
+            state.note(format!("unsupported type {type_:?}"));
+            write!(w, "::serde_json::Value")?;
         }
     }
     Ok(())
 }
 
-fn write_variants(w: &mut impl Write, state: &mut CodegenState) -> io::Result<()> {
-    Ok(())
-}
-
+/// Write the fields of a struct, returns whether any of them are flattened.
 fn write_fields(
     w: &mut impl Write,
     state: &mut CodegenState,
     keywords: &Keywords,
-) -> io::Result<()> {
+    location: &Url,
+) -> io::Result<bool> {
+    let mut fields = HashSet::new();
+    let mut flattened = false;
+    let mut initializers = vec![];
+    let mut complete = true;
+    let struct_ident = resolve_rust_ident(state, location);
     if let Some(props) = &keywords.props {
         let required = &vec![];
         let required = keywords.required.as_ref().map(|k| &k.0).unwrap_or(required);
-        for (property_name, property_schema) in &props.map {
+        let mut props: Vec<_> = props.map.iter().collect();
+        props.sort_by_key(|(name, _)| *name);
+        for (property_name, property_schema) in props {
+            // the tag is read and written by the enum this is a variant of
+            if state.tags.get(location) == Some(property_name) {
+                continue;
+            }
+            let ident = field_ident(property_name, &mut fields);
+            state.fields.push(ident.clone());
+            let ty = render_rust_type(state, property_schema)?;
             let url = get_url(property_schema);
-            // writeln!(w, "\t/// {url}")?;
-            write!(w, "\tpub {property_name}: ")?;
-            let is_required = required.iter().any(|p| p == property_name);
-            if !is_required {
-                write!(w, "Option<")?;
+            let property = match url.and_then(|url| state.context.schemas.get(url)) {
+                Some(ResolvedJsonSchema::Object(property)) => Some(property),
+                _ => None,
+            };
+            // the docs of a named type are on the type
+            let inline = url.is_some_and(|url| !state.idents.contains_key(url));
+            if let (true, Some(property)) = (inline, property) {
+                write_docs(w, property, None, "    ")?;
             }
-            resolve_rust_type(w, state, property_schema)?;
-            if !is_required {
-                write!(w, ">")?;
+            write_notes(w, state, "    ")?;
+            let name = ident.trim_start_matches("r#");
+            if property.is_some_and(is_deprecated) {
+                writeln!(w, "    #[deprecated]")?;
+                state.uses_deprecated = true;
             }
-            writeln!(w, ",")?;
-        }
-    }
-    if let Some(all_of) = &keywords.all_of {
-        for schema in &all_of.0 {
-            let url = get_url(schema);
-            if let Some(ResolvedJsonSchema::Object(keywords)) = state.context.schemas.get(url) {
-                let ident = resolve_rust_ident(state, keywords, url);
-                writeln!(w, "\t/// {url}")?;
-                writeln!(w, "\t#[serde(flatten)]")?;
-                write!(w, "\tpub {ident}: ")?;
-                resolve_rust_type(w, state, schema)?;
-                writeln!(w, ",")?;
+            if name != property_name {
+                writeln!(w, "    #[serde(rename = {property_name:?})]")?;
+            }
+            // a read only property is never deserialized, so it can't be required
+            let read_only = property
+                .and_then(|property| property.read_only.as_ref())
+                .is_some_and(|read_only| read_only.0);
+            let write_only = property
+                .and_then(|property| property.write_only.as_ref())
+                .is_some_and(|write_only| write_only.0);
+            if read_only {
+                writeln!(w, "    #[serde(skip_deserializing)]")?;
+            }
+            if write_only {
+                writeln!(w, "    #[serde(skip_serializing)]")?;
+            }
+            let required = required.contains(property_name) && !read_only;
+            let default = property.and_then(|property| property.default.as_ref());
+            if let Some(default) = default {
+                let expr = value_expr(&ty, &default.0);
+                state
+                    .defaults
+                    .push((name.to_owned(), ty.clone(), expr, !required));
+                initializers.push(format!("{ident}: Self::default_{name}(),"));
+            } else if required {
+                complete = false;
+            } else {
+                initializers.push(format!("{ident}: None,"));
+            }
+            let optional = !required && default.is_none();
+            match (required, default) {
+                (false, Some(_)) => {
+                    writeln!(
+                        w,
+                        "    #[serde(default = \"{struct_ident}::default_{name}\")]"
+                    )?;
+                    writeln!(w, "    pub {ident}: {ty},")?;
+                }
+                (false, None) => {
+                    if !write_only {
+                        writeln!(w, "    #[serde(skip_serializing_if = \"Option::is_none\")]")?;
+                    }
+                    writeln!(w, "    pub {ident}: Option<{ty}>,")?;
+                }
+                (true, _) => writeln!(w, "    pub {ident}: {ty},")?,
+            }
+            if state.validated {
+                field_checks(state, property_schema, &ident, optional);
             }
         }
     }
-    if let Some(any_of) = &keywords.any_of {
-        for schema in &any_of.0 {
-            let url = get_url(schema);
-            if let Some(ResolvedJsonSchema::Object(keywords)) = state.context.schemas.get(url) {
-                let ident = resolve_rust_ident(state, keywords, url);
-                // writeln!(w, "\t/// {url}")?;
-                writeln!(w, "\t#[serde(flatten)]")?;
-                write!(w, "\tpub {ident}: Option<")?;
-                resolve_rust_type(w, state, schema)?;
-                write!(w, ">")?;
-                writeln!(w, ",")?;
+    // members of allOf and anyOf that are structs are flattened into this one
+    let all_of = keywords.all_of.iter().flat_map(|all_of| &all_of.0);
+    let any_of = keywords.any_of.iter().flat_map(|any_of| &any_of.0);
+    let members = all_of.map(|schema| (schema, false));
+    for (schema, optional) in members.chain(any_of.map(|schema| (schema, true))) {
+        let Some(url) = get_url(schema) else { continue };
+        let Some(ident) = state.idents.get(url) else {
+            continue;
+        };
+        let Some(ResolvedJsonSchema::Object(member)) = state.context.schemas.get(url) else {
+            continue;
+        };
+        if !matches!(shape(member), Shape::Struct) {
+            continue;
+        }
+        let field = field_ident(ident, &mut fields);
+        state.fields.push(field.clone());
+        let ty = state.named_type(url, ident);
+        state.use_type(url);
+        writeln!(w, "    #[serde(flatten)]")?;
+        match optional {
+            true => {
+                writeln!(w, "    pub {field}: Option<{ty}>,")?;
+                initializers.push(format!("{field}: None,"));
+            }
+            false => {
+                writeln!(w, "    pub {field}: {ty},")?;
+                complete = false;
             }
         }
+        if state.validated {
+            field_checks(state, schema, &field, optional);
+        }
+        flattened = true;
+    }
+
+    // patternProperties and additionalProperties share one map of the remaining properties, it
+    // comes last so the flattened members take their properties first
+    let mut patterns: Vec<_> = keywords
+        .pat_props
+        .iter()
+        .flat_map(|pat_props| &pat_props.map)
+        .collect();
+    patterns.sort_by_key(|(pattern, _)| *pattern);
+    let mut values: Vec<&JsonSchema> = patterns.iter().map(|(_, schema)| *schema).collect();
+    if let Some(additional) = &keywords.additional_properties {
+        if !is_false(state.context, &additional.0) {
+            values.push(&additional.0);
+        }
+    }
+    values.dedup_by(|l, r| get_url(l).is_some_and(|l| get_url(r) == Some(l)));
+    state.initializers = complete.then_some(initializers);
+    let ty = match values.as_slice() {
+        [] => return Ok(flattened),
+        [schema] => {
+            state.indirect += 1;
+            let ty = render_rust_type(state, schema)?;
+            state.indirect -= 1;
+            ty
+        }
+        _ => {
+            state.note("properties of different schemas are not told apart");
+            "::serde_json::Value".to_owned()
+        }
+    };
+    for (pattern, _) in &patterns {
+        state.note(format!("properties matching {pattern:?}"));
+    }
+    write_notes(w, state, "    ")?;
+    let field = field_ident("extra", &mut fields);
+    state.fields.push(field.clone());
+    writeln!(w, "    #[serde(flatten)]")?;
+    writeln!(
+        w,
+        "    pub {field}: ::std::collections::BTreeMap<String, {ty}>,"
+    )?;
+    if let Some(initializers) = &mut state.initializers {
+        initializers.push(format!("{field}: ::std::collections::BTreeMap::new(),"));
+    }
+    if let (true, [schema]) = (state.validated, values.as_slice()) {
+        let mut lines = vec![];
+        value_checks(state, schema, "item", 12, &mut lines);
+        if !lines.is_empty() {
+            state
+                .checks
+                .push(format!("        for item in self.{field}.values() {{"));
+            state.checks.extend(lines);
+            state.checks.push("        }".to_owned());
+        }
+    }
+    Ok(true)
+}
+
+/// A schema that can't be represented is an alias of `serde_json::Value`.
+fn write_alias(
+    w: &mut impl Write,
+    state: &mut CodegenState,
+    location: &Url,
+    reason: &str,
+) -> io::Result<()> {
+    let ident = resolve_rust_ident(state, location);
+    write_type_docs(w, state, location)?;
+    writeln!(w, "// {reason}")?;
+    writeln!(w, "pub type {ident} = ::serde_json::Value;")?;
+    Ok(())
+}
+
+fn write_string_enum(
+    w: &mut impl Write,
+    state: &mut CodegenState,
+    values: &[&str],
+    location: &Url,
+) -> io::Result<()> {
+    let ident = resolve_rust_ident(state, location);
+    let default = schema_default(state, location)
+        .and_then(Value::as_str)
+        .and_then(|default| values.iter().position(|v| *v == default));
+    write_type_docs(w, state, location)?;
+    write_allow_deprecated(w, state, default.is_some())?;
+    writeln!(w, "{}", derive_default(DERIVE, default.is_some()))?;
+    writeln!(w, "pub enum {ident} {{")?;
+    let variants = variant_idents(values.iter().copied(), "Empty");
+    for (i, (value, variant)) in values.iter().zip(&variants).enumerate() {
+        if default == Some(i) {
+            writeln!(w, "    #[default]")?;
+        }
+        if variant != value {
+            writeln!(w, "    #[serde(rename = {value:?})]")?;
+        }
+        writeln!(w, "    {variant},")?;
+    }
+    writeln!(w, "}}")?;
+    Ok(())
+}
+
+/// An enum of integers is a fieldless enum with the values as discriminants.
+fn write_integer_enum(
+    w: &mut impl Write,
+    state: &mut CodegenState,
+    values: &[i64],
+    location: &Url,
+) -> io::Result<()> {
+    let ident = resolve_rust_ident(state, location);
+    let variant = |value: i64| match value.is_negative() {
+        true => format!("ValueMinus{}", value.unsigned_abs()),
+        false => format!("Value{value}"),
+    };
+    let default = schema_default(state, location)
+        .and_then(Value::as_i64)
+        .filter(|default| values.contains(default));
+    write_type_docs(w, state, location)?;
+    write_allow_deprecated(w, state, default.is_some())?;
+    writeln!(w, "{}", derive_default(DERIVE_REPR, default.is_some()))?;
+    writeln!(w, "#[repr(i64)]")?;
+    writeln!(w, "pub enum {ident} {{")?;
+    for value in values {
+        if default == Some(*value) {
+            writeln!(w, "    #[default]")?;
+        }
+        writeln!(w, "    {} = {value},", variant(*value))?;
+    }
+    writeln!(w, "}}")?;
+    Ok(())
+}
+
+/// A `oneOf` of structs told apart by a property is an internally tagged enum.
+fn write_tagged_enum(
+    w: &mut impl Write,
+    state: &mut CodegenState,
+    tagged: &Tagged,
+    location: &Url,
+) -> io::Result<()> {
+    let ident = resolve_rust_ident(state, location);
+    let mut types = vec![];
+    for (url, _) in &tagged.values {
+        let ident = resolve_rust_ident(state, url);
+        types.push(state.named_type(url, &ident));
+        state.use_type(url);
+    }
+    write_type_docs(w, state, location)?;
+    write_allow_deprecated(w, state, false)?;
+    writeln!(w, "{DERIVE}")?;
+    writeln!(w, "#[serde(tag = {:?})]", tagged.property)?;
+    writeln!(w, "pub enum {ident} {{")?;
+    let values = tagged.values.iter().map(|(_, value)| value.as_str());
+    let variants = variant_idents(values, "Variant");
+    for (((_, value), variant), ty) in tagged.values.iter().zip(variants).zip(types) {
+        if variant != *value {
+            writeln!(w, "    #[serde(rename = {value:?})]")?;
+        }
+        writeln!(w, "    {variant}({ty}),")?;
+    }
+    writeln!(w, "}}")?;
+    if let Some(default) = schema_default(state, location) {
+        write_default_impl(w, state, &ident, &value_expr(&ident, default))?;
+    }
+    Ok(())
+}
+
+/// Several types are an untagged enum with a variant for each, integers come before numbers so
+/// whole numbers keep their type.
+fn write_union(
+    w: &mut impl Write,
+    state: &mut CodegenState,
+    keywords: &Keywords,
+    types: &[String],
+    location: &Url,
+) -> io::Result<()> {
+    let mut variants = vec![];
+    for (name, _) in TYPES {
+        if !types.iter().any(|type_| type_ == name) {
+            continue;
+        }
+        let variant = type_ident(name).unwrap_or_default();
+        if name == "null" {
+            variants.push((variant, None, vec![]));
+            continue;
+        }
+        let mut ty = vec![];
+        resolve_type_name(&mut ty, state, keywords, name)?;
+        let ty = String::from_utf8_lossy(&ty).into_owned();
+        if state.validated && is_constrained_as(keywords, name) {
+            state.note(format!("the constraints of {name} values are not checked"));
+        }
+        variants.push((variant, Some(ty), std::mem::take(&mut state.notes)));
+    }
+    for type_ in types {
+        if !TYPES.iter().any(|(name, _)| name == type_) {
+            state.note(format!("unsupported type {type_:?}"));
+        }
+    }
+
+    let ident = resolve_rust_ident(state, location);
+    write_type_docs(w, state, location)?;
+    write_notes(w, state, "")?;
+    write_allow_deprecated(w, state, false)?;
+    writeln!(w, "{DERIVE}")?;
+    writeln!(w, "#[serde(untagged)]")?;
+    writeln!(w, "pub enum {ident} {{")?;
+    for (variant, ty, notes) in variants {
+        state.notes = notes;
+        write_notes(w, state, "    ")?;
+        match ty {
+            // a unit variant of an untagged enum is null
+            None => writeln!(w, "    {variant},")?,
+            Some(ty) => writeln!(w, "    {variant}({ty}),")?,
+        }
+    }
+    writeln!(w, "}}")?;
+    if let Some(default) = schema_default(state, location) {
+        write_default_impl(w, state, &ident, &value_expr(&ident, default))?;
     }
     Ok(())
 }
@@ -271,54 +1038,83 @@ fn write_enum(
     keywords: &Keywords,
     location: &Url,
 ) -> std::io::Result<()> {
-    let ident = resolve_rust_ident(state, keywords, location);
-    // writeln!(w, "/// {location}")?;
-    writeln!(w, "#[derive(::serde::Deserialize, ::serde::Serialize)]")?;
-    writeln!(w, "pub enum {} {{", ident)?;
-    let mut wrote_true_schema = false;
-    let mut wrote_false_schema = false;
     if let Some(enum_) = &keywords.enum_ {
-        for value in &enum_.0 {
-            match value {
-                Value::String(string) => {
-                    write!(w, "\t{string}")?;
-                }
-                _ => {}
+        let mut values: Vec<&Value> = vec![];
+        for value in enum_.0.value().into_iter().flatten() {
+            if !values.contains(&value) {
+                values.push(value);
             }
-            writeln!(w, ",")?;
-        }
-    }
-    if let Some(one_of) = &keywords.one_of {
-        for schema in &one_of.0 {
-            let url = get_url(schema);
-            let schema = state
-                .context
-                .schemas
-                .get(url)
-                .expect("schema to be resolved");
-            match schema {
-                ResolvedJsonSchema::Bool(bool) => {
-                    if *bool {
-                        wrote_true_schema = true
-                    } else {
-                        wrote_false_schema = true
-                    }
-                }
-                ResolvedJsonSchema::Object(keywords) => {
-                    let ident = resolve_rust_ident(state, keywords, url);
-                    write!(w, "\t{ident}({ident})")?;
-                }
-            }
-            writeln!(w, ",")?;
         }
-        if wrote_true_schema {
-            writeln!(w, "\tValue(serde_json::Value),")?;
+        if let Some(values) = values
+            .iter()
+            .map(|value| value.as_str())
+            .collect::<Option<Vec<_>>>()
+        {
+            return write_string_enum(w, state, &values, location);
+        }
+        if let Some(values) = values
+            .iter()
+            .map(|value| value.as_i64())
+            .collect::<Option<Vec<_>>>()
+        {
+            return write_integer_enum(w, state, &values, location);
         }
-        if wrote_false_schema {
-            writeln!(w, "\tEmpty,")?;
+        return write_alias(
+            w,
+            state,
+            location,
+            "only enums of strings or of integers are supported",
+        );
+    }
+    let Some(one_of) = &keywords.one_of else {
+        return match &keywords.type_ {
+            Some(TypeKeyword::Multiple(types)) => write_union(w, state, keywords, types, location),
+            _ => write_alias(w, state, location, "multiple types are not supported"),
+        };
+    };
+    if let Some(tagged) = state.tagged.remove(location) {
+        return write_tagged_enum(w, state, &tagged, location);
+    }
+
+    let mut variants = vec![];
+    for schema in &one_of.0 {
+        let url = get_url(schema);
+        match url.and_then(|url| state.context.schemas.get(url)) {
+            // no value is ever valid against false
+            Some(ResolvedJsonSchema::Bool(false)) => continue,
+            Some(ResolvedJsonSchema::Bool(true)) => {
+                variants.push(("Value".to_owned(), "::serde_json::Value".to_owned(), vec![]));
+            }
+            _ => {
+                let ty = render_rust_type(state, schema)?;
+                let name = match url.and_then(|url| state.idents.get(url)) {
+                    Some(ident) => ident.clone(),
+                    None if ty.ends_with("::serde_json::Value") => "Value".to_owned(),
+                    None => ty.clone(),
+                };
+                let notes = std::mem::take(&mut state.notes);
+                variants.push((name, ty, notes));
+            }
         }
     }
+
+    let ident = resolve_rust_ident(state, location);
+    write_type_docs(w, state, location)?;
+    write_allow_deprecated(w, state, false)?;
+    writeln!(w, "{DERIVE}")?;
+    // untagged, so deserializing may not have the exact semantics of oneOf
+    writeln!(w, "#[serde(untagged)]")?;
+    writeln!(w, "pub enum {ident} {{")?;
+    let names = variant_idents(variants.iter().map(|(name, _, _)| name.as_str()), "Variant");
+    for ((_, ty, notes), name) in variants.into_iter().zip(names) {
+        state.notes = notes;
+        write_notes(w, state, "    ")?;
+        writeln!(w, "    {name}({ty}),")?;
+    }
     writeln!(w, "}}")?;
+    if let Some(default) = schema_default(state, location) {
+        write_default_impl(w, state, &ident, &value_expr(&ident, default))?;
+    }
     Ok(())
 }
 
@@ -328,43 +1124,533 @@ fn write_struct(
     keywords: &Keywords,
     location: &Url,
 ) -> io::Result<()> {
-    let ident = resolve_rust_ident(state, keywords, location);
-    writeln!(w, "/// {}", location)?;
-    writeln!(w, "#[derive(::serde::Deserialize, ::serde::Serialize)]")?;
-    writeln!(w, "pub struct {} {{", &ident)?;
-    write_fields(w, state, keywords)?;
+    let ident = resolve_rust_ident(state, location);
+    let mut fields = vec![];
+    state.checks.clear();
+    state.fields.clear();
+    state.defaults.clear();
+    let flattened = write_fields(&mut fields, state, keywords, location)?;
+    // a struct with checks is deserialized as its fields and validated before it is returned
+    let unchecked = (state.validated && !state.checks.is_empty()).then(|| {
+        let preferred = format!("{ident}Unchecked");
+        let unchecked = std::iter::once(preferred.clone())
+            .chain((2..).map(|i| format!("{preferred}{i}")))
+            .find(|unchecked| !state.taken.contains(unchecked))
+            .unwrap_or(preferred);
+        state.taken.insert(unchecked.clone());
+        unchecked
+    });
+    let closed = keywords
+        .additional_properties
+        .as_ref()
+        .is_some_and(|additional| is_false(state.context, &additional.0));
+    write_type_docs(w, state, location)?;
+    if let Some(property) = state.tags.get(location) {
+        writeln!(
+            w,
+            "// {property:?} is the tag of the enum this is a variant of"
+        )?;
+    }
+    if closed && flattened {
+        // serde does not support deny_unknown_fields together with flatten
+        writeln!(
+            w,
+            "// additional properties are not denied next to flattened fields"
+        )?;
+    }
+    write_allow_deprecated(w, state, false)?;
+    writeln!(w, "{DERIVE}")?;
+    if closed && !flattened {
+        writeln!(w, "#[serde(deny_unknown_fields)]")?;
+    }
+    if let Some(unchecked) = &unchecked {
+        writeln!(w, "#[serde(try_from = {unchecked:?})]")?;
+    }
+    if fields.is_empty() {
+        writeln!(w, "pub struct {ident} {{}}")?;
+    } else {
+        writeln!(w, "pub struct {ident} {{")?;
+        w.write_all(&fields)?;
+        writeln!(w, "}}")?;
+    }
+
+    // the default of the schema comes before those of the fields
+    let default = match (&keywords.default, state.initializers.take()) {
+        (Some(default), _) => Some(value_expr(&ident, &default.0)),
+        (None, Some(initializers)) if !state.defaults.is_empty() => {
+            let mut expr = "Self {\n".to_owned();
+            for initializer in initializers {
+                expr.push_str(&format!("            {initializer}\n"));
+            }
+            Some(expr + "        }")
+        }
+        _ => None,
+    };
+    let mut functions = vec![];
+    for (name, ty, expr, serde) in state.defaults.drain(..) {
+        // the defaults of required fields are only used by `Default`
+        if serde || keywords.default.is_none() && default.is_some() {
+            functions.push(format!(
+                "    fn default_{name}() -> {ty} {{\n        {expr}\n    }}\n"
+            ));
+        }
+    }
+    if state.validated {
+        let mut validate = concat!(
+            "    /// Check the constraints that the types of the fields don't hold.\n",
+            "    pub fn validate(&self) -> Result<(), ValidationError> {\n",
+        )
+        .to_owned();
+        for line in state.checks.drain(..) {
+            validate.push_str(&format!("{line}\n"));
+        }
+        functions.push(validate + "        Ok(())\n    }\n");
+    }
+    if !functions.is_empty() {
+        writeln!(w)?;
+        write_allow_deprecated(w, state, true)?;
+        writeln!(w, "impl {ident} {{")?;
+        write!(w, "{}", functions.join("\n"))?;
+        writeln!(w, "}}")?;
+    }
+    if let Some(default) = default {
+        write_default_impl(w, state, &ident, &default)?;
+    }
+    if let Some(unchecked) = unchecked {
+        writeln!(w)?;
+        write_allow_deprecated(w, state, false)?;
+        writeln!(w, "#[derive(::serde::Deserialize)]")?;
+        if closed && !flattened {
+            writeln!(w, "#[serde(deny_unknown_fields)]")?;
+        }
+        writeln!(w, "struct {unchecked} {{")?;
+        w.write_all(&fields)?;
+        writeln!(w, "}}")?;
+        writeln!(w)?;
+        write_allow_deprecated(w, state, true)?;
+        writeln!(
+            w,
+            "impl ::std::convert::TryFrom<{unchecked}> for {ident} {{"
+        )?;
+        writeln!(w, "    type Error = ValidationError;")?;
+        writeln!(w)?;
+        writeln!(
+            w,
+            "    fn try_from(value: {unchecked}) -> Result<Self, ValidationError> {{"
+        )?;
+        writeln!(w, "        let value = Self {{")?;
+        for field in state.fields.drain(..) {
+            writeln!(w, "            {field}: value.{field},")?;
+        }
+        writeln!(w, "        }};")?;
+        writeln!(w, "        value.validate()?;")?;
+        writeln!(w, "        Ok(value)")?;
+        writeln!(w, "    }}")?;
+        writeln!(w, "}}")?;
+    }
+    Ok(())
+}
+
+/// Lines of `validate` that check a field of a struct.
+fn field_checks(state: &mut CodegenState, schema: &JsonSchema, field: &str, optional: bool) {
+    let mut lines = vec![];
+    match optional {
+        false => value_checks(state, schema, &format!("self.{field}"), 8, &mut lines),
+        true => value_checks(state, schema, "value", 12, &mut lines),
+    }
+    if lines.is_empty() {
+        return;
+    }
+    if optional {
+        state
+            .checks
+            .push(format!("        if let Some(value) = &self.{field} {{"));
+        lines.push("        }".to_owned());
+    }
+    state.checks.extend(lines);
+}
+
+/// Lines that check the value of `expr` against what its type doesn't hold, structs are validated
+/// and the number of items of arrays is counted.
+fn value_checks(
+    state: &CodegenState,
+    schema: &JsonSchema,
+    expr: &str,
+    indent: usize,
+    lines: &mut Vec<String>,
+) {
+    let Some(url) = get_url(schema) else { return };
+    let Some(ResolvedJsonSchema::Object(keywords)) = state.context.schemas.get(url) else {
+        return;
+    };
+    let pad = " ".repeat(indent);
+    if state.idents.contains_key(url) {
+        if matches!(shape(keywords), Shape::Struct) {
+            lines.push(format!("{pad}{expr}.validate()?;"));
+        }
+        return;
+    }
+    if !matches!(&keywords.type_, Some(TypeKeyword::Single(type_)) if type_ == "array") {
+        return;
+    }
+    let min_items = keywords.min_items.as_ref().and_then(|k| k.0.value());
+    let max_items = keywords.max_items.as_ref().and_then(|k| k.0.value());
+    if let Some(limit) = min_items.filter(|limit| limit.as_u64() != Some(0)) {
+        let condition = match limit.as_u64() {
+            Some(1) => format!("{expr}.is_empty()"),
+            _ => format!("{expr}.len() < {limit}"),
+        };
+        let message = format!("must have at least {limit} items");
+        push_check(lines, indent, &condition, url, &message);
+    }
+    if let Some(limit) = max_items {
+        let condition = format!("{expr}.len() > {limit}");
+        let message = format!("must have at most {limit} items");
+        push_check(lines, indent, &condition, url, &message);
+    }
+    if let (Some(items), None) = (&keywords.items, &keywords.prefix_items) {
+        let mut inner = vec![];
+        value_checks(state, &items.0, "item", indent + 4, &mut inner);
+        if !inner.is_empty() {
+            lines.push(format!("{pad}for item in {expr}.iter() {{"));
+            lines.extend(inner);
+            lines.push(format!("{pad}}}"));
+        }
+    }
+}
+
+/// Push `if {condition} { return Err(..) }` laid out the way rustfmt would.
+fn push_check(
+    lines: &mut Vec<String>,
+    indent: usize,
+    condition: &str,
+    location: &Url,
+    message: &str,
+) {
+    let pad = " ".repeat(indent);
+    let arguments = format!("{:?}, {message:?}", location.as_str());
+    lines.push(format!("{pad}if {condition} {{"));
+    let error = format!("{pad}    return Err(ValidationError::new({arguments}));");
+    if arguments.len() <= 60 && error.len() <= 100 {
+        lines.push(error);
+    } else {
+        lines.push(format!("{pad}    return Err(ValidationError::new("));
+        lines.push(format!("{pad}        {:?},", location.as_str()));
+        lines.push(format!("{pad}        {message:?},"));
+        lines.push(format!("{pad}    ));"));
+    }
+    lines.push(format!("{pad}}}"));
+}
+
+/// A string, integer or number with constraints its rust type doesn't hold.
+fn is_constrained(keywords: &Keywords) -> bool {
+    match &keywords.type_ {
+        Some(TypeKeyword::Single(type_)) => is_constrained_as(keywords, type_),
+        _ => false,
+    }
+}
+
+/// Whether values of `type_` have constraints its rust type doesn't hold.
+fn is_constrained_as(keywords: &Keywords, type_: &str) -> bool {
+    match (type_, scalar_type(keywords, type_)) {
+        ("string", Some("String")) => {
+            let email =
+                matches!(&keywords.format, Some(FormatKeyword(format)) if format == "email");
+            keywords.min_length.is_some()
+                || keywords.max_length.is_some()
+                || keywords.pat.is_some()
+                || email
+        }
+        ("integer" | "number", _) => {
+            keywords.min.is_some()
+                || keywords.max.is_some()
+                || keywords.excl_min.is_some()
+                || keywords.excl_max.is_some()
+                || keywords.multiple.is_some()
+        }
+        _ => false,
+    }
+}
+
+/// The range of a rust integer type.
+fn integer_range(ty: &str) -> Option<(i128, i128)> {
+    let range = match ty {
+        "i8" => (i8::MIN.into(), i8::MAX.into()),
+        "u8" => (0, u8::MAX.into()),
+        "i16" => (i16::MIN.into(), i16::MAX.into()),
+        "u16" => (0, u16::MAX.into()),
+        "i32" => (i32::MIN.into(), i32::MAX.into()),
+        "u32" => (0, u32::MAX.into()),
+        "i64" => (i64::MIN.into(), i64::MAX.into()),
+        "u64" => (0, u64::MAX.into()),
+        _ => return None,
+    };
+    Some(range)
+}
+
+/// `value` as a float that `limit` can be compared with.
+fn float_value(ty: &str, limit: f64) -> &'static str {
+    match ty {
+        "f64" => "value",
+        "f32" if limit.abs() <= f64::from(f32::MAX) => "value",
+        "f32" => "f64::from(value)",
+        _ => "(value as f64)",
+    }
+}
+
+/// The condition under which a `value` of `ty` fails `value {op} limit`, `None` if no value of
+/// the type does. Integers are compared as floats when the limit is out of their range.
+fn comparison(ty: &str, op: &str, limit: &Number) -> Option<String> {
+    let integer = limit
+        .as_i64()
+        .map(i128::from)
+        .or(limit.as_u64().map(i128::from));
+    if let (Some((min, max)), Some(limit)) = (integer_range(ty), integer) {
+        let (never, always) = match op {
+            "<" => (limit <= min, limit > max),
+            "<=" => (limit < min, limit >= max),
+            ">" => (limit >= max, limit < min),
+            _ => (limit > max, limit <= min),
+        };
+        if never {
+            return None;
+        }
+        // only the extreme itself fails, comparing against it otherwise is always true or false
+        if (op == "<=" && limit == min) || (op == ">=" && limit == max) {
+            return Some(format!("value == {limit}"));
+        }
+        if !always {
+            return Some(format!("value {op} {limit}"));
+        }
+    }
+    let limit = limit.as_f64()?;
+    Some(format!("{} {op} {limit:?}", float_value(ty, limit)))
+}
+
+/// Lines of `try_from` that check the constraints of a string.
+fn string_checks(state: &mut CodegenState, keywords: &Keywords, location: &Url) -> Vec<String> {
+    let mut lines = vec![];
+    let min_length = keywords.min_length.as_ref().and_then(|k| k.0.value());
+    if let Some(limit) = min_length.filter(|limit| limit.as_u64() != Some(0)) {
+        let condition = match limit.as_u64() {
+            Some(1) => "value.is_empty()".to_owned(),
+            _ => format!("value.chars().count() < {limit}"),
+        };
+        let message = format!("must have at least {limit} characters");
+        push_check(&mut lines, 8, &condition, location, &message);
+    }
+    if let Some(limit) = keywords.max_length.as_ref().and_then(|k| k.0.value()) {
+        let condition = format!("value.chars().count() > {limit}");
+        let message = format!("must have at most {limit} characters");
+        push_check(&mut lines, 8, &condition, location, &message);
+    }
+    if let Some(pattern) = keywords.pat.as_ref().and_then(|k| k.0.value()) {
+        match regex::Regex::new(pattern) {
+            Ok(_) => {
+                lines.push(format!("        const PATTERN: &str = {pattern:?};"));
+                lines.extend(COMPILE_PATTERN.map(str::to_owned));
+                let condition = "!regex.is_match(&value)";
+                push_check(&mut lines, 8, condition, location, "must match the pattern");
+            }
+            Err(_) => state.note(format!("the pattern {pattern:?} is not supported")),
+        }
+    }
+    if matches!(&keywords.format, Some(FormatKeyword(format)) if format == "email") {
+        lines.push(SPLIT_EMAIL.to_owned());
+        let condition = "local.is_empty() || !domain.contains('.')";
+        push_check(
+            &mut lines,
+            8,
+            condition,
+            location,
+            "must be an email address",
+        );
+    }
+    lines
+}
+
+/// Lines of `try_from` that check the constraints of an integer or number of type `ty`.
+fn number_checks(keywords: &Keywords, ty: &str, location: &Url) -> Vec<String> {
+    let mut lines = vec![];
+    // the comparison under which a value fails each limit
+    let limits = [
+        (keywords.min.as_ref().map(|k| &k.0), "<", "must be at least"),
+        (keywords.max.as_ref().map(|k| &k.0), ">", "must be at most"),
+        (
+            keywords.excl_min.as_ref().map(|k| &k.0),
+            "<=",
+            "must be greater than",
+        ),
+        (
+            keywords.excl_max.as_ref().map(|k| &k.0),
+            ">=",
+            "must be less than",
+        ),
+    ];
+    for (limit, op, message) in limits {
+        let Some(limit) = limit.and_then(Data::value) else {
+            continue;
+        };
+        if let Some(condition) = comparison(ty, op, limit) {
+            let message = format!("{message} {limit}");
+            push_check(&mut lines, 8, &condition, location, &message);
+        }
+    }
+    let Some(divisor) = keywords.multiple.as_ref().and_then(|k| k.0.value()) else {
+        return lines;
+    };
+    if !divisor.as_f64().is_some_and(|divisor| divisor > 0.0) {
+        lines.push(format!(
+            "        // multipleOf {divisor} is not positive, no value is checked against it"
+        ));
+        return lines;
+    }
+    let message = format!("must be a multiple of {divisor}");
+    match (integer_range(ty), divisor.as_i64().map(i128::from)) {
+        // every integer is a multiple of one
+        (Some(_), Some(1)) => {}
+        (Some((_, max)), Some(divisor)) if divisor <= max => {
+            let condition = format!("value % {divisor} != 0");
+            push_check(&mut lines, 8, &condition, location, &message);
+        }
+        _ => {
+            let divisor = divisor.as_f64().unwrap_or(1.0);
+            let value = float_value(ty, divisor);
+            let float = if value == "value" { ty } else { "f64" };
+            lines.push(format!("        let quotient = {value} / {divisor:?};"));
+            // allow for the rounding error of the division, like the validator does
+            let condition = format!(
+                "(quotient - quotient.round()).abs() > {float}::EPSILON * quotient.abs().max(1.0)"
+            );
+            push_check(&mut lines, 8, &condition, location, &message);
+        }
+    }
+    lines
+}
+
+/// A scalar with constraints is a newtype that can only be constructed, or deserialized, from
+/// values that meet them.
+fn write_newtype(
+    w: &mut impl Write,
+    state: &mut CodegenState,
+    keywords: &Keywords,
+    location: &Url,
+) -> io::Result<()> {
+    let Some(TypeKeyword::Single(type_)) = &keywords.type_ else {
+        return Ok(());
+    };
+    let ty = scalar_type(keywords, type_).unwrap_or("::serde_json::Value");
+    let ident = resolve_rust_ident(state, location);
+    let lines = match ty {
+        "String" => string_checks(state, keywords, location),
+        _ => number_checks(keywords, ty, location),
+    };
+
+    write_type_docs(w, state, location)?;
+    write_notes(w, state, "")?;
+    writeln!(w, "#[derive(Clone, Debug, ::serde::Serialize)]")?;
+    writeln!(w, "#[serde(transparent)]")?;
+    writeln!(w, "pub struct {ident}({ty});")?;
+    writeln!(w)?;
+    write_allow_deprecated(w, state, true)?;
+    writeln!(w, "impl {ident} {{")?;
+    writeln!(w, "    pub fn into_inner(self) -> {ty} {{")?;
+    writeln!(w, "        self.0")?;
+    writeln!(w, "    }}")?;
     writeln!(w, "}}")?;
+    writeln!(w)?;
+    write_allow_deprecated(w, state, true)?;
+    writeln!(w, "impl ::std::ops::Deref for {ident} {{")?;
+    writeln!(w, "    type Target = {ty};")?;
+    writeln!(w)?;
+    writeln!(w, "    fn deref(&self) -> &{ty} {{")?;
+    writeln!(w, "        &self.0")?;
+    writeln!(w, "    }}")?;
+    writeln!(w, "}}")?;
+    writeln!(w)?;
+    write_allow_deprecated(w, state, true)?;
+    writeln!(w, "impl ::std::convert::TryFrom<{ty}> for {ident} {{")?;
+    writeln!(w, "    type Error = ValidationError;")?;
+    writeln!(w)?;
+    writeln!(
+        w,
+        "    fn try_from(value: {ty}) -> Result<Self, ValidationError> {{"
+    )?;
+    for line in lines {
+        writeln!(w, "{line}")?;
+    }
+    writeln!(w, "        Ok(Self(value))")?;
+    writeln!(w, "    }}")?;
+    writeln!(w, "}}")?;
+    writeln!(w)?;
+    write_allow_deprecated(w, state, true)?;
+    writeln!(w, "impl<'de> ::serde::Deserialize<'de> for {ident} {{")?;
+    writeln!(
+        w,
+        "    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {{"
+    )?;
+    writeln!(
+        w,
+        "        let value = <{ty} as ::serde::Deserialize>::deserialize(deserializer)?;"
+    )?;
+    writeln!(
+        w,
+        "        Self::try_from(value).map_err(::serde::de::Error::custom)"
+    )?;
+    writeln!(w, "    }}")?;
+    writeln!(w, "}}")?;
+    if let Some(default) = schema_default(state, location) {
+        let expr = format!("Self::try_from({}).unwrap()", value_expr(ty, default));
+        write_default_impl(w, state, &ident, &expr)?;
+    }
     Ok(())
 }
 
+/// A constant string or integer is an enum of one variant.
 fn write_constant(
     w: &mut impl Write,
     state: &mut CodegenState,
     keywords: &Keywords,
     location: &Url,
 ) -> io::Result<()> {
-    let ident = resolve_rust_ident(state, keywords, location);
-    // writeln!(w, "/// {}", location)?;
-    writeln!(w, "#[derive(::serde::Deserialize, ::serde::Serialize)]")?;
-    writeln!(w, "pub struct {};", &ident)?;
-    Ok(())
+    let constant = keywords
+        .constant
+        .as_ref()
+        .and_then(|constant| constant.0.value());
+    if let Some(value) = constant.and_then(Value::as_i64) {
+        return write_integer_enum(w, state, &[value], location);
+    }
+    match constant {
+        Some(Value::String(value)) => write_string_enum(w, state, &[value], location),
+        _ => write_alias(
+            w,
+            state,
+            location,
+            "only constant strings or integers are supported",
+        ),
+    }
 }
 
 /// Boolean schemas are generated as serde_json::Value if true otherwise () if false.
 ///
 /// Scalar schemas (boolean, number, string) are generated as their scalar rust equivalents.
-/// A hint from format is used to determine the most likely candidate.
+/// A hint from format is used to determine the most likely candidate. In validated code scalars
+/// with constraints are newtypes that check them, see [`RustOptions`].
 ///
 /// Object schemas are created as structs where the the properties are field names: T
-/// (wrapped in option if not requried), the addiotnal properties fall in a add_props: HashMap<String, T>
-/// and pattern properties are a pat_props HashMap<HashMap<String, Vec<T>>> where T is the identifier to the
-/// type generated by the referenced schema.
+/// (wrapped in option if not requried), the additional and pattern properties fall in a flattened
+/// extra: BTreeMap<String, T> where T is the identifier to the type generated by the referenced schema.
+/// additionalProperties: false denies unknown fields. A field whose type contains the struct itself
+/// is boxed.
 ///
-/// If there are multiple types specified for a schema they are wrapped in an enum. If there is an anyOf/allOF
-/// definition each schema becomes an field (optional if anyOf) with a serde(flatten) tag. If there is an oneOF definition
-/// each schema will create or extend the current enum in the order they are defined.
+/// If there are multiple types specified for a schema they are wrapped in an untagged enum with a variant per
+/// type. If there is an anyOf/allOF definition each schema becomes an field (optional if anyOf) with a
+/// serde(flatten) tag. If there is an oneOF definition each schema will create or extend the current enum in the
+/// order they are defined. A oneOf of structs that a property tells apart, by an OpenAPI discriminator or a
+/// distinct const in each, is tagged by that property. An enum of strings or integers becomes a fieldless enum,
+/// integers are (de)serialized with serde_repr.
 ///
-/// Note that most enum variants will have serde(untagged) meaning that deserializing straight from the struct type
+/// Note that other enum variants will have serde(untagged) meaning that deserializing straight from the struct type
 /// may have invalid json schema semantics for oneOf.
 fn generate_rust_type(
     w: &mut impl Write,
@@ -372,80 +1658,136 @@ fn generate_rust_type(
     context: &Context,
     location: &Url,
 ) -> io::Result<()> {
-    let schema = context.schemas.get(location);
-    match schema {
-        Some(ResolvedJsonSchema::Object(keywords)) => {
-            // different combination of keywords present will decide whether or not the resulting type will be a struct or enum variant.
-            match keywords {
-                Keywords {
-                    constant: Some(_), ..
-                } => {
-                    write_constant(w, state, keywords, location)?;
-                }
-                // if a schema contains the enum keywords it must be represented as a rust enum
-                Keywords { enum_: Some(_), .. } => {
-                    write_enum(w, state, keywords, location)?;
-                }
-                // if a schema contains the oneOf keyword it must be represented as a rust enum
-                Keywords {
-                    one_of: Some(_), ..
-                } => {
-                    write_enum(w, state, keywords, location)?;
-                }
-                // if a schema contains multiple types it must be represented as a rust enum
-                Keywords {
-                    type_: Some(TypeKeyword::Multiple(_)),
-                    ..
-                } => {
-                    write_enum(w, state, keywords, location)?;
-                }
-                // if a schema contains a single type and does not include the oneOf keyword it must be represented as a struct
-                Keywords {
-                    type_: Some(TypeKeyword::Single(ty)),
-                    one_of: None,
-                    ..
-                } => {
-                    // struct
-                    match ty.as_str() {
-                        "object" => {
-                            write_struct(w, state, keywords, location)?;
-                        }
-                        _ => {
-                            // no other cases
-                        }
-                    }
-                }
-                // if a schema has no type or oneOf keywords but has properties it can be represented as a struct
-                Keywords {
-                    props: Some(_),
-                    one_of: None,
-                    type_: None,
-                    ..
-                } => {
-                    write_struct(w, state, keywords, location)?;
-                }
-                // if a schema has the allOf keyword but no oneOf keyword it can be represented as a struct
-                Keywords {
-                    all_of: Some(_),
-                    one_of: None,
-                    ..
-                } => {
-                    write_struct(w, state, keywords, location)?;
-                }
-                _ => {
-                    writeln!(w, "// {location}")?;
-                }
-            }
-        }
-        Some(ResolvedJsonSchema::Bool(_)) => {}
-        None => {}
+    let Some(ResolvedJsonSchema::Object(keywords)) = context.schemas.get(location) else {
+        return Ok(());
+    };
+    state.component = state.components.get(location).copied();
+    state.deprecated = is_deprecated(keywords);
+    state.uses_deprecated = false;
+    match state.shape(keywords) {
+        Shape::Struct => write_struct(w, state, keywords, location),
+        Shape::Enum => write_enum(w, state, keywords, location),
+        Shape::Constant => write_constant(w, state, keywords, location),
+        Shape::Newtype => write_newtype(w, state, keywords, location),
+        Shape::Inline => Ok(()),
     }
-    Ok(())
 }
 
+#[derive(Default)]
+pub struct RustOptions {
+    /// scalars with constraints like `minLength`, `pattern`, `minimum` or `multipleOf` become
+    /// newtypes that check them when constructed with `TryFrom` or deserialized, and structs get
+    /// a `validate` method for the number of items of their arrays that also runs when they are
+    /// deserialized. Constraints of a scalar that is one of several types are not checked, the
+    /// variant notes it. The code needs the `regex` crate for patterns.
+    pub validated: bool,
+}
+
+/// Write a module with a type for every schema in the context that needs one, ordered by url.
+/// Property names that are not identifiers are renamed and what can't be represented falls back
+/// to `serde_json::Value` with a comment saying why.
 pub fn generate_rust_code(w: &mut impl Write, context: &Context) -> io::Result<()> {
+    generate_rust_code_with(w, context, &RustOptions::default())
+}
+
+/// [`generate_rust_code`] with options.
+pub fn generate_rust_code_with(
+    w: &mut impl Write,
+    context: &Context,
+    options: &RustOptions,
+) -> io::Result<()> {
     let mut state = CodegenState::new(context);
-    for (url, _) in &context.schemas {
+    state.validated = options.validated;
+    if options.validated {
+        state.taken.insert("ValidationError".to_owned());
+    }
+    let graph = ReferenceGraph::new(context);
+    for (i, component) in graph.cycles().into_iter().enumerate() {
+        state
+            .components
+            .extend(component.into_iter().map(|url| (url, i)));
+    }
+    let mut urls: Vec<&Url> = context
+        .schemas
+        .iter()
+        .filter_map(|(url, schema)| match schema {
+            ResolvedJsonSchema::Object(keywords)
+                if !matches!(state.shape(keywords), Shape::Inline) =>
+            {
+                Some(url)
+            }
+            _ => None,
+        })
+        .collect();
+    urls.sort();
+    // every type is named before any is written, so names don't depend on the order of references
+    for url in &urls {
+        resolve_rust_ident(&mut state, url);
+    }
+    // a struct can only be a variant of enums that are tagged by the same property
+    for url in &urls {
+        let Some(ResolvedJsonSchema::Object(keywords)) = context.schemas.get(*url) else {
+            continue;
+        };
+        if keywords.enum_.is_some() || !matches!(shape(keywords), Shape::Enum) {
+            continue;
+        }
+        let Some(tagged) = discriminate(context, keywords, url) else {
+            continue;
+        };
+        let conflicts = tagged.values.iter().any(|(branch, _)| {
+            state
+                .tags
+                .get(branch)
+                .is_some_and(|property| *property != tagged.property)
+        });
+        if conflicts {
+            continue;
+        }
+        for (branch, _) in &tagged.values {
+            state.tags.insert(branch.clone(), tagged.property.clone());
+        }
+        state.tagged.insert((*url).clone(), tagged);
+    }
+    // the tag is only left out of structs that are used as nothing but variants, enums with a
+    // branch that is also used elsewhere are untagged, which may in turn use other branches
+    loop {
+        let shared: Vec<Url> = state
+            .tagged
+            .iter()
+            .filter(|(_, tagged)| {
+                tagged.values.iter().any(|(branch, _)| {
+                    graph.incoming(branch).any(|edge| {
+                        let location = edge.keyword_location.as_str();
+                        let variant = location.starts_with("/oneOf/")
+                            && state.tagged.contains_key(&edge.from);
+                        let definition = edge.kind == EdgeKind::Subschema
+                            && (location.starts_with("/$defs/")
+                                || location.starts_with("/definitions/"));
+                        !variant && !definition
+                    })
+                })
+            })
+            .map(|(url, _)| url.clone())
+            .collect();
+        if shared.is_empty() {
+            break;
+        }
+        for url in shared {
+            if let Some(tagged) = state.tagged.remove(&url) {
+                for (branch, _) in tagged.values {
+                    state.tags.remove(&branch);
+                }
+            }
+        }
+    }
+    writeln!(w, "// Generated from JSON schemas, do not edit by hand.")?;
+    if options.validated {
+        writeln!(w)?;
+        write!(w, "{VALIDATION_ERROR}")?;
+    }
+    for url in urls {
+        writeln!(w)?;
         generate_rust_type(w, &mut state, context, url)?;
     }
     Ok(())
@@ -1,40 +1,126 @@
 use super::{CodegenState, ResolvedJsonSchema};
-use crate::{context::Context, keywords::Keywords};
+use crate::{
+    context::Context,
+    keywords::Keywords,
+    migrate::{SUBSCHEMAS, SUBSCHEMA_MAPS},
+    pointer::AbsoluteJsonPointer,
+};
+use case_utils::Case;
 use url::Url;
 
+/// Names of the prelude and of the paths the generated code uses, a type with one of these names
+/// would shadow them.
+pub(super) const RESERVED: [&str; 11] = [
+    "Self", "Box", "Option", "Some", "None", "Result", "Ok", "Err", "String", "Vec", "Default",
+];
+
 pub(super) enum ResolveIdentError {
+    /// the url is not a compiled schema.
     Unresolvable,
+    /// neither the title nor the url of the schema have anything to name it after.
     NotEnoughInfo,
-    SimilarNameExists,
+    /// every name the schema could have is taken, holds the preferred one.
+    SimilarNameExists(String),
 }
 
+/// Name the schema at `url` after its title, the names of the properties or definitions it is
+/// nested in, from the innermost outwards, or the file it is in. The first name that is not taken
+/// is used.
 pub(super) fn try_resolve_ident<'a>(
     context: &Context,
     state: &'a mut CodegenState,
     url: &Url,
 ) -> Result<&'a String, ResolveIdentError> {
-    if let Some(schema) = context.schemas.get(url) {
-        match (url.fragment(), schema) {
-            (
-                Some(fragment),
-                ResolvedJsonSchema::Object(Keywords {
-                    title: Some(title), ..
-                }),
-            ) => {}
-            (
-                None,
-                ResolvedJsonSchema::Object(Keywords {
-                    title: Some(title), ..
-                }),
-            ) => {}
+    let Some(schema) = context.schemas.get(url) else {
+        return Err(ResolveIdentError::Unresolvable);
+    };
+    if !state.idents.contains_key(url) {
+        let mut candidates = vec![];
+        if let ResolvedJsonSchema::Object(Keywords {
+            title: Some(title), ..
+        }) = schema
+        {
+            candidates.extend(type_ident(&title.0));
+        }
+        let names = pointer_names(url);
+        for i in (0..names.len()).rev() {
+            candidates.push(names[i..].concat());
+        }
+        if let Some(file) = type_ident(file_stem(url)) {
+            candidates.push(file + &names.concat());
+        }
+
+        let Some(preferred) = candidates.first().cloned() else {
+            return Err(ResolveIdentError::NotEnoughInfo);
+        };
+        let ident = candidates
+            .into_iter()
+            .find(|ident| !state.taken.contains(ident) && !RESERVED.contains(&ident.as_str()))
+            .ok_or(ResolveIdentError::SimilarNameExists(preferred))?;
+        state.taken.insert(ident.clone());
+        state.idents.insert(url.clone(), ident);
+    }
+    Ok(&state.idents[url])
+}
+
+/// A PascalCase type identifier, `None` if nothing of `name` is left.
+pub(super) fn type_ident(name: &str) -> Option<String> {
+    let ident: String = Case::Pascal
+        .convert(name)
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect();
+    match ident.chars().next()? {
+        '0'..='9' => Some(format!("_{ident}")),
+        _ => Some(ident),
+    }
+}
+
+/// The type identifiers of the properties and definitions in the fragment of `url`, an item of an
+/// array is named after the array with an `Item` suffix.
+fn pointer_names(url: &Url) -> Vec<String> {
+    let Some(pointer) = url
+        .fragment()
+        .and_then(|fragment| AbsoluteJsonPointer::from_fragment(fragment).ok())
+    else {
+        return vec![];
+    };
+    let mut names: Vec<String> = vec![];
+    let mut segments = pointer.segments().iter();
+    while let Some(segment) = segments.next() {
+        match segment.as_str() {
+            "items" | "prefixItems" => {
+                if let Some(name) = names.last_mut() {
+                    name.push_str("Item");
+                }
+                if segment == "prefixItems" {
+                    segments.next();
+                }
+            }
+            keyword if SUBSCHEMA_MAPS.contains(&keyword) => {
+                names.extend(segments.next().and_then(|name| type_ident(name)));
+            }
+            keyword if SUBSCHEMAS.contains(&keyword) => {
+                segments.next();
+            }
             _ => {}
         }
-        state
-            .idents
-            .get(url)
-            .map(Ok)
-            .unwrap_or(Err(ResolveIdentError::Unresolvable))
-    } else {
-        Err(ResolveIdentError::Unresolvable)
     }
+    names
+}
+
+/// The name OpenAPI gives the schema at `url`, the last segment of its pointer or else the file
+/// it is in.
+pub(super) fn schema_name(url: &Url) -> String {
+    url.fragment()
+        .and_then(|fragment| AbsoluteJsonPointer::from_fragment(fragment).ok())
+        .and_then(|pointer| pointer.segments().last().cloned())
+        .unwrap_or_else(|| file_stem(url).to_owned())
+}
+
+fn file_stem(url: &Url) -> &str {
+    let path = std::path::Path::new(url.path());
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default()
 }
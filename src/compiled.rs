@@ -0,0 +1,99 @@
+//! Compiled schemas that can be shared between threads.
+
+use crate::{
+    context::Context,
+    serialize,
+    stream::{self, StreamError},
+    tree::SchemaTree,
+    validator::{ValidationError, Validator},
+    value::Instance,
+};
+use std::sync::Arc;
+use url::Url;
+
+/// An immutable handle to a schema of a compiled [`Context`]. Cloning is cheap as the context and
+/// its evaluation tree are shared, any number of threads can validate against the same handle
+/// without locking.
+#[derive(Clone)]
+pub struct CompiledSchema {
+    context: Arc<Context>,
+    tree: Arc<SchemaTree>,
+    location: Url,
+    node: usize,
+}
+
+impl CompiledSchema {
+    /// Lower the schemas of the context, returns `None` if no schema was compiled at `location`.
+    pub fn new(context: impl Into<Arc<Context>>, location: Url) -> Option<Self> {
+        let context = context.into();
+        let tree = Arc::new(SchemaTree::new(&context));
+        let node = tree.node_index(&location)?;
+        Some(Self {
+            context,
+            tree,
+            location,
+            node,
+        })
+    }
+
+    pub fn location(&self) -> &Url {
+        &self.location
+    }
+
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    pub fn tree(&self) -> &SchemaTree {
+        &self.tree
+    }
+
+    /// The index of the schema in the evaluation tree.
+    pub fn node(&self) -> usize {
+        self.node
+    }
+
+    /// Another schema of the same context, the context and tree are shared with this handle.
+    pub fn schema(&self, location: Url) -> Option<Self> {
+        let node = self.tree.node_index(&location)?;
+        Some(Self {
+            context: self.context.clone(),
+            tree: self.tree.clone(),
+            location,
+            node,
+        })
+    }
+
+    /// Validate a `&serde_json::Value` or a node of a [`ValueArena`](crate::value::ValueArena).
+    pub fn validate<'v>(&self, instance: impl Instance<'v>) -> Result<(), Vec<ValidationError>> {
+        let mut validator = Validator::new(&self.tree);
+        if validator.validate(self.node, instance) {
+            Ok(())
+        } else {
+            Err(validator.errors)
+        }
+    }
+
+    /// Whether the instance is valid, without building the list of errors.
+    pub fn is_valid<'v>(&self, instance: impl Instance<'v>) -> bool {
+        Validator::short_circuit(&self.tree).validate(self.node, instance)
+    }
+
+    /// Validate the value produced by a deserializer while it is being deserialized, without
+    /// building a [`serde_json::Value`] for it.
+    pub fn validate_deserializer<'de, D: serde::Deserializer<'de>>(
+        &self,
+        deserializer: D,
+    ) -> Result<(), StreamError<D::Error>> {
+        stream::validate(&self.tree, self.node, deserializer)
+    }
+
+    /// Validate a typed value through its [`serde::Serialize`] implementation, the value is
+    /// checked as the json `serde_json` would serialize it to, without building that json.
+    pub fn validate_serialize<T: serde::Serialize + ?Sized>(
+        &self,
+        value: &T,
+    ) -> Result<(), StreamError<serde_json::Error>> {
+        serialize::validate(&self.tree, self.node, value)
+    }
+}
@@ -52,10 +52,22 @@ impl<'a> Compiler<'a> {
     pub fn compile_rel_key(&mut self, f: impl FnOnce(&mut String), schema: &mut JsonSchema) {
         // store len of string to truncate value back to current location
         let truncate = self.json_pointer.len();
+        let fragment = self.location.fragment().map(str::to_owned);
         (f)(&mut self.json_pointer);
         self.location.set_fragment(Some(&self.json_pointer));
         self.compile(schema);
         self.json_pointer.truncate(truncate);
+        self.location.set_fragment(fragment.as_deref());
+    }
+    /// Resolve a pointer found in the current schema against the current location.
+    pub fn resolve_pointer(&self, pointer: &JsonPointer) -> Result<Url, url::ParseError> {
+        pointer.to_absolute(self.location)
+    }
+    /// Queue a url to be compiled once the current schema has been processed.
+    pub fn defer(&mut self, url: Url) {
+        if !self.context.schemas.contains_key(&url) {
+            self.refs.push(Reference::Ref(url));
+        }
     }
     pub fn compile(&mut self, schema: &mut JsonSchema) {
         match {
@@ -112,6 +124,10 @@ impl<'a> Compiler<'a> {
 pub struct Context {
     client: Client,
     pub schemas: HashMap<Url, ResolvedJsonSchema>,
+    /// follow `$data` references of value keywords, e.g. `"minimum": { "$data": "1/start" }`.
+    /// Off by default, `const` then compares against the reference object itself and the other
+    /// keywords always fail.
+    pub data_references: bool,
 }
 
 impl Context {
@@ -132,7 +148,11 @@ impl Context {
     pub fn new() -> Self {
         let client = Client::new();
         let schemas = HashMap::new();
-        Self { schemas, client }
+        Self {
+            schemas,
+            client,
+            data_references: false,
+        }
     }
 
     /// Resolve a series of references in parallel.
@@ -154,7 +174,7 @@ impl Context {
     pub async fn compile_schema(&mut self, schema: &mut JsonSchema, mut location: Url) {
         let mut refs = vec![];
         Compiler {
-            json_pointer: String::new(),
+            json_pointer: location.fragment().unwrap_or_default().to_owned(),
             location: &mut location,
             refs: &mut refs,
             context: self,
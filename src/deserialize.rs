@@ -0,0 +1,100 @@
+//! Deserialization of typed values that are validated against a schema on the way.
+
+use crate::{
+    compiled::CompiledSchema,
+    patch::Patcher,
+    validator::{escape, ValidationError},
+};
+use serde::de::DeserializeOwned;
+use serde_path_to_error::Segment;
+use std::fmt::{self, Write};
+
+#[derive(Debug)]
+pub enum DeserializeError {
+    /// the input is not valid json.
+    Syntax(serde_json::Error),
+    /// the patched document does not match the schema.
+    Invalid(Vec<ValidationError>),
+    /// the document matches the schema but could not be deserialized into the type.
+    Type {
+        /// json pointer to the value that failed to deserialize.
+        instance_location: String,
+        error: serde_json::Error,
+    },
+}
+
+impl DeserializeError {
+    /// The json pointers of the values that caused the error, empty for syntax errors.
+    pub fn instance_locations(&self) -> Vec<&str> {
+        match self {
+            DeserializeError::Syntax(_) => vec![],
+            DeserializeError::Invalid(errors) => errors
+                .iter()
+                .map(|error| error.instance_location.as_str())
+                .collect(),
+            DeserializeError::Type {
+                instance_location, ..
+            } => vec![instance_location],
+        }
+    }
+}
+
+impl fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeserializeError::Syntax(error) => write!(f, "{error}"),
+            DeserializeError::Invalid(errors) => {
+                write!(f, "document is invalid")?;
+                for error in errors {
+                    write!(f, "\n{error}")?;
+                }
+                Ok(())
+            }
+            DeserializeError::Type {
+                instance_location,
+                error,
+            } => write!(f, "{instance_location}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for DeserializeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DeserializeError::Syntax(error) | DeserializeError::Type { error, .. } => Some(error),
+            DeserializeError::Invalid(_) => None,
+        }
+    }
+}
+
+/// Parse `json`, apply the patch phase of the schema (defaults and type coercion), validate the
+/// patched document and deserialize it into `T`. The input is only parsed once.
+pub fn from_str_validated<T: DeserializeOwned>(
+    schema: &CompiledSchema,
+    json: &str,
+) -> Result<T, DeserializeError> {
+    let mut value: serde_json::Value =
+        serde_json::from_str(json).map_err(DeserializeError::Syntax)?;
+    Patcher::new(schema.context()).patch(schema.location(), &mut value);
+    schema.validate(&value).map_err(DeserializeError::Invalid)?;
+    serde_path_to_error::deserialize(value).map_err(|error| {
+        let mut instance_location = String::new();
+        for segment in error.path().iter() {
+            match segment {
+                Segment::Seq { index } => {
+                    let _ = write!(instance_location, "/{index}");
+                }
+                // externally tagged variants are keys of an object in json
+                Segment::Map { key: name } | Segment::Enum { variant: name } => {
+                    instance_location.push('/');
+                    instance_location.push_str(&escape(name));
+                }
+                Segment::Unknown => {}
+            }
+        }
+        DeserializeError::Type {
+            instance_location,
+            error: error.into_inner(),
+        }
+    })
+}
@@ -0,0 +1,989 @@
+//! Compare two versions of a compiled schema and classify the changes between them.
+//!
+//! Both schemas are walked in parallel through their subschemas, following `$ref` in their own
+//! [`Context`]. A change that rejects values the old schema accepted breaks producers, which may
+//! still send them, a change that accepts values the old schema rejected breaks consumers, which
+//! may not expect them. Changes inside `oneOf` and `if` can do both, they are breaking.
+
+use crate::{
+    context::Context,
+    keywords::{Data, Keywords},
+    schema::{JsonSchema, ResolvedJsonSchema},
+    tree::TYPES,
+    validator::{compare_numbers, escape, is_multiple_of},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Number, Value};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
+use url::Url;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffReport {
+    pub changes: Vec<Change>,
+}
+
+impl DiffReport {
+    /// Whether a value accepted by the old schema may be rejected by the new one.
+    pub fn breaks_producers(&self) -> bool {
+        self.changes.iter().any(|change| {
+            matches!(
+                change.compatibility,
+                Compatibility::BreaksProducers | Compatibility::Breaking
+            )
+        })
+    }
+
+    /// Whether a value rejected by the old schema may be accepted by the new one.
+    pub fn breaks_consumers(&self) -> bool {
+        self.changes.iter().any(|change| {
+            matches!(
+                change.compatibility,
+                Compatibility::BreaksConsumers | Compatibility::Breaking
+            )
+        })
+    }
+
+    pub fn is_compatible(&self) -> bool {
+        !self.breaks_producers() && !self.breaks_consumers()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Change {
+    /// json pointer to the changed keyword, through the keywords of the new schema, e.g.
+    /// `/properties/name/maxLength`.
+    pub location: String,
+    pub kind: ChangeKind,
+    pub compatibility: Compatibility,
+    /// the old value of the keyword, or the entry of it that was removed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old: Option<Value>,
+    /// the new value of the keyword, or the entry of it that was added.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new: Option<Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    /// a bound or limit now allows fewer values.
+    Tightened,
+    /// a bound or limit now allows more values.
+    Loosened,
+    /// a value changed in a way that allows some new values and rejects some old ones.
+    Changed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Compatibility {
+    Compatible,
+    /// values valid under the old schema may be invalid under the new one.
+    BreaksProducers,
+    /// values invalid under the old schema may be valid under the new one.
+    BreaksConsumers,
+    /// both producers and consumers may break.
+    Breaking,
+}
+
+/// Diff the schema at `old_url` of `old` with the schema at `new_url` of `new`, both contexts
+/// need to be compiled.
+pub fn diff(old: &Context, old_url: &Url, new: &Context, new_url: &Url) -> DiffReport {
+    let old_schema = JsonSchema::Resolved(old_url.clone());
+    let new_schema = JsonSchema::Resolved(new_url.clone());
+    let empty = Keywords::default();
+    let mut differ = Differ {
+        old,
+        new,
+        empty: &empty,
+        visited: HashSet::new(),
+        either_way: false,
+        changes: vec![],
+    };
+    let mut location = String::new();
+    differ.walk(Some(&old_schema), Some(&new_schema), &mut location, false);
+    DiffReport {
+        changes: differ.changes,
+    }
+}
+
+/// How a change affects the set of accepted values.
+#[derive(Clone, Copy)]
+enum Effect {
+    None,
+    Tightens,
+    Loosens,
+    Both,
+}
+
+enum View<'c> {
+    Bool(bool),
+    Keywords(&'c Keywords),
+}
+
+struct Differ<'c> {
+    old: &'c Context,
+    new: &'c Context,
+    /// the keywords of a schema that is `true`.
+    empty: &'c Keywords,
+    /// pairs of schemas already compared, whether they were compared under a `not` and
+    /// `either_way`.
+    visited: HashSet<(Url, Url, bool, bool)>,
+    /// inside a `oneOf` branch or an `if` a change of the accepted values can move values in
+    /// both directions, e.g. a loosened branch makes values match two branches of `oneOf`.
+    either_way: bool,
+    changes: Vec<Change>,
+}
+
+impl<'c> Differ<'c> {
+    fn view(context: &'c Context, schema: Option<&'c JsonSchema>) -> View<'c> {
+        match schema {
+            None => View::Bool(true),
+            Some(JsonSchema::Bool(bool)) => View::Bool(*bool),
+            Some(JsonSchema::Object(keywords)) => View::Keywords(keywords),
+            Some(JsonSchema::Resolved(url)) => match context.schema(url) {
+                Some(ResolvedJsonSchema::Bool(bool)) => View::Bool(*bool),
+                Some(ResolvedJsonSchema::Object(keywords)) => View::Keywords(keywords),
+                None => View::Bool(false),
+            },
+            Some(_) => View::Bool(false),
+        }
+    }
+
+    fn record(
+        &mut self,
+        location: &str,
+        kind: ChangeKind,
+        effect: Effect,
+        old: Option<Value>,
+        new: Option<Value>,
+        negated: bool,
+    ) {
+        let compatibility = match (effect, negated) {
+            (Effect::None, _) => Compatibility::Compatible,
+            _ if self.either_way => Compatibility::Breaking,
+            (Effect::Both, _) => Compatibility::Breaking,
+            (Effect::Tightens, false) | (Effect::Loosens, true) => Compatibility::BreaksProducers,
+            (Effect::Loosens, false) | (Effect::Tightens, true) => Compatibility::BreaksConsumers,
+        };
+        self.changes.push(Change {
+            location: location.to_owned(),
+            kind,
+            compatibility,
+            old,
+            new,
+        });
+    }
+
+    /// Compare two subschemas, `None` is an absent subschema which allows everything. Under a
+    /// `not` a tightened schema loosens the schema around it, `negated` flips the effects.
+    fn walk(
+        &mut self,
+        old: Option<&'c JsonSchema>,
+        new: Option<&'c JsonSchema>,
+        location: &mut String,
+        negated: bool,
+    ) {
+        if let (Some(JsonSchema::Resolved(old)), Some(JsonSchema::Resolved(new))) = (old, new) {
+            let key = (old.clone(), new.clone(), negated, self.either_way);
+            if !self.visited.insert(key) {
+                return;
+            }
+        }
+        let (old, new) = match (Self::view(self.old, old), Self::view(self.new, new)) {
+            (View::Bool(false), View::Bool(false)) => return,
+            (View::Bool(false), _) => {
+                let old = Some(Value::Bool(false));
+                self.record(
+                    location,
+                    ChangeKind::Loosened,
+                    Effect::Loosens,
+                    old,
+                    None,
+                    negated,
+                );
+                return;
+            }
+            (_, View::Bool(false)) => {
+                let new = Some(Value::Bool(false));
+                self.record(
+                    location,
+                    ChangeKind::Tightened,
+                    Effect::Tightens,
+                    None,
+                    new,
+                    negated,
+                );
+                return;
+            }
+            (View::Bool(true), View::Bool(true)) => return,
+            (View::Bool(true), View::Keywords(new)) => (self.empty, new),
+            (View::Keywords(old), View::Bool(true)) => (old, self.empty),
+            (View::Keywords(old), View::Keywords(new)) => (old, new),
+        };
+        self.keywords(old, new, location, negated);
+    }
+
+    fn keywords(
+        &mut self,
+        old: &'c Keywords,
+        new: &'c Keywords,
+        location: &mut String,
+        negated: bool,
+    ) {
+        let truncate = location.len();
+        self.types(old, new, location, negated);
+        self.values(old, new, location, negated);
+
+        // `minContains` and `maxContains` do not take `$data` references
+        let contains = |keyword: Option<&Number>| keyword.cloned().map(Data::Value);
+        let old_min_contains = contains(old.min_contains.as_ref().map(|k| &k.0));
+        let new_min_contains = contains(new.min_contains.as_ref().map(|k| &k.0));
+        let old_max_contains = contains(old.max_contains.as_ref().map(|k| &k.0));
+        let new_max_contains = contains(new.max_contains.as_ref().map(|k| &k.0));
+        let bounds = [
+            (
+                "minimum",
+                old.min.as_ref().map(|k| &k.0),
+                new.min.as_ref().map(|k| &k.0),
+                true,
+            ),
+            (
+                "exclusiveMinimum",
+                old.excl_min.as_ref().map(|k| &k.0),
+                new.excl_min.as_ref().map(|k| &k.0),
+                true,
+            ),
+            (
+                "maximum",
+                old.max.as_ref().map(|k| &k.0),
+                new.max.as_ref().map(|k| &k.0),
+                false,
+            ),
+            (
+                "exclusiveMaximum",
+                old.excl_max.as_ref().map(|k| &k.0),
+                new.excl_max.as_ref().map(|k| &k.0),
+                false,
+            ),
+            (
+                "minLength",
+                old.min_length.as_ref().map(|k| &k.0),
+                new.min_length.as_ref().map(|k| &k.0),
+                true,
+            ),
+            (
+                "maxLength",
+                old.max_length.as_ref().map(|k| &k.0),
+                new.max_length.as_ref().map(|k| &k.0),
+                false,
+            ),
+            (
+                "minItems",
+                old.min_items.as_ref().map(|k| &k.0),
+                new.min_items.as_ref().map(|k| &k.0),
+                true,
+            ),
+            (
+                "maxItems",
+                old.max_items.as_ref().map(|k| &k.0),
+                new.max_items.as_ref().map(|k| &k.0),
+                false,
+            ),
+            (
+                "minProperties",
+                old.min_props.as_ref().map(|k| &k.0),
+                new.min_props.as_ref().map(|k| &k.0),
+                true,
+            ),
+            (
+                "maxProperties",
+                old.max_props.as_ref().map(|k| &k.0),
+                new.max_props.as_ref().map(|k| &k.0),
+                false,
+            ),
+            (
+                "minContains",
+                old_min_contains.as_ref(),
+                new_min_contains.as_ref(),
+                true,
+            ),
+            (
+                "maxContains",
+                old_max_contains.as_ref(),
+                new_max_contains.as_ref(),
+                false,
+            ),
+        ];
+        for (keyword, old, new, lower) in bounds {
+            location.push('/');
+            location.push_str(keyword);
+            self.bound(old, new, lower, location, negated);
+            location.truncate(truncate);
+        }
+
+        location.push_str("/multipleOf");
+        match (
+            old.multiple.as_ref().map(|k| &k.0),
+            new.multiple.as_ref().map(|k| &k.0),
+        ) {
+            (Some(Data::Value(old)), Some(Data::Value(new))) if old != new => {
+                let (kind, effect) = if is_multiple_of(old, new) {
+                    (ChangeKind::Loosened, Effect::Loosens)
+                } else if is_multiple_of(new, old) {
+                    (ChangeKind::Tightened, Effect::Tightens)
+                } else {
+                    (ChangeKind::Changed, Effect::Both)
+                };
+                let (old, new) = (Value::Number(old.clone()), Value::Number(new.clone()));
+                self.record(location, kind, effect, Some(old), Some(new), negated);
+            }
+            (old, new) => self.keyword(old, new, location, negated),
+        }
+        location.truncate(truncate);
+
+        location.push_str("/pattern");
+        self.keyword(
+            old.pat.as_ref().map(|k| &k.0),
+            new.pat.as_ref().map(|k| &k.0),
+            location,
+            negated,
+        );
+        location.truncate(truncate);
+        location.push_str("/format");
+        let format = |keywords: &'c Keywords| keywords.format.as_ref().map(|k| &k.0);
+        self.keyword(format(old), format(new), location, negated);
+        location.truncate(truncate);
+        location.push_str("/$dynamicRef");
+        let dynamic_ref = |keywords: &'c Keywords| keywords.dynamic_ref.as_ref().map(|k| &k.0);
+        self.keyword(dynamic_ref(old), dynamic_ref(new), location, negated);
+        location.truncate(truncate);
+
+        let unique = |keywords: &Keywords| keywords.unique_items.as_ref().is_some_and(|k| k.0);
+        if unique(old) != unique(new) {
+            location.push_str("/uniqueItems");
+            let (kind, effect) = match unique(new) {
+                true => (ChangeKind::Tightened, Effect::Tightens),
+                false => (ChangeKind::Loosened, Effect::Loosens),
+            };
+            let (old, new) = (Value::Bool(unique(old)), Value::Bool(unique(new)));
+            self.record(location, kind, effect, Some(old), Some(new), negated);
+            location.truncate(truncate);
+        }
+
+        self.required(old, new, location, negated);
+        self.dependencies(old, new, location, negated);
+        self.properties(old, new, location, negated);
+        self.items(old, new, location, negated);
+        self.applicators(old, new, location, negated);
+    }
+
+    fn types(&mut self, old: &Keywords, new: &Keywords, location: &mut String, negated: bool) {
+        let mask = |keywords: &Keywords| keywords.type_.as_ref().map_or(u8::MAX, |k| k.mask());
+        let (old_mask, new_mask) = (mask(old), mask(new));
+        if old_mask == new_mask {
+            return;
+        }
+        let truncate = location.len();
+        location.push_str("/type");
+        for (name, bit) in TYPES {
+            if old_mask & bit != 0 && new_mask & bit == 0 {
+                let old = Some(Value::String(name.to_owned()));
+                self.record(
+                    location,
+                    ChangeKind::Removed,
+                    Effect::Tightens,
+                    old,
+                    None,
+                    negated,
+                );
+            } else if old_mask & bit == 0 && new_mask & bit != 0 {
+                let new = Some(Value::String(name.to_owned()));
+                self.record(
+                    location,
+                    ChangeKind::Added,
+                    Effect::Loosens,
+                    None,
+                    new,
+                    negated,
+                );
+            }
+        }
+        location.truncate(truncate);
+    }
+
+    /// `enum` and `const` are both compared as the set of values they allow.
+    fn values(&mut self, old: &Keywords, new: &Keywords, location: &mut String, negated: bool) {
+        fn values(keywords: &Keywords) -> Option<(&'static str, Data<Vec<&Value>>)> {
+            if let Some(constant) = &keywords.constant {
+                let values = match &constant.0 {
+                    Data::Value(value) => Data::Value(vec![value]),
+                    Data::Reference(reference) => Data::Reference(reference.clone()),
+                };
+                return Some(("const", values));
+            }
+            let values = match &keywords.enum_.as_ref()?.0 {
+                Data::Value(values) => Data::Value(values.iter().collect()),
+                Data::Reference(reference) => Data::Reference(reference.clone()),
+            };
+            Some(("enum", values))
+        }
+        let truncate = location.len();
+        match (values(old), values(new)) {
+            (None, None) => {}
+            (Some((keyword, old)), None) => {
+                location.push('/');
+                location.push_str(keyword);
+                let old = serde_json::to_value(&old).ok();
+                self.record(
+                    location,
+                    ChangeKind::Removed,
+                    Effect::Loosens,
+                    old,
+                    None,
+                    negated,
+                );
+            }
+            (None, Some((keyword, new))) => {
+                location.push('/');
+                location.push_str(keyword);
+                let new = serde_json::to_value(&new).ok();
+                self.record(
+                    location,
+                    ChangeKind::Added,
+                    Effect::Tightens,
+                    None,
+                    new,
+                    negated,
+                );
+            }
+            (Some((_, Data::Value(old))), Some((keyword, Data::Value(new)))) => {
+                location.push('/');
+                location.push_str(keyword);
+                for value in old.iter().filter(|value| !new.contains(value)) {
+                    let old = Some((*value).clone());
+                    self.record(
+                        location,
+                        ChangeKind::Removed,
+                        Effect::Tightens,
+                        old,
+                        None,
+                        negated,
+                    );
+                }
+                for value in new.iter().filter(|value| !old.contains(value)) {
+                    let new = Some((*value).clone());
+                    self.record(
+                        location,
+                        ChangeKind::Added,
+                        Effect::Loosens,
+                        None,
+                        new,
+                        negated,
+                    );
+                }
+            }
+            (Some((_, old)), Some((keyword, new))) => {
+                location.push('/');
+                location.push_str(keyword);
+                let (old, new) = (
+                    serde_json::to_value(&old).ok(),
+                    serde_json::to_value(&new).ok(),
+                );
+                if old != new {
+                    self.record(
+                        location,
+                        ChangeKind::Changed,
+                        Effect::Both,
+                        old,
+                        new,
+                        negated,
+                    );
+                }
+            }
+        }
+        location.truncate(truncate);
+    }
+
+    fn bound(
+        &mut self,
+        old: Option<&Data<Number>>,
+        new: Option<&Data<Number>>,
+        lower: bool,
+        location: &str,
+        negated: bool,
+    ) {
+        let (Some(Data::Value(old)), Some(Data::Value(new))) = (old, new) else {
+            return self.keyword(old, new, location, negated);
+        };
+        let (kind, effect) = match (compare_numbers(new, old), lower) {
+            (Some(Ordering::Equal), _) => return,
+            (Some(Ordering::Greater), true) | (Some(Ordering::Less), false) => {
+                (ChangeKind::Tightened, Effect::Tightens)
+            }
+            (Some(_), _) => (ChangeKind::Loosened, Effect::Loosens),
+            (None, _) => (ChangeKind::Changed, Effect::Both),
+        };
+        let (old, new) = (Value::Number(old.clone()), Value::Number(new.clone()));
+        self.record(location, kind, effect, Some(old), Some(new), negated);
+    }
+
+    /// A keyword that constrains values when present, any change of its value may break both.
+    fn keyword<T: Serialize + PartialEq>(
+        &mut self,
+        old: Option<&T>,
+        new: Option<&T>,
+        location: &str,
+        negated: bool,
+    ) {
+        let value = |value: &T| serde_json::to_value(value).ok();
+        match (old, new) {
+            (Some(old), Some(new)) if old != new => {
+                let (old, new) = (value(old), value(new));
+                self.record(
+                    location,
+                    ChangeKind::Changed,
+                    Effect::Both,
+                    old,
+                    new,
+                    negated,
+                );
+            }
+            (Some(old), None) => {
+                let old = value(old);
+                self.record(
+                    location,
+                    ChangeKind::Removed,
+                    Effect::Loosens,
+                    old,
+                    None,
+                    negated,
+                );
+            }
+            (None, Some(new)) => {
+                let new = value(new);
+                self.record(
+                    location,
+                    ChangeKind::Added,
+                    Effect::Tightens,
+                    None,
+                    new,
+                    negated,
+                );
+            }
+            _ => {}
+        }
+    }
+
+    fn required(&mut self, old: &Keywords, new: &Keywords, location: &mut String, negated: bool) {
+        let required = |keywords: &Keywords| {
+            let mut required: Vec<String> =
+                keywords.required.iter().flat_map(|k| k.0.clone()).collect();
+            required.sort();
+            required
+        };
+        let (old, new) = (required(old), required(new));
+        let truncate = location.len();
+        location.push_str("/required");
+        for name in old.iter().filter(|name| !new.contains(name)) {
+            let old = Some(Value::String(name.clone()));
+            self.record(
+                location,
+                ChangeKind::Removed,
+                Effect::Loosens,
+                old,
+                None,
+                negated,
+            );
+        }
+        for name in new.iter().filter(|name| !old.contains(name)) {
+            let new = Some(Value::String(name.clone()));
+            self.record(
+                location,
+                ChangeKind::Added,
+                Effect::Tightens,
+                None,
+                new,
+                negated,
+            );
+        }
+        location.truncate(truncate);
+    }
+
+    /// `dependentRequired` is compared like `required` and `dependentSchemas` like subschemas,
+    /// for each property that triggers them.
+    fn dependencies(
+        &mut self,
+        old: &'c Keywords,
+        new: &'c Keywords,
+        location: &mut String,
+        negated: bool,
+    ) {
+        let truncate = location.len();
+        let required = |keywords: &'c Keywords| keywords.dependent_required.as_ref().map(|k| &k.0);
+        let (old_required, new_required) = (required(old), required(new));
+        let mut names: Vec<&String> = old_required
+            .into_iter()
+            .chain(new_required)
+            .flat_map(|map| map.keys())
+            .collect();
+        names.sort();
+        names.dedup();
+        for name in names {
+            let required = |map: Option<&'c HashMap<String, Vec<String>>>| {
+                map.and_then(|map| map.get(name))
+                    .map_or(&[][..], |required| required.as_slice())
+            };
+            let (old, new) = (required(old_required), required(new_required));
+            location.push_str("/dependentRequired/");
+            location.push_str(&escape(name));
+            for property in old.iter().filter(|property| !new.contains(property)) {
+                let old = Some(Value::String(property.clone()));
+                self.record(
+                    location,
+                    ChangeKind::Removed,
+                    Effect::Loosens,
+                    old,
+                    None,
+                    negated,
+                );
+            }
+            for property in new.iter().filter(|property| !old.contains(property)) {
+                let new = Some(Value::String(property.clone()));
+                self.record(
+                    location,
+                    ChangeKind::Added,
+                    Effect::Tightens,
+                    None,
+                    new,
+                    negated,
+                );
+            }
+            location.truncate(truncate);
+        }
+
+        let schemas = |keywords: &'c Keywords| keywords.dependent_schemas.as_ref().map(|k| &k.map);
+        let (old_schemas, new_schemas) = (schemas(old), schemas(new));
+        let mut names: Vec<&String> = old_schemas
+            .into_iter()
+            .chain(new_schemas)
+            .flat_map(|map| map.keys())
+            .collect();
+        names.sort();
+        names.dedup();
+        for name in names {
+            location.push_str("/dependentSchemas/");
+            location.push_str(&escape(name));
+            // a missing dependent schema allows everything
+            self.walk(
+                old_schemas.and_then(|map| map.get(name)),
+                new_schemas.and_then(|map| map.get(name)),
+                location,
+                negated,
+            );
+            location.truncate(truncate);
+        }
+    }
+
+    fn properties(
+        &mut self,
+        old: &'c Keywords,
+        new: &'c Keywords,
+        location: &mut String,
+        negated: bool,
+    ) {
+        let additional =
+            |keywords: &'c Keywords| keywords.additional_properties.as_ref().map(|k| &*k.0);
+        let (old_additional, new_additional) = (additional(old), additional(new));
+        let maps = [
+            (
+                "properties",
+                old.props.as_ref().map(|k| &k.map),
+                new.props.as_ref().map(|k| &k.map),
+            ),
+            (
+                "patternProperties",
+                old.pat_props.as_ref().map(|k| &k.map),
+                new.pat_props.as_ref().map(|k| &k.map),
+            ),
+        ];
+        let truncate = location.len();
+        for (keyword, old_map, new_map) in maps {
+            let mut names: Vec<&String> = old_map
+                .into_iter()
+                .chain(new_map)
+                .flat_map(|map| map.keys())
+                .collect();
+            names.sort();
+            names.dedup();
+            for name in names {
+                location.push('/');
+                location.push_str(keyword);
+                location.push('/');
+                location.push_str(&escape(name));
+                let name_value = Some(Value::String(name.clone()));
+                match (
+                    old_map.and_then(|map| map.get(name)),
+                    new_map.and_then(|map| map.get(name)),
+                ) {
+                    (Some(old), Some(new)) => self.walk(Some(old), Some(new), location, negated),
+                    // a new pattern constrains the properties it matches, a new property is
+                    // compared to the additional properties it was before
+                    (None, Some(new)) => {
+                        let (effect, walk) = match keyword {
+                            "patternProperties" => (Effect::Tightens, false),
+                            _ if Self::is_false(self.old, old_additional) => {
+                                (Effect::Loosens, false)
+                            }
+                            _ => (Effect::None, true),
+                        };
+                        self.record(
+                            location,
+                            ChangeKind::Added,
+                            effect,
+                            None,
+                            name_value,
+                            negated,
+                        );
+                        if walk {
+                            self.walk(old_additional, Some(new), location, negated);
+                        }
+                    }
+                    (Some(old), None) => {
+                        let (effect, walk) = match keyword {
+                            "patternProperties" => (Effect::Loosens, false),
+                            _ if Self::is_false(self.new, new_additional) => {
+                                (Effect::Tightens, false)
+                            }
+                            _ => (Effect::None, true),
+                        };
+                        self.record(
+                            location,
+                            ChangeKind::Removed,
+                            effect,
+                            name_value,
+                            None,
+                            negated,
+                        );
+                        if walk {
+                            self.walk(Some(old), new_additional, location, negated);
+                        }
+                    }
+                    (None, None) => {}
+                }
+                location.truncate(truncate);
+            }
+        }
+        let subschemas = [
+            ("additionalProperties", old_additional, new_additional),
+            (
+                "propertyNames",
+                old.prop_names.as_ref().map(|k| &*k.0),
+                new.prop_names.as_ref().map(|k| &*k.0),
+            ),
+            (
+                "unevaluatedProperties",
+                old.unevaluated_props.as_ref().map(|k| &*k.0),
+                new.unevaluated_props.as_ref().map(|k| &*k.0),
+            ),
+        ];
+        for (keyword, old, new) in subschemas {
+            location.push('/');
+            location.push_str(keyword);
+            self.walk(old, new, location, negated);
+            location.truncate(truncate);
+        }
+    }
+
+    fn items(
+        &mut self,
+        old: &'c Keywords,
+        new: &'c Keywords,
+        location: &mut String,
+        negated: bool,
+    ) {
+        let prefix =
+            |keywords: &'c Keywords| keywords.prefix_items.as_ref().map_or(&[][..], |k| &k.0[..]);
+        let items = |keywords: &'c Keywords| keywords.items.as_ref().map(|k| &*k.0);
+        let (old_prefix, new_prefix) = (prefix(old), prefix(new));
+        let truncate = location.len();
+        // past the end of one prefix its items are covered by `items`
+        for i in 0..old_prefix.len().max(new_prefix.len()) {
+            let old_item = old_prefix.get(i).or(items(old));
+            let new_item = new_prefix.get(i).or(items(new));
+            match i < new_prefix.len() {
+                true => location.push_str(&format!("/prefixItems/{i}")),
+                false => location.push_str("/items"),
+            }
+            self.walk(old_item, new_item, location, negated);
+            location.truncate(truncate);
+        }
+        location.push_str("/items");
+        self.walk(items(old), items(new), location, negated);
+        location.truncate(truncate);
+        location.push_str("/unevaluatedItems");
+        let unevaluated =
+            |keywords: &'c Keywords| keywords.unevaluated_items.as_ref().map(|k| &*k.0);
+        self.walk(unevaluated(old), unevaluated(new), location, negated);
+        location.truncate(truncate);
+
+        // an absent `contains` does not require an item, unlike `contains: true`
+        location.push_str("/contains");
+        match (&old.contains, &new.contains) {
+            (Some(old), Some(new)) => self.walk(Some(&old.0), Some(&new.0), location, negated),
+            (Some(_), None) => self.record(
+                location,
+                ChangeKind::Removed,
+                Effect::Loosens,
+                None,
+                None,
+                negated,
+            ),
+            (None, Some(_)) => self.record(
+                location,
+                ChangeKind::Added,
+                Effect::Tightens,
+                None,
+                None,
+                negated,
+            ),
+            (None, None) => {}
+        }
+        location.truncate(truncate);
+    }
+
+    fn applicators(
+        &mut self,
+        old: &'c Keywords,
+        new: &'c Keywords,
+        location: &mut String,
+        negated: bool,
+    ) {
+        let truncate = location.len();
+        // an entry added to `allOf` constrains the schema, one added to `anyOf` allows more
+        // values, any change to `oneOf` can change how many branches match
+        let lists = [
+            (
+                "allOf",
+                old.all_of.as_ref().map(|k| &k.0),
+                new.all_of.as_ref().map(|k| &k.0),
+                Effect::Tightens,
+            ),
+            (
+                "anyOf",
+                old.any_of.as_ref().map(|k| &k.0),
+                new.any_of.as_ref().map(|k| &k.0),
+                Effect::Loosens,
+            ),
+            (
+                "oneOf",
+                old.one_of.as_ref().map(|k| &k.0),
+                new.one_of.as_ref().map(|k| &k.0),
+                Effect::Loosens,
+            ),
+        ];
+        for (keyword, old, new, added) in lists {
+            let (old, new) = (
+                old.map_or(&[][..], |v| &v[..]),
+                new.map_or(&[][..], |v| &v[..]),
+            );
+            let either_way = self.either_way;
+            self.either_way |= keyword == "oneOf";
+            for i in 0..old.len().max(new.len()) {
+                location.push_str(&format!("/{keyword}/{i}"));
+                match (old.get(i), new.get(i)) {
+                    (Some(old), Some(new)) => self.walk(Some(old), Some(new), location, negated),
+                    (None, Some(_)) => {
+                        self.record(location, ChangeKind::Added, added, None, None, negated)
+                    }
+                    (Some(_), None) => {
+                        let removed = match added {
+                            Effect::Tightens => Effect::Loosens,
+                            _ => Effect::Tightens,
+                        };
+                        self.record(location, ChangeKind::Removed, removed, None, None, negated);
+                    }
+                    (None, None) => {}
+                }
+                location.truncate(truncate);
+            }
+            self.either_way = either_way;
+        }
+
+        location.push_str("/not");
+        match (&old.not, &new.not) {
+            (Some(old), Some(new)) => self.walk(Some(&old.0), Some(&new.0), location, !negated),
+            (Some(_), None) => self.record(
+                location,
+                ChangeKind::Removed,
+                Effect::Loosens,
+                None,
+                None,
+                negated,
+            ),
+            (None, Some(_)) => self.record(
+                location,
+                ChangeKind::Added,
+                Effect::Tightens,
+                None,
+                None,
+                negated,
+            ),
+            (None, None) => {}
+        }
+        location.truncate(truncate);
+
+        // a changed `if` moves values between `then` and `else`, it is one change whatever
+        // changed inside it
+        let (either_way, start) = (self.either_way, self.changes.len());
+        self.either_way = true;
+        location.push_str("/if");
+        let condition = |keywords: &'c Keywords| keywords.if_then_else.as_ref().map(|k| &*k.if_);
+        self.walk(condition(old), condition(new), location, negated);
+        let changed = self
+            .changes
+            .drain(start..)
+            .any(|change| change.compatibility == Compatibility::Breaking);
+        if changed {
+            self.record(
+                location,
+                ChangeKind::Changed,
+                Effect::Both,
+                None,
+                None,
+                negated,
+            );
+        }
+        location.truncate(truncate);
+        self.either_way = either_way;
+
+        let branches = |keywords: &'c Keywords| {
+            let if_then_else = keywords.if_then_else.as_ref();
+            [
+                if_then_else.and_then(|k| k.then.as_deref()),
+                if_then_else.and_then(|k| k.else_.as_deref()),
+            ]
+        };
+        for ((old, new), keyword) in branches(old)
+            .into_iter()
+            .zip(branches(new))
+            .zip(["then", "else"])
+        {
+            location.push('/');
+            location.push_str(keyword);
+            self.walk(old, new, location, negated);
+            location.truncate(truncate);
+        }
+    }
+
+    fn is_false(context: &'c Context, schema: Option<&'c JsonSchema>) -> bool {
+        matches!(Self::view(context, schema), View::Bool(false))
+    }
+}
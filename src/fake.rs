@@ -0,0 +1,774 @@
+//! Generate instances of a compiled schema for fixtures and contract tests.
+//!
+//! Values are built from the keywords of the schema: `type`, `enum`/`const`, numeric bounds and
+//! `multipleOf`, string lengths, `format`, `pattern` (by walking the parsed regex), `required`,
+//! `properties`, `additionalProperties`, `items`/`prefixItems`, `minItems`/`maxItems`,
+//! `uniqueItems` and `contains`. Schemas of `allOf` apply together, one branch of `anyOf` and
+//! `oneOf` is picked. Keywords that are not used to build values, like `not` or `if`, are
+//! enforced by validating every generated value and trying again when it fails.
+
+use crate::{
+    context::Context,
+    keywords::{Data, Keywords},
+    lint::is_false,
+    schema::{JsonSchema, ResolvedJsonSchema},
+    tree::{SchemaTree, ARRAY, BOOLEAN, INTEGER, NULL, NUMBER, OBJECT, STRING},
+    validator::Validator,
+};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use regex::Regex;
+use regex_syntax::hir::{Class, Hir, HirKind};
+use serde_json::{json, Map, Number, Value};
+use std::fmt;
+use url::Url;
+
+pub struct FakeOptions {
+    /// the seed of the random number generator, the same seed produces the same instances.
+    pub seed: u64,
+    /// only generate required properties, the fewest items, the shortest strings and numbers
+    /// closest to zero.
+    pub minimal: bool,
+    /// how often a schema may be entered again through `$ref` within one instance, past it only
+    /// the minimal instance of the schema is generated.
+    pub max_depth: usize,
+    /// the most items, characters or optional repetitions added beyond the minimum.
+    pub max_extra: usize,
+    /// instances tried before giving up, instances that fail validation are discarded.
+    pub attempts: usize,
+}
+
+impl Default for FakeOptions {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            minimal: false,
+            max_depth: 2,
+            max_extra: 3,
+            attempts: 32,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FakeError {
+    /// no schema was compiled at the url.
+    NotFound(Url),
+    /// no valid instance was found within the attempts, e.g. for the schema `false`.
+    NoValidInstance(Url),
+    /// no invalid instance was found within the attempts, e.g. for the schema `true`.
+    NoInvalidInstance(Url),
+}
+
+impl fmt::Display for FakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FakeError::NotFound(url) => write!(f, "no schema compiled at {url}"),
+            FakeError::NoValidInstance(url) => write!(f, "no valid instance found for {url}"),
+            FakeError::NoInvalidInstance(url) => write!(f, "no invalid instance found for {url}"),
+        }
+    }
+}
+
+impl std::error::Error for FakeError {}
+
+/// A generator of instances for the schemas of a compiled [`Context`].
+pub struct Faker<'c> {
+    context: &'c Context,
+    tree: SchemaTree,
+    rng: StdRng,
+    options: FakeOptions,
+    /// the schemas being generated, to limit recursion.
+    stack: Vec<&'c Url>,
+}
+
+impl<'c> Faker<'c> {
+    pub fn new(context: &'c Context, options: FakeOptions) -> Self {
+        Self {
+            context,
+            tree: SchemaTree::new(context),
+            rng: StdRng::seed_from_u64(options.seed),
+            options,
+            stack: vec![],
+        }
+    }
+
+    /// A random instance that is valid under the schema at `url`.
+    pub fn generate(&mut self, url: &Url) -> Result<Value, FakeError> {
+        let url = self.url(url)?;
+        for _ in 0..self.options.attempts.max(1) {
+            let minimal = self.options.minimal;
+            if let Some(value) = self.instance(&[url], minimal) {
+                if self.is_valid(url, &value) {
+                    return Ok(value);
+                }
+            }
+        }
+        Err(FakeError::NoValidInstance(url.clone()))
+    }
+
+    /// A random instance that is invalid under the schema at `url`, a valid instance with one
+    /// of its values changed to break a keyword.
+    pub fn generate_invalid(&mut self, url: &Url) -> Result<Value, FakeError> {
+        let url = self.url(url)?;
+        for _ in 0..self.options.attempts.max(1) {
+            let minimal = self.options.minimal;
+            let valid = self.instance(&[url], minimal).unwrap_or(Value::Null);
+            let mut candidates = self.mutations(url, &valid, 0);
+            candidates.shuffle(&mut self.rng);
+            if let Some(value) = candidates.into_iter().find(|v| !self.is_valid(url, v)) {
+                return Ok(value);
+            }
+        }
+        Err(FakeError::NoInvalidInstance(url.clone()))
+    }
+
+    fn url(&self, url: &Url) -> Result<&'c Url, FakeError> {
+        match self.context.schemas.get_key_value(url) {
+            Some((url, _)) => Ok(url),
+            None => Err(FakeError::NotFound(url.clone())),
+        }
+    }
+
+    fn is_valid(&self, url: &Url, value: &Value) -> bool {
+        self.tree
+            .node_index(url)
+            .is_some_and(|node| Validator::short_circuit(&self.tree).validate(node, value))
+    }
+
+    /// The keywords of the schemas and of their `allOf`, with one branch of each `anyOf` and
+    /// `oneOf`. `None` if one of them is `false`.
+    fn parts(&mut self, schemas: &[&'c Url], minimal: bool) -> Option<Vec<&'c Keywords>> {
+        let mut parts = vec![];
+        let mut pending = schemas.to_vec();
+        let mut seen = vec![];
+        while let Some(url) = pending.pop() {
+            if seen.contains(&url) {
+                continue;
+            }
+            seen.push(url);
+            let keywords = match self.context.schema(url)? {
+                ResolvedJsonSchema::Bool(true) => continue,
+                ResolvedJsonSchema::Bool(false) => return None,
+                ResolvedJsonSchema::Object(keywords) => keywords,
+            };
+            parts.push(keywords);
+            for schema in keywords.all_of.iter().flat_map(|k| &k.0) {
+                pending.extend(subschema(schema)?);
+            }
+            let branches = [
+                keywords.any_of.as_ref().map(|k| &k.0),
+                keywords.one_of.as_ref().map(|k| &k.0),
+            ];
+            for branches in branches.into_iter().flatten() {
+                let branch = match minimal {
+                    true => branches.first(),
+                    false => branches.choose(&mut self.rng),
+                };
+                pending.extend(subschema(branch?)?);
+            }
+        }
+        Some(parts)
+    }
+
+    /// An instance of all of the schemas, `None` if they cannot be satisfied.
+    fn instance(&mut self, schemas: &[&'c Url], minimal: bool) -> Option<Value> {
+        let depth = schemas
+            .iter()
+            .map(|url| self.stack.iter().filter(|entered| *entered == url).count())
+            .max()
+            .unwrap_or_default();
+        // past the depth only the minimal instance is generated, which ends most recursion
+        if depth > self.options.max_depth + self.options.max_extra {
+            return None;
+        }
+        let minimal = minimal || depth >= self.options.max_depth;
+        self.stack.extend(schemas);
+        let value = self
+            .parts(schemas, minimal)
+            .and_then(|parts| self.value(&parts, minimal));
+        self.stack.truncate(self.stack.len() - schemas.len());
+        value
+    }
+
+    fn value(&mut self, parts: &[&'c Keywords], minimal: bool) -> Option<Value> {
+        if let Some(values) = self.values(parts) {
+            return match minimal {
+                true => values.first().cloned(),
+                false => values.choose(&mut self.rng).cloned(),
+            };
+        }
+        let types = self.types(parts);
+        let ty = match minimal {
+            true => *types.first()?,
+            false => *types.choose(&mut self.rng)?,
+        };
+        match ty {
+            NULL => Some(Value::Null),
+            BOOLEAN => Some(Value::Bool(!minimal && self.rng.gen_bool(0.5))),
+            INTEGER | NUMBER => self.number(parts, ty == INTEGER, minimal),
+            STRING => self.string(parts, minimal).map(Value::String),
+            ARRAY => self.array(parts, minimal),
+            _ => self.object(parts, minimal),
+        }
+    }
+
+    /// The values allowed by `const` and `enum` that are valid under every part.
+    fn values(&self, parts: &[&'c Keywords]) -> Option<Vec<Value>> {
+        let mut values: Option<Vec<Value>> = None;
+        for keywords in parts {
+            let allowed = match (&keywords.constant, &keywords.enum_) {
+                (Some(constant), _) => vec![constant.0.value()?.clone()],
+                (None, Some(enum_)) => enum_.0.value()?.clone(),
+                (None, None) => continue,
+            };
+            values = Some(match values {
+                None => allowed,
+                Some(values) => values.into_iter().filter(|v| allowed.contains(v)).collect(),
+            });
+        }
+        values
+    }
+
+    /// The types allowed by every part, the simplest first. Without `type` the types are guessed
+    /// from the keywords that are present.
+    fn types(&self, parts: &[&'c Keywords]) -> Vec<u8> {
+        let mut mask = parts
+            .iter()
+            .filter_map(|keywords| keywords.type_.as_ref())
+            .fold(u8::MAX, |mask, ty| mask & ty.mask());
+        if parts.iter().all(|keywords| keywords.type_.is_none()) {
+            let present = |check: fn(&Keywords) -> bool| parts.iter().any(|k| check(k));
+            mask = 0;
+            if present(|k| k.props.is_some() || k.required.is_some() || k.min_props.is_some()) {
+                mask |= OBJECT;
+            }
+            if present(|k| k.items.is_some() || k.prefix_items.is_some() || k.min_items.is_some()) {
+                mask |= ARRAY;
+            }
+            if present(|k| k.min_length.is_some() || k.pat.is_some() || k.format.is_some()) {
+                mask |= STRING;
+            }
+            if present(|k| k.min.is_some() || k.max.is_some() || k.multiple.is_some()) {
+                mask |= NUMBER;
+            }
+            if mask == 0 {
+                mask = NULL | BOOLEAN | INTEGER | STRING;
+            }
+        }
+        [NULL, BOOLEAN, INTEGER, NUMBER, STRING, ARRAY, OBJECT]
+            .into_iter()
+            .filter(|ty| mask & ty != 0)
+            .collect()
+    }
+
+    fn number(&mut self, parts: &[&'c Keywords], integer: bool, minimal: bool) -> Option<Value> {
+        let number = |data: Option<&Data<Number>>| data.and_then(Data::value)?.as_f64();
+        let (mut lower, mut upper) = (f64::MIN, f64::MAX);
+        let mut multiple = None;
+        // exclusive bounds are moved inwards by a step that is small for the bound
+        let step = |bound: f64| match integer {
+            true => 1.0,
+            false => (bound.abs() * 1e-9).max(1e-9),
+        };
+        for keywords in parts {
+            if let Some(min) = number(keywords.min.as_ref().map(|k| &k.0)) {
+                lower = lower.max(min);
+            }
+            if let Some(min) = number(keywords.excl_min.as_ref().map(|k| &k.0)) {
+                lower = lower.max(min + step(min));
+            }
+            if let Some(max) = number(keywords.max.as_ref().map(|k| &k.0)) {
+                upper = upper.min(max);
+            }
+            if let Some(max) = number(keywords.excl_max.as_ref().map(|k| &k.0)) {
+                upper = upper.min(max - step(max));
+            }
+            multiple = multiple.or(number(keywords.multiple.as_ref().map(|k| &k.0)));
+        }
+        // without a bound values stay near the other bound or zero
+        let span = 100.0;
+        match (lower == f64::MIN, upper == f64::MAX) {
+            (true, true) => (lower, upper) = (-span, span),
+            (true, false) => lower = upper - span,
+            (false, true) => upper = lower + span,
+            (false, false) => {}
+        }
+        let mut multiple = multiple.filter(|m| *m > 0.0).unwrap_or(0.0);
+        if integer {
+            (lower, upper) = (lower.ceil(), upper.floor());
+            if multiple == 0.0 {
+                multiple = 1.0;
+            }
+        }
+        let value = if multiple > 0.0 {
+            let scaled = |bound: f64| (bound / multiple).clamp(f64::MIN, f64::MAX);
+            let (low, high) = (scaled(lower).ceil(), scaled(upper).floor());
+            if low > high {
+                return None;
+            }
+            let factor = match minimal {
+                true => 0f64.clamp(low, high),
+                false => self.between(low, high).round(),
+            };
+            factor * multiple
+        } else {
+            if lower > upper {
+                return None;
+            }
+            match minimal {
+                true => 0f64.clamp(lower, upper),
+                false => self.between(lower, upper),
+            }
+        };
+        // integral values are written without a fraction as long as an integer type holds them
+        if integer || value.fract() == 0.0 {
+            if value.abs() < i64::MAX as f64 {
+                return Some(json!(value as i64));
+            }
+            if (0.0..u64::MAX as f64).contains(&value) {
+                return Some(json!(value as u64));
+            }
+        }
+        Number::from_f64(value).map(Value::Number)
+    }
+
+    /// A number in `lower..=upper`, also for ranges whose span does not fit in a `f64`.
+    fn between(&mut self, lower: f64, upper: f64) -> f64 {
+        // rand scales the span up a little, so it has to stay well below the largest float
+        if upper - lower <= f64::MAX / 2.0 {
+            return self.rng.gen_range(lower..=upper);
+        }
+        let t: f64 = self.rng.gen();
+        (lower * (1.0 - t) + upper * t).clamp(lower, upper)
+    }
+
+    fn string(&mut self, parts: &[&'c Keywords], minimal: bool) -> Option<String> {
+        let number = |data: Option<&Data<Number>>| data.and_then(Data::value)?.as_u64();
+        let min = parts
+            .iter()
+            .filter_map(|k| number(k.min_length.as_ref().map(|k| &k.0)))
+            .max()
+            .unwrap_or(0) as usize;
+        let max = parts
+            .iter()
+            .filter_map(|k| number(k.max_length.as_ref().map(|k| &k.0)))
+            .min()
+            .map_or(usize::MAX, |max| max as usize);
+        if min > max {
+            return None;
+        }
+        let formats = parts.iter().filter_map(|k| k.format.as_ref());
+        if let Some(value) = formats.filter_map(|k| self.format(&k.0)).next() {
+            return Some(value);
+        }
+        let pattern = parts.iter().find_map(|k| k.pat.as_ref()?.0.value());
+        if let Some(hir) = pattern.and_then(|p| regex_syntax::Parser::new().parse(p).ok()) {
+            // the regex does not know the lengths, keep the first string that fits
+            let mut value = String::new();
+            for _ in 0..self.options.attempts.max(1) {
+                value.clear();
+                self.sample(&hir, &mut value, minimal);
+                let len = value.chars().count();
+                if len >= min && len <= max {
+                    break;
+                }
+            }
+            return Some(value);
+        }
+        let len = match minimal {
+            true => min,
+            false => self
+                .rng
+                .gen_range(min..=max.min(min + self.options.max_extra * 3)),
+        };
+        let chars = b"abcdefghijklmnopqrstuvwxyz0123456789";
+        Some(
+            (0..len)
+                .map(|_| match minimal {
+                    true => 'a',
+                    false => *chars.choose(&mut self.rng).unwrap_or(&b'a') as char,
+                })
+                .collect(),
+        )
+    }
+
+    /// A string of a format, `None` for formats that are not known.
+    fn format(&mut self, format: &str) -> Option<String> {
+        let word: String = (0..self.rng.gen_range(3..8))
+            .map(|_| self.rng.gen_range(b'a'..=b'z') as char)
+            .collect();
+        let (year, month, day) = (
+            self.rng.gen_range(1970..2100),
+            self.rng.gen_range(1..=12),
+            self.rng.gen_range(1..=28),
+        );
+        let (hour, minute, second) = (
+            self.rng.gen_range(0..24),
+            self.rng.gen_range(0..60),
+            self.rng.gen_range(0..60),
+        );
+        let date = format!("{year:04}-{month:02}-{day:02}");
+        let time = format!("{hour:02}:{minute:02}:{second:02}Z");
+        let value = match format {
+            "date-time" => format!("{date}T{time}"),
+            "date" => date,
+            "time" => time,
+            "duration" => format!("P{day}DT{hour}H"),
+            "email" | "idn-email" => format!("{word}@example.com"),
+            "hostname" | "idn-hostname" => format!("{word}.example.com"),
+            "ipv4" => {
+                let octets: Vec<String> = (0..4)
+                    .map(|_| self.rng.gen_range(0..=255u8).to_string())
+                    .collect();
+                octets.join(".")
+            }
+            "ipv6" => {
+                let groups: Vec<String> = (0..8)
+                    .map(|_| format!("{:x}", self.rng.gen::<u16>()))
+                    .collect();
+                groups.join(":")
+            }
+            "uri" | "url" | "iri" => format!("https://example.com/{word}"),
+            "uri-reference" | "iri-reference" | "json-pointer" => format!("/{word}"),
+            "uri-template" => format!("https://example.com/{{{word}}}"),
+            "relative-json-pointer" => "0".to_owned(),
+            "regex" => word,
+            "uuid" => {
+                let bytes: [u8; 16] = self.rng.gen();
+                let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+                // version 4 and the variant of RFC 4122
+                let variant = ["8", "9", "a", "b"][usize::from(bytes[8] & 3)];
+                format!(
+                    "{}-{}-4{}-{variant}{}-{}",
+                    &hex[..8],
+                    &hex[8..12],
+                    &hex[13..16],
+                    &hex[17..20],
+                    &hex[20..]
+                )
+            }
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// Append a string matching the regex.
+    fn sample(&mut self, hir: &Hir, out: &mut String, minimal: bool) {
+        match hir.kind() {
+            HirKind::Empty | HirKind::Look(_) => {}
+            HirKind::Literal(literal) => out.push_str(&String::from_utf8_lossy(&literal.0)),
+            HirKind::Class(Class::Unicode(class)) => {
+                // prefer printable ascii where the class allows it, e.g. for `\d`
+                let ascii: Vec<_> = class
+                    .ranges()
+                    .iter()
+                    .filter(|range| range.start() <= '~' && range.end() >= ' ')
+                    .collect();
+                let ranges = match ascii.is_empty() {
+                    true => class.ranges().iter().collect(),
+                    false => ascii,
+                };
+                let range = match minimal {
+                    true => ranges.first(),
+                    false => ranges.choose(&mut self.rng),
+                };
+                if let Some(range) = range {
+                    let (mut start, mut end) = (range.start() as u32, range.end() as u32);
+                    if start <= 0x7e && end >= 0x20 {
+                        (start, end) = (start.max(0x20), end.min(0x7e));
+                    }
+                    let c = match minimal {
+                        true => start,
+                        false => self.rng.gen_range(start..=end),
+                    };
+                    out.push(char::from_u32(c).unwrap_or(range.start()));
+                }
+            }
+            HirKind::Class(Class::Bytes(class)) => {
+                let ranges = class.ranges();
+                let range = match minimal {
+                    true => ranges.first(),
+                    false => ranges.choose(&mut self.rng),
+                };
+                if let Some(range) = range {
+                    let end = range.end().min(0x7f).max(range.start());
+                    out.push(self.rng.gen_range(range.start()..=end) as char);
+                }
+            }
+            HirKind::Repetition(repetition) => {
+                let extra = self.options.max_extra as u32;
+                let max = repetition.max.unwrap_or(u32::MAX);
+                let count = match minimal {
+                    true => repetition.min,
+                    false => self
+                        .rng
+                        .gen_range(repetition.min..=max.min(repetition.min + extra)),
+                };
+                for _ in 0..count {
+                    self.sample(&repetition.sub, out, minimal);
+                }
+            }
+            HirKind::Capture(capture) => self.sample(&capture.sub, out, minimal),
+            HirKind::Concat(hirs) => {
+                for hir in hirs {
+                    self.sample(hir, out, minimal);
+                }
+            }
+            HirKind::Alternation(hirs) => {
+                let hir = match minimal {
+                    true => hirs.first(),
+                    false => hirs.choose(&mut self.rng),
+                };
+                if let Some(hir) = hir {
+                    self.sample(hir, out, minimal);
+                }
+            }
+        }
+    }
+
+    fn array(&mut self, parts: &[&'c Keywords], minimal: bool) -> Option<Value> {
+        let number = |data: Option<&Data<Number>>| data.and_then(Data::value)?.as_u64();
+        let contains: Vec<&'c JsonSchema> = parts
+            .iter()
+            .filter_map(|k| k.contains.as_ref().map(|k| &*k.0))
+            .collect();
+        let min_contains = parts
+            .iter()
+            .filter_map(|k| k.min_contains.as_ref()?.0.as_u64())
+            .max()
+            .unwrap_or(1) as usize;
+        let min_contains = if contains.is_empty() { 0 } else { min_contains };
+        let min = parts
+            .iter()
+            .filter_map(|k| number(k.min_items.as_ref().map(|k| &k.0)))
+            .max()
+            .unwrap_or(0) as usize;
+        let mut max = parts
+            .iter()
+            .filter_map(|k| number(k.max_items.as_ref().map(|k| &k.0)))
+            .min()
+            .map_or(usize::MAX, |max| max as usize);
+        for keywords in parts {
+            let prefix = keywords.prefix_items.as_ref().map_or(0, |k| k.0.len());
+            if matches!(&keywords.items, Some(items) if is_false(self.context, &items.0)) {
+                max = max.min(prefix);
+            }
+        }
+        let min = min.max(min_contains);
+        if min > max {
+            return None;
+        }
+        let len = match minimal {
+            true => min,
+            false => self
+                .rng
+                .gen_range(min..=max.min(min + self.options.max_extra)),
+        };
+        let unique = parts
+            .iter()
+            .any(|k| k.unique_items.as_ref().is_some_and(|k| k.0));
+        let mut items: Vec<Value> = vec![];
+        for i in 0..len {
+            let mut schemas = vec![];
+            for keywords in parts {
+                let prefix = keywords.prefix_items.as_ref().and_then(|k| k.0.get(i));
+                let items = keywords.items.as_ref().map(|k| &*k.0);
+                if let Some(schema) = prefix.or(items) {
+                    schemas.extend(subschema(schema)?);
+                }
+            }
+            // the last items satisfy `contains`
+            if i + min_contains >= len {
+                for schema in &contains {
+                    schemas.extend(subschema(schema)?);
+                }
+            }
+            let mut item = self.instance(&schemas, minimal)?;
+            for _ in 0..self.options.attempts {
+                if !unique || !items.contains(&item) {
+                    break;
+                }
+                item = self.instance(&schemas, false)?;
+            }
+            items.push(item);
+        }
+        Some(Value::Array(items))
+    }
+
+    fn object(&mut self, parts: &[&'c Keywords], minimal: bool) -> Option<Value> {
+        let number = |data: Option<&Data<Number>>| data.and_then(Data::value)?.as_u64();
+        let min = parts
+            .iter()
+            .filter_map(|k| number(k.min_props.as_ref().map(|k| &k.0)))
+            .max()
+            .unwrap_or(0) as usize;
+        let max = parts
+            .iter()
+            .filter_map(|k| number(k.max_props.as_ref().map(|k| &k.0)))
+            .min()
+            .map_or(usize::MAX, |max| max as usize);
+        let mut required: Vec<&String> = parts
+            .iter()
+            .flat_map(|k| k.required.iter().flat_map(|k| &k.0))
+            .collect();
+        required.sort();
+        required.dedup();
+        let mut optional: Vec<&String> = parts
+            .iter()
+            .flat_map(|k| k.props.iter().flat_map(|k| k.map.keys()))
+            .filter(|name| !required.contains(name))
+            .collect();
+        optional.sort();
+        optional.dedup();
+
+        let mut object = Map::new();
+        for name in required {
+            let schemas = self.property_schemas(parts, name)?;
+            object.insert(name.clone(), self.instance(&schemas, minimal)?);
+        }
+        for name in optional {
+            if object.len() >= max || minimal && object.len() >= min {
+                break;
+            }
+            if object.len() >= min && !self.rng.gen_bool(0.5) {
+                continue;
+            }
+            // an optional property that cannot be generated is left out
+            let value = self
+                .property_schemas(parts, name)
+                .and_then(|schemas| self.instance(&schemas, minimal));
+            if let Some(value) = value {
+                object.insert(name.clone(), value);
+            }
+        }
+        for i in 0.. {
+            if object.len() >= min {
+                break;
+            }
+            let name = format!("property{i}");
+            let schemas = self.property_schemas(parts, &name)?;
+            object.insert(name, self.instance(&schemas, minimal)?);
+        }
+        Some(Value::Object(object))
+    }
+
+    /// The schemas a property of the name is valid against, `None` if one of them is `false`.
+    fn property_schemas(&self, parts: &[&'c Keywords], name: &str) -> Option<Vec<&'c Url>> {
+        let mut schemas = vec![];
+        for keywords in parts {
+            if let Some(schema) = keywords.props.as_ref().and_then(|k| k.map.get(name)) {
+                schemas.extend(subschema(schema)?);
+                continue;
+            }
+            let mut matched = false;
+            for (pattern, schema) in keywords.pat_props.iter().flat_map(|k| &k.map) {
+                if Regex::new(pattern).is_ok_and(|regex| regex.is_match(name)) {
+                    matched = true;
+                    schemas.extend(subschema(schema)?);
+                }
+            }
+            if let (false, Some(additional)) = (matched, &keywords.additional_properties) {
+                schemas.extend(subschema(&additional.0)?);
+            }
+        }
+        Some(schemas)
+    }
+
+    /// Values that are likely invalid, derived from a valid value and the keywords of the
+    /// schema, the caller keeps the ones that fail validation.
+    fn mutations(&mut self, url: &'c Url, valid: &Value, depth: usize) -> Vec<Value> {
+        let mut candidates = vec![
+            Value::Null,
+            json!(true),
+            json!(0),
+            json!(0.5),
+            json!("invalid"),
+            json!([]),
+            json!({}),
+        ];
+        let Some(parts) = self.parts(&[url], true) else {
+            return candidates;
+        };
+        for keywords in &parts {
+            let number = |data: Option<&Data<Number>>| data.and_then(Data::value)?.as_f64();
+            let bounds = [
+                (number(keywords.min.as_ref().map(|k| &k.0)), -1.0),
+                (number(keywords.max.as_ref().map(|k| &k.0)), 1.0),
+                (number(keywords.excl_min.as_ref().map(|k| &k.0)), 0.0),
+                (number(keywords.excl_max.as_ref().map(|k| &k.0)), 0.0),
+            ];
+            for (bound, offset) in bounds {
+                candidates.extend(
+                    bound
+                        .and_then(|bound| Number::from_f64(bound + offset))
+                        .map(Value::Number),
+                );
+            }
+            if let (Some(multiple), Some(value)) = (
+                number(keywords.multiple.as_ref().map(|k| &k.0)),
+                valid.as_f64(),
+            ) {
+                candidates.extend(Number::from_f64(value + multiple / 2.0).map(Value::Number));
+            }
+            let length = |data: Option<&Data<Number>>| data.and_then(Data::value)?.as_u64();
+            if let Some(min) = length(keywords.min_length.as_ref().map(|k| &k.0)) {
+                candidates.push(json!("a".repeat(min.saturating_sub(1) as usize)));
+            }
+            if let Some(max) = length(keywords.max_length.as_ref().map(|k| &k.0)) {
+                candidates.push(json!("a".repeat(max as usize + 1)));
+            }
+            if keywords.pat.is_some() {
+                candidates.extend([json!(""), json!("\u{0}~!")]);
+            }
+        }
+        match valid {
+            Value::Array(items) => {
+                if !items.is_empty() {
+                    candidates.push(Value::Array(items[..items.len() - 1].to_vec()));
+                    let mut duplicated = items.clone();
+                    duplicated.push(items[0].clone());
+                    candidates.push(Value::Array(duplicated));
+                }
+                let mut extended = items.clone();
+                extended.extend(std::iter::repeat_n(Value::Null, self.options.max_extra + 1));
+                candidates.push(Value::Array(extended));
+            }
+            Value::Object(object) => {
+                for name in object.keys() {
+                    let mut removed = object.clone();
+                    removed.remove(name);
+                    candidates.push(Value::Object(removed));
+                }
+                let mut added = object.clone();
+                added.insert("unexpectedProperty".to_owned(), Value::Null);
+                candidates.push(Value::Object(added));
+                // break one property of the object
+                if depth < self.options.max_depth {
+                    for (name, value) in object {
+                        let Some(schemas) = self.property_schemas(&parts, name) else {
+                            continue;
+                        };
+                        for schema in schemas {
+                            for mutation in self.mutations(schema, value, depth + 1) {
+                                let mut changed = object.clone();
+                                changed.insert(name.clone(), mutation);
+                                candidates.push(Value::Object(changed));
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        candidates
+    }
+}
+
+/// The url of a compiled subschema, `Some(None)` for `true` and `None` for `false`.
+fn subschema(schema: &JsonSchema) -> Option<Option<&Url>> {
+    match schema {
+        JsonSchema::Resolved(url) => Some(Some(url)),
+        JsonSchema::Bool(false) => None,
+        _ => Some(None),
+    }
+}
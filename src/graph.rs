@@ -0,0 +1,296 @@
+//! The reference graph between the schemas of a compiled [`Context`].
+//!
+//! Every entry of [`Context::schemas`] is a node. Edges either point at an inline subschema
+//! (`properties`, `items`, ...) or follow a `$ref`/`$dynamicRef` to another entry, and are
+//! labelled with the keyword location they originate from.
+
+use crate::{
+    context::Context,
+    pointer::JsonPointer,
+    schema::{JsonSchema, ResolvedJsonSchema},
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{self, Write},
+};
+use url::Url;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EdgeKind {
+    /// The target is a subschema written inline in the source schema.
+    Subschema,
+    /// The target was reached through `$ref`.
+    Ref,
+    /// The target was reached through `$dynamicRef`.
+    DynamicRef,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edge {
+    pub from: Url,
+    pub to: Url,
+    /// location of the keyword relative to `from`, e.g. `/properties/owner/$ref`.
+    pub keyword_location: String,
+    pub kind: EdgeKind,
+}
+
+pub struct ReferenceGraph {
+    nodes: BTreeSet<Url>,
+    edges: Vec<Edge>,
+}
+
+/// The url an inline subschema at `keyword_location` of `owner` is compiled to.
+fn inline_location(owner: &Url, keyword_location: &str) -> Url {
+    let mut url = owner.clone();
+    let fragment = format!(
+        "{}{}",
+        owner.fragment().unwrap_or_default(),
+        keyword_location
+    );
+    url.set_fragment(Some(&fragment));
+    url
+}
+
+/// Whether a url points at an entry of a `$defs` or `definitions` keyword.
+fn is_definition(url: &Url) -> bool {
+    let mut segments = url.fragment().unwrap_or_default().rsplit('/');
+    segments.next();
+    matches!(segments.next(), Some("$defs" | "definitions"))
+}
+
+/// Whether a url points at the root of a document rather than a location inside it.
+fn is_document(url: &Url) -> bool {
+    url.fragment().map(str::is_empty).unwrap_or(true)
+}
+
+impl ReferenceGraph {
+    pub fn new(context: &Context) -> Self {
+        let mut nodes = BTreeSet::new();
+        let mut edges = vec![];
+        let mut urls: Vec<_> = context.schemas.keys().collect();
+        urls.sort();
+        for url in urls {
+            nodes.insert(url.clone());
+            let keywords = match context.schemas.get(url) {
+                Some(ResolvedJsonSchema::Object(keywords)) => keywords,
+                _ => continue,
+            };
+            for (keyword_location, schema) in keywords.subschemas() {
+                if let JsonSchema::Resolved(to) = schema {
+                    if *to == inline_location(url, &keyword_location) {
+                        edges.push(Edge {
+                            from: url.clone(),
+                            to: to.clone(),
+                            keyword_location,
+                            kind: EdgeKind::Subschema,
+                        });
+                    } else {
+                        edges.push(Edge {
+                            from: url.clone(),
+                            to: to.clone(),
+                            keyword_location: format!("{keyword_location}/$ref"),
+                            kind: EdgeKind::Ref,
+                        });
+                    }
+                }
+            }
+            if let Some(dynamic_ref) = &keywords.dynamic_ref {
+                if let JsonPointer::Absolute(to) = &dynamic_ref.0 {
+                    edges.push(Edge {
+                        from: url.clone(),
+                        to: to.clone(),
+                        keyword_location: "/$dynamicRef".to_owned(),
+                        kind: EdgeKind::DynamicRef,
+                    });
+                }
+            }
+        }
+        // references to schemas that failed to resolve still show up as nodes
+        for edge in &edges {
+            nodes.insert(edge.to.clone());
+        }
+        Self { nodes, edges }
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = &Url> {
+        self.nodes.iter()
+    }
+
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    /// Edges created by `$ref` or `$dynamicRef`, leaving out inline subschemas.
+    pub fn references(&self) -> impl Iterator<Item = &Edge> {
+        self.edges
+            .iter()
+            .filter(|edge| edge.kind != EdgeKind::Subschema)
+    }
+
+    pub fn outgoing<'a>(&'a self, url: &'a Url) -> impl Iterator<Item = &'a Edge> {
+        self.edges.iter().filter(move |edge| &edge.from == url)
+    }
+
+    pub fn incoming<'a>(&'a self, url: &'a Url) -> impl Iterator<Item = &'a Edge> {
+        self.edges.iter().filter(move |edge| &edge.to == url)
+    }
+
+    /// Strongly connected components of the graph in reverse topological order, every node
+    /// appears in exactly one component.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<Url>> {
+        let index: BTreeMap<&Url, usize> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, url)| (url, i))
+            .collect();
+        let urls: Vec<&Url> = self.nodes.iter().collect();
+        let mut successors = vec![vec![]; urls.len()];
+        for edge in &self.edges {
+            successors[index[&edge.from]].push(index[&edge.to]);
+        }
+
+        // iterative tarjan, recursion would overflow on deeply nested schemas
+        let mut order = vec![usize::MAX; urls.len()];
+        let mut low = vec![0; urls.len()];
+        let mut on_stack = vec![false; urls.len()];
+        let mut stack = vec![];
+        let mut components = vec![];
+        let mut next = 0;
+        for root in 0..urls.len() {
+            if order[root] != usize::MAX {
+                continue;
+            }
+            let mut frames = vec![(root, 0)];
+            order[root] = next;
+            low[root] = next;
+            next += 1;
+            stack.push(root);
+            on_stack[root] = true;
+            while let Some((node, child)) = frames.pop() {
+                if let Some(&succ) = successors[node].get(child) {
+                    frames.push((node, child + 1));
+                    if order[succ] == usize::MAX {
+                        order[succ] = next;
+                        low[succ] = next;
+                        next += 1;
+                        stack.push(succ);
+                        on_stack[succ] = true;
+                        frames.push((succ, 0));
+                    } else if on_stack[succ] {
+                        low[node] = low[node].min(order[succ]);
+                    }
+                    continue;
+                }
+                if low[node] == order[node] {
+                    let mut component = vec![];
+                    while let Some(member) = stack.pop() {
+                        on_stack[member] = false;
+                        component.push(urls[member].clone());
+                        if member == node {
+                            break;
+                        }
+                    }
+                    component.sort();
+                    components.push(component);
+                }
+                if let Some(&(parent, _)) = frames.last() {
+                    low[parent] = low[parent].min(low[node]);
+                }
+            }
+        }
+        components
+    }
+
+    /// The components that contain a cycle, these are the recursive schemas.
+    pub fn cycles(&self) -> Vec<Vec<Url>> {
+        self.strongly_connected_components()
+            .into_iter()
+            .filter(|component| match component.as_slice() {
+                [url] => self.outgoing(url).any(|edge| &edge.to == url),
+                _ => true,
+            })
+            .collect()
+    }
+
+    /// Whether a schema can reach itself through its references.
+    pub fn is_recursive(&self, url: &Url) -> bool {
+        self.cycles()
+            .iter()
+            .any(|component| component.contains(url))
+    }
+
+    /// Entries of `$defs` and `definitions` that cannot be reached from the root of any document.
+    pub fn unused_defs(&self) -> Vec<&Url> {
+        let mut reachable = BTreeSet::new();
+        let mut pending: Vec<&Url> = self.nodes.iter().filter(|url| is_document(url)).collect();
+        while let Some(url) = pending.pop() {
+            if reachable.insert(url) {
+                // a definition is only used if something references it, containment alone does not count
+                pending.extend(
+                    self.outgoing(url)
+                        .filter(|edge| {
+                            !(edge.kind == EdgeKind::Subschema && is_definition(&edge.to))
+                        })
+                        .map(|edge| &edge.to),
+                );
+            }
+        }
+        self.nodes
+            .iter()
+            .filter(|url| is_definition(url) && !reachable.contains(url))
+            .collect()
+    }
+
+    /// Write the graph in the graphviz DOT language. Inline subschemas are drawn dashed.
+    pub fn write_dot(&self, w: &mut impl Write) -> io::Result<()> {
+        writeln!(w, "digraph schemas {{")?;
+        for url in &self.nodes {
+            writeln!(w, "\t{:?};", url.as_str())?;
+        }
+        for edge in &self.edges {
+            write!(
+                w,
+                "\t{:?} -> {:?} [label={:?}",
+                edge.from.as_str(),
+                edge.to.as_str(),
+                edge.keyword_location
+            )?;
+            if edge.kind == EdgeKind::Subschema {
+                write!(w, ", style=dashed")?;
+            }
+            writeln!(w, "];")?;
+        }
+        writeln!(w, "}}")?;
+        Ok(())
+    }
+
+    /// Write the graph as a mermaid flowchart. Inline subschemas are drawn dotted.
+    pub fn write_mermaid(&self, w: &mut impl Write) -> io::Result<()> {
+        let ids: BTreeMap<&Url, usize> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, url)| (url, i))
+            .collect();
+        let escape = |s: &str| s.replace('"', "#quot;");
+        writeln!(w, "flowchart LR")?;
+        for (url, id) in &ids {
+            writeln!(w, "\tn{id}[\"{}\"]", escape(url.as_str()))?;
+        }
+        for edge in &self.edges {
+            let arrow = match edge.kind {
+                EdgeKind::Subschema => "-.->",
+                _ => "-->",
+            };
+            writeln!(
+                w,
+                "\tn{} {arrow}|\"{}\"| n{}",
+                ids[&edge.from],
+                escape(&edge.keyword_location),
+                ids[&edge.to]
+            )?;
+        }
+        Ok(())
+    }
+}
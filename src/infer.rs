@@ -0,0 +1,404 @@
+//! Infer a schema from sample instances.
+//!
+//! Every sample is merged into a summary of the values seen at each location, all items of the
+//! arrays at a location are merged into one summary, then a [`JsonSchema`] is built from it:
+//! `type`, `required` for properties present in enough of the objects, `enum` for strings with
+//! few distinct values, `format` for dates, uuids and uris, `minimum`/`maximum` and `$defs` for
+//! object shapes that occur more than once. The schema is plain [`Keywords`], it can be compiled
+//! into a [`Context`](crate::context::Context) and passed to the code generators.
+
+use crate::{
+    keywords::{
+        Data, DefsKeyword, EnumKeyword, FormatKeyword, ItemsKeyword, Keywords, MaximumKeyword,
+        MinimumKeyword, PropertiesKeyword, RequiredKeyword, TypeKeyword,
+    },
+    pointer::JsonPointer,
+    schema::JsonSchema,
+    validator::{compare_numbers, escape},
+};
+use serde_json::{Number, Value};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+};
+
+pub struct InferOptions {
+    /// share of the objects at a location a property needs to be present in to be required.
+    pub required_ratio: f64,
+    /// strings with at most this many distinct values become an `enum`, when every value was
+    /// seen at least twice on average.
+    pub max_enum_values: usize,
+    /// detect `date-time`, `date`, `uuid` and `uri` strings.
+    pub formats: bool,
+    /// add `minimum` and `maximum` of the numbers seen.
+    pub numeric_bounds: bool,
+    /// move object shapes that occur at more than one location into `$defs`.
+    pub extract_defs: bool,
+}
+
+impl Default for InferOptions {
+    fn default() -> Self {
+        Self {
+            required_ratio: 1.0,
+            max_enum_values: 8,
+            formats: true,
+            numeric_bounds: true,
+            extract_defs: true,
+        }
+    }
+}
+
+/// Infer a schema from the samples with the default options.
+pub fn infer<'v>(samples: impl IntoIterator<Item = &'v Value>) -> JsonSchema {
+    let mut inference = Inference::new(InferOptions::default());
+    for sample in samples {
+        inference.add(sample);
+    }
+    inference.finish()
+}
+
+/// Samples seen so far, samples can be added one at a time e.g. while reading a feed.
+pub struct Inference {
+    options: InferOptions,
+    root: Summary,
+}
+
+impl Inference {
+    pub fn new(options: InferOptions) -> Self {
+        Self {
+            options,
+            root: Summary::default(),
+        }
+    }
+
+    pub fn add(&mut self, sample: &Value) {
+        let max_strings = self.options.max_enum_values;
+        self.root.add(sample, max_strings);
+    }
+
+    /// The schema of all samples, a schema without any samples is `false`.
+    pub fn finish(self) -> JsonSchema {
+        if self.root.count == 0 {
+            return JsonSchema::Bool(false);
+        }
+        let mut builder = Builder::new(&self.options, self.options.extract_defs);
+        if builder.extract {
+            builder.count_shapes(&self.root);
+        }
+        let mut keywords = builder.keywords(&self.root, "root");
+        if !builder.defs.is_empty() {
+            keywords.defs = Some(DefsKeyword {
+                map: builder.defs.into_iter().collect(),
+            });
+        }
+        JsonSchema::Object(keywords)
+    }
+}
+
+/// The values seen at one location.
+#[derive(Default)]
+struct Summary {
+    count: usize,
+    nulls: usize,
+    booleans: usize,
+    integers: usize,
+    /// numbers with a fraction.
+    numbers: usize,
+    minimum: Option<Number>,
+    maximum: Option<Number>,
+    strings: usize,
+    /// the distinct strings and how often they were seen, `None` once there are too many.
+    values: Option<BTreeMap<String, usize>>,
+    /// the formats all strings matched so far.
+    formats: Vec<&'static str>,
+    arrays: usize,
+    items: Option<Box<Summary>>,
+    objects: usize,
+    properties: BTreeMap<String, Summary>,
+}
+
+impl Summary {
+    fn add(&mut self, value: &Value, max_strings: usize) {
+        self.count += 1;
+        match value {
+            Value::Null => self.nulls += 1,
+            Value::Bool(_) => self.booleans += 1,
+            Value::Number(number) => {
+                match number.is_f64() && number.as_f64().is_some_and(|f| f.fract() != 0.0) {
+                    true => self.numbers += 1,
+                    false => self.integers += 1,
+                }
+                let extend = |bound: &mut Option<Number>, ordering| match bound {
+                    Some(bound) if compare_numbers(number, bound) != Some(ordering) => {}
+                    _ => *bound = Some(number.clone()),
+                };
+                extend(&mut self.minimum, Ordering::Less);
+                extend(&mut self.maximum, Ordering::Greater);
+            }
+            Value::String(string) => {
+                if self.strings == 0 {
+                    self.values = Some(BTreeMap::new());
+                    self.formats = FORMATS.iter().map(|(format, _)| *format).collect();
+                }
+                self.strings += 1;
+                if let Some(values) = &mut self.values {
+                    *values.entry(string.clone()).or_default() += 1;
+                    if values.len() > max_strings {
+                        self.values = None;
+                    }
+                }
+                self.formats.retain(|format| {
+                    FORMATS
+                        .iter()
+                        .any(|(name, matches)| name == format && matches(string))
+                });
+            }
+            Value::Array(items) => {
+                self.arrays += 1;
+                let summary = self.items.get_or_insert_with(Default::default);
+                for item in items {
+                    summary.add(item, max_strings);
+                }
+            }
+            Value::Object(map) => {
+                self.objects += 1;
+                for (name, value) in map {
+                    let summary = self.properties.entry(name.clone()).or_default();
+                    summary.add(value, max_strings);
+                }
+            }
+        }
+    }
+}
+
+struct Builder<'o> {
+    options: &'o InferOptions,
+    /// whether shapes that occur more than once are moved to `$defs`.
+    extract: bool,
+    /// how often each object shape occurs, by its schema.
+    shapes: HashMap<String, usize>,
+    defs: BTreeMap<String, JsonSchema>,
+    /// the names of the shapes moved to `$defs`.
+    names: HashMap<String, String>,
+}
+
+impl<'o> Builder<'o> {
+    fn new(options: &'o InferOptions, extract: bool) -> Self {
+        Self {
+            options,
+            extract,
+            shapes: HashMap::new(),
+            defs: BTreeMap::new(),
+            names: HashMap::new(),
+        }
+    }
+
+    /// The schema of a summary with all subschemas inline, as a key to compare shapes.
+    fn shape(&self, summary: &Summary) -> String {
+        let keywords = Builder::new(self.options, false).keywords(summary, "");
+        serde_json::to_value(keywords)
+            .map(|value| value.to_string())
+            .unwrap_or_default()
+    }
+
+    fn count_shapes(&mut self, summary: &Summary) {
+        let subschemas = summary.properties.values().chain(summary.items.as_deref());
+        for subschema in subschemas {
+            self.count_shapes(subschema);
+            if is_shape(subschema) {
+                *self.shapes.entry(self.shape(subschema)).or_default() += 1;
+            }
+        }
+    }
+
+    /// The schema at a location, a `$ref` to `$defs` for shapes that occur more than once.
+    fn schema(&mut self, summary: &Summary, name: &str) -> JsonSchema {
+        if !self.extract || !is_shape(summary) {
+            return JsonSchema::Object(self.keywords(summary, name));
+        }
+        let shape = self.shape(summary);
+        if self.shapes.get(&shape).copied().unwrap_or_default() < 2 {
+            return JsonSchema::Object(self.keywords(summary, name));
+        }
+        let name = match self.names.get(&shape) {
+            Some(name) => name.clone(),
+            None => {
+                let mut unique = name.to_owned();
+                for i in 2.. {
+                    if !self.defs.contains_key(&unique) {
+                        break;
+                    }
+                    unique = format!("{name}{i}");
+                }
+                self.names.insert(shape, unique.clone());
+                // reserve the name before the subschemas of the shape pick theirs
+                self.defs.insert(unique.clone(), JsonSchema::Bool(true));
+                let keywords = self.keywords(summary, &unique);
+                self.defs
+                    .insert(unique.clone(), JsonSchema::Object(keywords));
+                unique
+            }
+        };
+        JsonSchema::Ref {
+            ref_: JsonPointer::Relative(format!("#/$defs/{}", escape(&name))),
+        }
+    }
+
+    /// The keywords of a summary, `name` is used for the shapes of its subschemas in `$defs`.
+    fn keywords(&mut self, summary: &Summary, name: &str) -> Keywords {
+        let mut keywords = Keywords::default();
+        let mut types = vec![];
+        if summary.nulls > 0 {
+            types.push("null");
+        }
+        if summary.booleans > 0 {
+            types.push("boolean");
+        }
+        match (summary.integers, summary.numbers) {
+            (0, 0) => {}
+            (_, 0) => types.push("integer"),
+            _ => types.push("number"),
+        }
+        if summary.strings > 0 {
+            types.push("string");
+        }
+        if summary.arrays > 0 {
+            types.push("array");
+        }
+        if summary.objects > 0 {
+            types.push("object");
+        }
+        keywords.type_ = match &types[..] {
+            [] => None,
+            [ty] => Some(TypeKeyword::Single(ty.to_string())),
+            types => Some(TypeKeyword::Multiple(
+                types.iter().map(ToString::to_string).collect(),
+            )),
+        };
+
+        if self.options.numeric_bounds && summary.integers + summary.numbers > 0 {
+            keywords.min = summary
+                .minimum
+                .clone()
+                .map(|n| MinimumKeyword(Data::Value(n)));
+            keywords.max = summary
+                .maximum
+                .clone()
+                .map(|n| MaximumKeyword(Data::Value(n)));
+        }
+        // an enum replaces the type of the strings, it only applies if they are all there is
+        let only_strings = summary.strings + summary.nulls == summary.count;
+        match &summary.values {
+            Some(values) if only_strings && values.len() * 2 <= summary.strings => {
+                let mut values: Vec<Value> = values.keys().cloned().map(Value::String).collect();
+                if summary.nulls > 0 {
+                    values.push(Value::Null);
+                }
+                keywords.enum_ = Some(EnumKeyword(Data::Value(values)));
+            }
+            _ if self.options.formats && summary.strings > 0 => {
+                keywords.format = summary
+                    .formats
+                    .first()
+                    .map(|format| FormatKeyword(format.to_string()));
+            }
+            _ => {}
+        }
+
+        if summary.objects > 0 {
+            let required: Vec<String> = summary
+                .properties
+                .iter()
+                .filter(|(_, property)| {
+                    property.count as f64 >= self.options.required_ratio * summary.objects as f64
+                })
+                .map(|(property, _)| property.clone())
+                .collect();
+            if !required.is_empty() {
+                keywords.required = Some(RequiredKeyword(required));
+            }
+            if !summary.properties.is_empty() {
+                let map = summary
+                    .properties
+                    .iter()
+                    .map(|(property, summary)| (property.clone(), self.schema(summary, property)))
+                    .collect();
+                keywords.props = Some(PropertiesKeyword { map });
+            }
+        }
+        if let Some(items) = &summary.items {
+            let items = self.schema(items, &format!("{name}Item"));
+            keywords.items = Some(ItemsKeyword(Box::new(items)));
+        }
+        keywords
+    }
+}
+
+/// Objects with properties that are worth a name of their own.
+fn is_shape(summary: &Summary) -> bool {
+    summary.objects > 0 && summary.objects == summary.count && !summary.properties.is_empty()
+}
+
+type FormatCheck = (&'static str, fn(&str) -> bool);
+
+/// The formats that are detected, in order of preference.
+const FORMATS: [FormatCheck; 4] = [
+    ("date-time", is_date_time),
+    ("date", is_date),
+    ("uuid", is_uuid),
+    ("uri", is_uri),
+];
+
+fn digits(s: &str, len: usize) -> bool {
+    s.len() == len && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn is_date(s: &str) -> bool {
+    let parts: Vec<&str> = s.split('-').collect();
+    matches!(&parts[..], [year, month, day] if digits(year, 4) && digits(month, 2) && digits(day, 2))
+        && matches!(s[5..7].parse::<u8>(), Ok(1..=12))
+        && matches!(s[8..10].parse::<u8>(), Ok(1..=31))
+}
+
+fn is_date_time(s: &str) -> bool {
+    let Some((date, time)) = s.split_once(['T', 't']) else {
+        return false;
+    };
+    let (time, offset) = match time.find(['Z', 'z', '+', '-']) {
+        Some(i) => time.split_at(i),
+        None => return false,
+    };
+    let time = time.split_once('.').map_or(time, |(time, fraction)| {
+        match !fraction.is_empty() && fraction.bytes().all(|b| b.is_ascii_digit()) {
+            true => time,
+            false => "",
+        }
+    });
+    let hms: Vec<&str> = time.split(':').collect();
+    let offset_valid = match offset {
+        "Z" | "z" => true,
+        offset => {
+            let hm: Vec<&str> = offset[1..].split(':').collect();
+            matches!(&hm[..], [h, m] if digits(h, 2) && digits(m, 2))
+        }
+    };
+    is_date(date)
+        && matches!(&hms[..], [h, m, sec] if digits(h, 2) && digits(m, 2) && digits(sec, 2))
+        && offset_valid
+}
+
+fn is_uuid(s: &str) -> bool {
+    let parts: Vec<&str> = s.split('-').collect();
+    let lengths = [8, 4, 4, 4, 12];
+    parts.len() == lengths.len()
+        && parts
+            .iter()
+            .zip(lengths)
+            .all(|(part, len)| part.len() == len && part.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+fn is_uri(s: &str) -> bool {
+    // a colon alone, like in `key:value`, does not make a uri
+    url::Url::parse(s)
+        .is_ok_and(|url| s.contains("://") || url.scheme() == "urn" || url.scheme() == "mailto")
+}
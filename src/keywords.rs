@@ -7,11 +7,15 @@ mod any_of;
 mod comment;
 mod constant;
 mod contains;
+mod data;
 mod default;
 mod definitions;
 mod defs;
+mod dependent_required;
+mod dependent_schemas;
 mod deprecated;
 mod description;
+mod discriminator;
 mod dynamic_anchor;
 mod dynamic_ref;
 mod r#enum;
@@ -57,11 +61,15 @@ pub use any_of::AnyOfKeyword;
 pub use comment::CommentKeyword;
 pub use constant::ConstantKeyword;
 pub use contains::ContainsKeyword;
+pub use data::{Data, DataReference};
 pub use default::DefaultKeyword;
 pub use definitions::DefinitionsKeyword;
 pub use defs::DefsKeyword;
+pub use dependent_required::DependentRequiredKeyword;
+pub use dependent_schemas::DependentSchemasKeyword;
 pub use deprecated::DeprecatedKeyword;
 pub use description::DescriptionKeyword;
+pub use discriminator::DiscriminatorKeyword;
 pub use dynamic_anchor::DynamicAnchorKeyword;
 pub use dynamic_ref::DynamicRefKeyword;
 pub use examples::ExamplesKeyword;
@@ -102,8 +110,11 @@ pub use write_only::WriteOnlyKeyword;
 
 use crate::{
     context::{Compiler, Context},
-    validator::Validator,
+    patch::Patcher,
+    schema::JsonSchema,
+    tree::Lowering,
 };
+use serde_json::Value;
 use url::Url;
 
 /// A trait that all keywords need to implement, it is responsible to taking the
@@ -114,20 +125,18 @@ use url::Url;
 ///     be replaced with their proper schema object
 /// 2. The patch phase
 ///     keywords are allowed to alter the document before validation begins, this allows keywords like "default" to
-///     patch the document before
-/// 3. The validate phase
-///     keywords are tested against the relevant value individually, keywords can return one of 4 values
-///         - Pass
-///         - Fail(reason)
-///         - N/A
-///         - Defer(keywords)
-///     if any keywords return Fail, the schema does not validate. All keywords are checked in order to provide better error
-///     handling. Deffering allows keywords to refer to the results of dependents, although it generally wiser to factor out
-///     dependent keywords.
+///     patch the document before it is validated (see [`crate::patch`]). Keywords with subschemas pass the parts of
+///     the document they apply to on to the patcher.
+/// 3. The lower phase
+///     keywords are lowered into the ops of an evaluation tree (see [`crate::tree`]). Only keywords that are present
+///     produce ops, subschemas are referred to by their index in the tree and anything that can be prepared ahead of
+///     time like patterns is prepared here. Keywords that depend on their siblings, like "additionalProperties" on
+///     "properties", read them while lowering. The validator then evaluates the ops against the document, keywords
+///     like "unevaluatedProperties" rely on the annotations of the ops before them which is why they are lowered last.
 pub trait Keyword {
     fn compile(&mut self, compiler: &mut Compiler);
-    fn patch(&self, validator: Validator);
-    fn validate(&self, validator: Validator);
+    fn patch(&self, patcher: &mut Patcher, value: &mut Value);
+    fn lower(&self, lowering: &mut Lowering);
 }
 
 macro_rules! def_keywords {
@@ -149,6 +158,22 @@ macro_rules! def_keywords {
                     }
                 )*
             }
+
+            pub fn patch(&self, patcher: &mut Patcher, value: &mut Value) {
+                $(
+                    if let Some(keyword) = &self.$ident {
+                        Keyword::patch(keyword, patcher, value);
+                    }
+                )*
+            }
+
+            pub fn lower(&self, lowering: &mut Lowering) {
+                $(
+                    if let Some(keyword) = &self.$ident {
+                        Keyword::lower(keyword, lowering);
+                    }
+                )*
+            }
         }
     };
 }
@@ -164,8 +189,11 @@ def_keywords!(
     default: DefaultKeyword = "default"
     definitions: DefinitionsKeyword = "definitions"
     defs: DefsKeyword = "$defs"
+    dependent_required: DependentRequiredKeyword = "dependentRequired"
+    dependent_schemas: DependentSchemasKeyword = "dependentSchemas"
     deprecated: DeprecatedKeyword = "deprecated"
     description: DescriptionKeyword = "description"
+    discriminator: DiscriminatorKeyword = "discriminator"
     dynamic_anchor: DynamicAnchorKeyword = "$dynamicAnchor"
     dynamic_ref: DynamicRefKeyword = "$dynamicRef"
     enum_: EnumKeyword = "enum"
@@ -200,17 +228,87 @@ def_keywords!(
     title: TitleKeyword = "title"
     type_: TypeKeyword = "type"
     unevaluated_items: UnevaluatedItemsKeyword = "unevaluatedItems"
+    unevaluated_props: UnevaluatedPropertiesKeyword = "unevaluatedProperties"
     unique_items: UniqueItemsKeyword = "uniqueItems"
     write_only: WriteOnlyKeyword = "writeOnly"
 );
 
-pub enum IllogicalSchema {}
+pub use crate::lint::IllogicalSchema;
 
 impl Keywords {
-    /// test whether or not the keywords in the schema form a logical schema.
-    pub fn is_logical(&self) -> bool {
-        match self {
-            _ => false,
+    /// All direct subschemas of this schema paired with their keyword location relative to it,
+    /// e.g. `/properties/name` or `/items`. Map keywords are listed in key order.
+    pub fn subschemas(&self) -> Vec<(String, &JsonSchema)> {
+        let mut subschemas = vec![];
+        let maps = [
+            ("$defs", self.defs.as_ref().map(|k| &k.map)),
+            ("definitions", self.definitions.as_ref().map(|k| &k.map)),
+            (
+                "dependentSchemas",
+                self.dependent_schemas.as_ref().map(|k| &k.map),
+            ),
+            ("properties", self.props.as_ref().map(|k| &k.map)),
+            ("patternProperties", self.pat_props.as_ref().map(|k| &k.map)),
+        ];
+        for (keyword, map) in maps {
+            let mut entries: Vec<_> = map.into_iter().flatten().collect();
+            entries.sort_by_key(|(k, _)| *k);
+            for (k, schema) in entries {
+                subschemas.push((format!("/{keyword}/{k}"), schema));
+            }
+        }
+        let lists = [
+            ("allOf", self.all_of.as_ref().map(|k| &k.0)),
+            ("anyOf", self.any_of.as_ref().map(|k| &k.0)),
+            ("oneOf", self.one_of.as_ref().map(|k| &k.0)),
+            ("prefixItems", self.prefix_items.as_ref().map(|k| &k.0)),
+        ];
+        for (keyword, schemas) in lists {
+            for (i, schema) in schemas.into_iter().flatten().enumerate() {
+                subschemas.push((format!("/{keyword}/{i}"), schema));
+            }
         }
+        let boxed = [
+            (
+                "additionalProperties",
+                self.additional_properties.as_ref().map(|k| &k.0),
+            ),
+            ("contains", self.contains.as_ref().map(|k| &k.0)),
+            ("items", self.items.as_ref().map(|k| &k.0)),
+            ("not", self.not.as_ref().map(|k| &k.0)),
+            ("propertyNames", self.prop_names.as_ref().map(|k| &k.0)),
+            (
+                "unevaluatedItems",
+                self.unevaluated_items.as_ref().map(|k| &k.0),
+            ),
+            (
+                "unevaluatedProperties",
+                self.unevaluated_props.as_ref().map(|k| &k.0),
+            ),
+        ];
+        for (keyword, schema) in boxed {
+            if let Some(schema) = schema {
+                subschemas.push((format!("/{keyword}"), &**schema));
+            }
+        }
+        if let Some(if_then_else) = &self.if_then_else {
+            subschemas.push(("/if".to_owned(), &*if_then_else.if_));
+            if let Some(then) = &if_then_else.then {
+                subschemas.push(("/then".to_owned(), &**then));
+            }
+            if let Some(else_) = &if_then_else.else_ {
+                subschemas.push(("/else".to_owned(), &**else_));
+            }
+        }
+        subschemas
+    }
+
+    /// test whether or not the keywords in the schema form a logical schema, that is none of the
+    /// problems found by [`Keywords::illogical`] is a contradiction.
+    pub fn is_logical(&self) -> bool {
+        !self
+            .illogical()
+            .iter()
+            .any(IllogicalSchema::is_contradiction)
     }
 }
@@ -1,63 +1,63 @@
-use crate::{
-    context::Compiler,
-    schema::JsonSchema,
-    validator::{Validator, ValidatorState},
-};
+use crate::tree::{Lowering, Op};
+use crate::{context::Compiler, patch::Patcher, schema::JsonSchema};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Deserialize, Serialize)]
 pub struct AdditionalPropertiesKeyword(pub Box<JsonSchema>);
 
 impl super::Keyword for AdditionalPropertiesKeyword {
     fn compile(&mut self, compiler: &mut Compiler) {
-        compiler.compile(&mut self.0);
+        compiler.compile_rel_key(|s| s.push_str("/additionalProperties"), &mut self.0);
     }
 
-    fn patch(&self, validator: Validator) {}
-
-    fn validate(&self, mut validator: Validator) {
-        if let Some(value) = &mut validator.value {
-            match value {
-                serde_json::Value::Null => {
-                    validator.state = ValidatorState::Fail(format!("null is not an object"))
-                }
-                serde_json::Value::Bool(_) => {
-                    validator.state = ValidatorState::Fail(format!("bool is not an object"))
-                }
-                serde_json::Value::Number(_) => {
-                    validator.state = ValidatorState::Fail(format!("number is not an object"))
-                }
-                serde_json::Value::String(_) => {
-                    validator.state = ValidatorState::Fail(format!("string is not an object"))
-                }
-                serde_json::Value::Array(_) => {
-                    validator.state = ValidatorState::Fail(format!("array is not an object"))
-                }
-                serde_json::Value::Object(map) => {
-                    for (k, v) in map {
-                        match &validator.schema {
-                            JsonSchema::Object(keywords) => {
-                                let in_props = keywords
-                                    .props
-                                    .as_ref()
-                                    .map(|props| props.map.contains_key(k))
-                                    .unwrap_or(false);
-                                if !in_props {
-                                    let in_pat_props = keywords
-                                        .pat_props
-                                        .as_ref()
-                                        .map(|pat_props| pat_props.map.keys().any(|k| false))
-                                        .unwrap_or(false);
-                                    if !in_pat_props {
-                                        // validate the values that are additional with the provided schema.
-                                    }
-                                }
-                            }
-                            _ => {}
-                        }
-                    }
-                }
+    fn patch(&self, patcher: &mut Patcher, value: &mut Value) {
+        let Value::Object(map) = value else { return };
+        let keywords = patcher.keywords();
+        let properties = keywords.and_then(|keywords| keywords.props.as_ref());
+        let patterns: Vec<Regex> = keywords
+            .and_then(|keywords| keywords.pat_props.as_ref())
+            .map(|pat_props| {
+                pat_props
+                    .map
+                    .keys()
+                    .filter_map(|pattern| Regex::new(pattern).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        for (k, v) in map.iter_mut() {
+            let in_props = properties.is_some_and(|props| props.map.contains_key(k));
+            if !in_props && !patterns.iter().any(|regex| regex.is_match(k)) {
+                patcher.child(&self.0, v);
             }
         }
     }
+
+    fn lower(&self, lowering: &mut Lowering) {
+        let keywords = lowering.keywords;
+        let mut properties: Vec<String> = keywords
+            .props
+            .as_ref()
+            .map(|props| props.map.keys().cloned().collect())
+            .unwrap_or_default();
+        properties.sort();
+        let patterns = keywords
+            .pat_props
+            .as_ref()
+            .map(|pat_props| {
+                pat_props
+                    .map
+                    .keys()
+                    .filter_map(|pattern| Regex::new(pattern).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let node = lowering.subschema(&self.0);
+        lowering.push(Op::AdditionalProperties {
+            node,
+            properties: properties.into_boxed_slice(),
+            patterns,
+        });
+    }
 }
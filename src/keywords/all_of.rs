@@ -1,5 +1,8 @@
+use crate::patch::Patcher;
 use crate::schema::JsonSchema;
+use crate::tree::{Lowering, Op};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::fmt::Write;
 
 #[derive(Deserialize, Serialize)]
@@ -11,6 +14,13 @@ impl super::Keyword for AllOfKeyword {
             compiler.compile_rel_key(|s| write!(s, "/allOf/{i}").unwrap(), schema);
         }
     }
-    fn patch(&self, validator: crate::validator::Validator) {}
-    fn validate(&self, validator: crate::validator::Validator) {}
+    fn patch(&self, patcher: &mut Patcher, value: &mut Value) {
+        for schema in &self.0 {
+            patcher.apply(schema, value);
+        }
+    }
+    fn lower(&self, lowering: &mut Lowering) {
+        let nodes = lowering.subschemas(&self.0);
+        lowering.push(Op::AllOf(nodes));
+    }
 }
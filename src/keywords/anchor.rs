@@ -6,7 +6,7 @@ pub struct AnchorKeyword(pub String);
 impl super::Keyword for AnchorKeyword {
     fn compile(&mut self, compiler: &mut crate::context::Compiler) {}
 
-    fn patch(&self, validator: crate::validator::Validator) {}
+    fn patch(&self, patcher: &mut crate::patch::Patcher, value: &mut serde_json::Value) {}
 
-    fn validate(&self, validator: crate::validator::Validator) {}
+    fn lower(&self, lowering: &mut crate::tree::Lowering) {}
 }
@@ -1,4 +1,5 @@
 use crate::schema::JsonSchema;
+use crate::tree::{Lowering, Op};
 use serde::{Deserialize, Serialize};
 use std::fmt::Write;
 
@@ -11,6 +12,9 @@ impl super::Keyword for AnyOfKeyword {
             compiler.compile_rel_key(|s| write!(s, "/anyOf/{i}").unwrap(), schema);
         }
     }
-    fn patch(&self, validator: crate::validator::Validator) {}
-    fn validate(&self, validator: crate::validator::Validator) {}
+    fn patch(&self, patcher: &mut crate::patch::Patcher, value: &mut serde_json::Value) {}
+    fn lower(&self, lowering: &mut Lowering) {
+        let nodes = lowering.subschemas(&self.0);
+        lowering.push(Op::AnyOf(nodes));
+    }
 }
@@ -1,10 +1,24 @@
+use super::Data;
+use crate::tree::{Lowering, Op};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize)]
-pub struct ConstantKeyword(pub serde_json::Value);
+pub struct ConstantKeyword(pub Data<serde_json::Value>);
 
 impl super::Keyword for ConstantKeyword {
     fn compile(&mut self, compiler: &mut crate::context::Compiler) {}
-    fn patch(&self, validator: crate::validator::Validator) {}
-    fn validate(&self, validator: crate::validator::Validator) {}
+    fn patch(&self, patcher: &mut crate::patch::Patcher, value: &mut serde_json::Value) {}
+    fn lower(&self, lowering: &mut Lowering) {
+        match &self.0 {
+            // without `$data` support a reference is an ordinary object to compare against
+            Data::Reference(reference) if !lowering.data_references() => {
+                lowering.push(Op::Const(serde_json::json!({ "$data": reference.pointer })));
+            }
+            data => {
+                if let Some(value) = lowering.data("const", data) {
+                    lowering.push(Op::Const(value.clone()));
+                }
+            }
+        }
+    }
 }
@@ -1,4 +1,5 @@
 use crate::schema::JsonSchema;
+use crate::tree::{Lowering, Op};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize)]
@@ -6,8 +7,21 @@ pub struct ContainsKeyword(pub Box<JsonSchema>);
 
 impl super::Keyword for ContainsKeyword {
     fn compile(&mut self, compiler: &mut crate::context::Compiler) {
-        compiler.compile(&mut self.0);
+        compiler.compile_rel_key(|s| s.push_str("/contains"), &mut self.0);
+    }
+    fn patch(&self, patcher: &mut crate::patch::Patcher, value: &mut serde_json::Value) {}
+    fn lower(&self, lowering: &mut Lowering) {
+        let keywords = lowering.keywords;
+        let min = keywords
+            .min_contains
+            .as_ref()
+            .and_then(|min_contains| min_contains.0.as_u64())
+            .unwrap_or(1);
+        let max = keywords
+            .max_contains
+            .as_ref()
+            .and_then(|max_contains| max_contains.0.as_u64());
+        let node = lowering.subschema(&self.0);
+        lowering.push(Op::Contains { node, min, max });
     }
-    fn patch(&self, validator: crate::validator::Validator) {}
-    fn validate(&self, validator: crate::validator::Validator) {}
 }
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The value of a keyword that may instead be a `$data` reference to a value of the instance,
+/// e.g. `"minimum": { "$data": "1/startDate" }`. References are only followed when the context
+/// enables [`data_references`](crate::context::Context::data_references).
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum Data<T> {
+    Reference(DataReference),
+    Value(T),
+}
+
+impl<T> Data<T> {
+    /// The literal value, `None` for references.
+    pub fn value(&self) -> Option<&T> {
+        match self {
+            Data::Reference(_) => None,
+            Data::Value(value) => Some(value),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct DataReference {
+    /// an absolute or relative json pointer into the instance.
+    #[serde(rename = "$data")]
+    pub pointer: String,
+}
+
+impl<'de> Deserialize<'de> for DataReference {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // a derived struct would also accept a one element array, e.g. the `enum` `["a"]`
+        let mut map = BTreeMap::<String, String>::deserialize(deserializer)?;
+        match (map.remove("$data"), map.is_empty()) {
+            (Some(pointer), true) => Ok(DataReference { pointer }),
+            _ => Err(serde::de::Error::custom(
+                "expected an object with only `$data`",
+            )),
+        }
+    }
+}
@@ -1,4 +1,3 @@
-use crate::validator::Validator;
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize)]
@@ -6,6 +5,6 @@ pub struct DefaultKeyword(pub serde_json::Value);
 
 impl super::Keyword for DefaultKeyword {
     fn compile(&mut self, compiler: &mut crate::context::Compiler) {}
-    fn patch(&self, validator: Validator) {}
-    fn validate(&self, validator: Validator) {}
+    fn patch(&self, patcher: &mut crate::patch::Patcher, value: &mut serde_json::Value) {}
+    fn lower(&self, lowering: &mut crate::tree::Lowering) {}
 }
@@ -14,6 +14,6 @@ impl super::Keyword for DefsKeyword {
             compiler.compile_rel_key(|s| write!(s, "/$defs/{k}").unwrap(), v);
         }
     }
-    fn patch(&self, validator: crate::validator::Validator) {}
-    fn validate(&self, validator: crate::validator::Validator) {}
+    fn patch(&self, patcher: &mut crate::patch::Patcher, value: &mut serde_json::Value) {}
+    fn lower(&self, lowering: &mut crate::tree::Lowering) {}
 }
@@ -0,0 +1,20 @@
+use crate::tree::{Lowering, Op};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Deserialize, Serialize)]
+pub struct DependentRequiredKeyword(pub HashMap<String, Vec<String>>);
+
+impl super::Keyword for DependentRequiredKeyword {
+    fn compile(&mut self, compiler: &mut crate::context::Compiler) {}
+    fn patch(&self, patcher: &mut crate::patch::Patcher, value: &mut serde_json::Value) {}
+    fn lower(&self, lowering: &mut Lowering) {
+        let mut dependencies: Vec<_> = self
+            .0
+            .iter()
+            .map(|(k, required)| (k.clone(), required.clone().into_boxed_slice()))
+            .collect();
+        dependencies.sort_by(|(l, _), (r, _)| l.cmp(r));
+        lowering.push(Op::DependentRequired(dependencies.into_boxed_slice()));
+    }
+}
@@ -0,0 +1,36 @@
+use crate::patch::Patcher;
+use crate::schema::JsonSchema;
+use crate::tree::{Lowering, Op};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::HashMap, fmt::Write};
+
+#[derive(Deserialize, Serialize, Default)]
+pub struct DependentSchemasKeyword {
+    #[serde(flatten)]
+    pub map: HashMap<String, JsonSchema>,
+}
+
+impl super::Keyword for DependentSchemasKeyword {
+    fn compile(&mut self, compiler: &mut crate::context::Compiler) {
+        for (k, v) in &mut self.map {
+            compiler.compile_rel_key(|s| write!(s, "/dependentSchemas/{k}").unwrap(), v);
+        }
+    }
+    fn patch(&self, patcher: &mut Patcher, value: &mut Value) {
+        for (k, schema) in &self.map {
+            if value.get(k).is_some() {
+                patcher.apply(schema, value);
+            }
+        }
+    }
+    fn lower(&self, lowering: &mut Lowering) {
+        let mut dependencies: Vec<_> = self
+            .map
+            .iter()
+            .map(|(k, schema)| (k.clone(), lowering.subschema(schema)))
+            .collect();
+        dependencies.sort_by(|(l, _), (r, _)| l.cmp(r));
+        lowering.push(Op::DependentSchemas(dependencies.into_boxed_slice()));
+    }
+}
@@ -1,10 +1,10 @@
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize)]
-pub struct DeprecatedKeyword;
+pub struct DeprecatedKeyword(pub bool);
 
 impl super::Keyword for DeprecatedKeyword {
     fn compile(&mut self, compiler: &mut crate::context::Compiler) {}
-    fn patch(&self, validator: crate::validator::Validator) {}
-    fn validate(&self, validator: crate::validator::Validator) {}
+    fn patch(&self, patcher: &mut crate::patch::Patcher, value: &mut serde_json::Value) {}
+    fn lower(&self, lowering: &mut crate::tree::Lowering) {}
 }
@@ -5,6 +5,6 @@ pub struct DescriptionKeyword(pub String);
 
 impl super::Keyword for DescriptionKeyword {
     fn compile(&mut self, compiler: &mut crate::context::Compiler) {}
-    fn patch(&self, validator: crate::validator::Validator) {}
-    fn validate(&self, validator: crate::validator::Validator) {}
+    fn patch(&self, patcher: &mut crate::patch::Patcher, value: &mut serde_json::Value) {}
+    fn lower(&self, lowering: &mut crate::tree::Lowering) {}
 }
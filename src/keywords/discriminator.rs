@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The `discriminator` of OpenAPI, names the property that tells the schemas of a `oneOf` apart.
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscriminatorKeyword {
+    pub property_name: String,
+    /// values of the property mapped to a reference to their schema.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub mapping: BTreeMap<String, String>,
+}
+
+impl super::Keyword for DiscriminatorKeyword {
+    fn compile(&mut self, compiler: &mut crate::context::Compiler) {}
+    fn patch(&self, patcher: &mut crate::patch::Patcher, value: &mut serde_json::Value) {}
+    fn lower(&self, lowering: &mut crate::tree::Lowering) {}
+}
@@ -1,11 +1,24 @@
 use crate::pointer::JsonPointer;
+use crate::tree::{Lowering, Op};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize)]
 pub struct DynamicRefKeyword(pub JsonPointer);
 
 impl super::Keyword for DynamicRefKeyword {
-    fn compile(&mut self, compiler: &mut crate::context::Compiler) {}
-    fn patch(&self, validator: crate::validator::Validator) {}
-    fn validate(&self, validator: crate::validator::Validator) {}
+    fn compile(&mut self, compiler: &mut crate::context::Compiler) {
+        // store the reference as an absolute url so it can be followed once the schema is compiled.
+        if let Ok(url) = compiler.resolve_pointer(&self.0) {
+            compiler.defer(url.clone());
+            self.0 = JsonPointer::Absolute(url);
+        }
+    }
+    fn patch(&self, patcher: &mut crate::patch::Patcher, value: &mut serde_json::Value) {}
+    fn lower(&self, lowering: &mut Lowering) {
+        // dynamic scopes are not tracked, the reference is followed to its initial target
+        if let JsonPointer::Absolute(url) = &self.0 {
+            let node = lowering.url(url);
+            lowering.push(Op::DynamicRef(node));
+        }
+    }
 }
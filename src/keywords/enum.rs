@@ -1,10 +1,16 @@
+use super::Data;
+use crate::tree::{Lowering, Op};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize)]
-pub struct EnumKeyword(pub Vec<serde_json::Value>);
+pub struct EnumKeyword(pub Data<Vec<serde_json::Value>>);
 
 impl super::Keyword for EnumKeyword {
     fn compile(&mut self, compiler: &mut crate::context::Compiler) {}
-    fn patch(&self, validator: crate::validator::Validator) {}
-    fn validate(&self, validator: crate::validator::Validator) {}
+    fn patch(&self, patcher: &mut crate::patch::Patcher, value: &mut serde_json::Value) {}
+    fn lower(&self, lowering: &mut Lowering) {
+        if let Some(values) = lowering.data("enum", &self.0) {
+            lowering.push(Op::Enum(values.clone().into_boxed_slice()));
+        }
+    }
 }
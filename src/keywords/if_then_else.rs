@@ -1,14 +1,32 @@
+use crate::schema::JsonSchema;
+use crate::tree::{Lowering, Op};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
 pub struct IfThenElseKeyword {
-    if_: (),
-    then: (),
-    else_: (),
+    #[serde(rename = "if")]
+    pub if_: Box<JsonSchema>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub then: Option<Box<JsonSchema>>,
+    #[serde(rename = "else", skip_serializing_if = "Option::is_none")]
+    pub else_: Option<Box<JsonSchema>>,
 }
 
 impl super::Keyword for IfThenElseKeyword {
-    fn compile(&mut self, compiler: &mut crate::context::Compiler) {}
-    fn patch(&self, validator: crate::validator::Validator) {}
-    fn validate(&self, validator: crate::validator::Validator) {}
+    fn compile(&mut self, compiler: &mut crate::context::Compiler) {
+        compiler.compile_rel_key(|s| s.push_str("/if"), &mut self.if_);
+        if let Some(then) = &mut self.then {
+            compiler.compile_rel_key(|s| s.push_str("/then"), then);
+        }
+        if let Some(else_) = &mut self.else_ {
+            compiler.compile_rel_key(|s| s.push_str("/else"), else_);
+        }
+    }
+    fn patch(&self, patcher: &mut crate::patch::Patcher, value: &mut serde_json::Value) {}
+    fn lower(&self, lowering: &mut Lowering) {
+        let if_ = lowering.subschema(&self.if_);
+        let then = self.then.as_ref().map(|then| lowering.subschema(then));
+        let else_ = self.else_.as_ref().map(|else_| lowering.subschema(else_));
+        lowering.push(Op::IfThenElse { if_, then, else_ });
+    }
 }
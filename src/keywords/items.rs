@@ -1,4 +1,7 @@
+use crate::patch::Patcher;
+use crate::tree::{Lowering, Op};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::schema::JsonSchema;
 
@@ -7,8 +10,28 @@ pub struct ItemsKeyword(pub Box<JsonSchema>);
 
 impl super::Keyword for ItemsKeyword {
     fn compile(&mut self, compiler: &mut crate::context::Compiler) {
-        compiler.compile(&mut self.0);
+        compiler.compile_rel_key(|s| s.push_str("/items"), &mut self.0);
+    }
+    fn patch(&self, patcher: &mut Patcher, value: &mut Value) {
+        let Value::Array(items) = value else { return };
+        let skip = patcher
+            .keywords()
+            .and_then(|keywords| keywords.prefix_items.as_ref())
+            .map(|prefix_items| prefix_items.0.len())
+            .unwrap_or(0);
+        for item in items.iter_mut().skip(skip) {
+            patcher.child(&self.0, item);
+        }
+    }
+    fn lower(&self, lowering: &mut Lowering) {
+        // items only applies to the items following prefixItems
+        let skip = lowering
+            .keywords
+            .prefix_items
+            .as_ref()
+            .map(|prefix_items| prefix_items.0.len())
+            .unwrap_or(0);
+        let node = lowering.subschema(&self.0);
+        lowering.push(Op::Items { node, skip });
     }
-    fn patch(&self, validator: crate::validator::Validator) {}
-    fn validate(&self, validator: crate::validator::Validator) {}
 }
@@ -1,10 +1,23 @@
+use super::Data;
+use crate::tree::{Lowering, Op};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize)]
-pub struct MaxItemsKeyword(pub serde_json::Number);
+pub struct MaxItemsKeyword(pub Data<serde_json::Number>);
 
 impl super::Keyword for MaxItemsKeyword {
     fn compile(&mut self, compiler: &mut crate::context::Compiler) {}
-    fn patch(&self, validator: crate::validator::Validator) {}
-    fn validate(&self, validator: crate::validator::Validator) {}
+    fn patch(&self, patcher: &mut crate::patch::Patcher, value: &mut serde_json::Value) {}
+    fn lower(&self, lowering: &mut Lowering) {
+        let Some(number) = lowering.data("maxItems", &self.0) else {
+            return;
+        };
+        match number.as_u64() {
+            Some(limit) => lowering.push(Op::MaxItems(limit)),
+            None => lowering.push(Op::Invalid(
+                "maxItems",
+                format!("{number} is not a valid limit"),
+            )),
+        }
+    }
 }
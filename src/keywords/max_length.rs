@@ -1,10 +1,23 @@
+use super::Data;
+use crate::tree::{Lowering, Op};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize)]
-pub struct MaxLengthKeyword(pub serde_json::Number);
+pub struct MaxLengthKeyword(pub Data<serde_json::Number>);
 
 impl super::Keyword for MaxLengthKeyword {
     fn compile(&mut self, compiler: &mut crate::context::Compiler) {}
-    fn patch(&self, validator: crate::validator::Validator) {}
-    fn validate(&self, validator: crate::validator::Validator) {}
+    fn patch(&self, patcher: &mut crate::patch::Patcher, value: &mut serde_json::Value) {}
+    fn lower(&self, lowering: &mut Lowering) {
+        let Some(number) = lowering.data("maxLength", &self.0) else {
+            return;
+        };
+        match number.as_u64() {
+            Some(limit) => lowering.push(Op::MaxLength(limit)),
+            None => lowering.push(Op::Invalid(
+                "maxLength",
+                format!("{number} is not a valid limit"),
+            )),
+        }
+    }
 }
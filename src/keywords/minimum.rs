@@ -1,10 +1,16 @@
+use super::Data;
+use crate::tree::{Lowering, Op};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize)]
-pub struct MinimumKeyword(pub serde_json::Number);
+pub struct MinimumKeyword(pub Data<serde_json::Number>);
 
 impl super::Keyword for MinimumKeyword {
     fn compile(&mut self, compiler: &mut crate::context::Compiler) {}
-    fn patch(&self, validator: crate::validator::Validator) {}
-    fn validate(&self, validator: crate::validator::Validator) {}
+    fn patch(&self, patcher: &mut crate::patch::Patcher, value: &mut serde_json::Value) {}
+    fn lower(&self, lowering: &mut Lowering) {
+        if let Some(number) = lowering.data("minimum", &self.0) {
+            lowering.push(Op::Minimum(number.clone()));
+        }
+    }
 }
@@ -1,12 +1,16 @@
-use std::ops::Mul;
-
+use super::Data;
+use crate::tree::{Lowering, Op};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize)]
-pub struct MultipleOfKeyword(pub serde_json::Number);
+pub struct MultipleOfKeyword(pub Data<serde_json::Number>);
 
 impl super::Keyword for MultipleOfKeyword {
     fn compile(&mut self, compiler: &mut crate::context::Compiler) {}
-    fn patch(&self, validator: crate::validator::Validator) {}
-    fn validate(&self, validator: crate::validator::Validator) {}
+    fn patch(&self, patcher: &mut crate::patch::Patcher, value: &mut serde_json::Value) {}
+    fn lower(&self, lowering: &mut Lowering) {
+        if let Some(number) = lowering.data("multipleOf", &self.0) {
+            lowering.push(Op::MultipleOf(number.clone()));
+        }
+    }
 }
@@ -1,4 +1,5 @@
 use crate::schema::JsonSchema;
+use crate::tree::{Lowering, Op};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize)]
@@ -6,8 +7,11 @@ pub struct NotKeyword(pub Box<JsonSchema>);
 
 impl super::Keyword for NotKeyword {
     fn compile(&mut self, compiler: &mut crate::context::Compiler) {
-        compiler.compile(&mut self.0);
+        compiler.compile_rel_key(|s| s.push_str("/not"), &mut self.0);
+    }
+    fn patch(&self, patcher: &mut crate::patch::Patcher, value: &mut serde_json::Value) {}
+    fn lower(&self, lowering: &mut Lowering) {
+        let node = lowering.subschema(&self.0);
+        lowering.push(Op::Not(node));
     }
-    fn patch(&self, validator: crate::validator::Validator) {}
-    fn validate(&self, validator: crate::validator::Validator) {}
 }
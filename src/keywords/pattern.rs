@@ -1,10 +1,19 @@
+use super::Data;
+use crate::tree::{Lowering, Op};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize)]
-pub struct PatternKeyword(pub String);
+pub struct PatternKeyword(pub Data<String>);
 
 impl super::Keyword for PatternKeyword {
     fn compile(&mut self, compiler: &mut crate::context::Compiler) {}
-    fn patch(&self, validator: crate::validator::Validator) {}
-    fn validate(&self, validator: crate::validator::Validator) {}
+    fn patch(&self, patcher: &mut crate::patch::Patcher, value: &mut serde_json::Value) {}
+    fn lower(&self, lowering: &mut Lowering) {
+        let Some(pattern) = lowering.data("pattern", &self.0) else {
+            return;
+        };
+        if let Some(regex) = lowering.regex("pattern", pattern) {
+            lowering.push(Op::Pattern(regex));
+        }
+    }
 }
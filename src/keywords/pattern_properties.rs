@@ -1,5 +1,9 @@
+use crate::patch::Patcher;
 use crate::schema::JsonSchema;
+use crate::tree::{Lowering, Op};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::{collections::HashMap, fmt::Write};
 
 #[derive(Deserialize, Serialize)]
@@ -14,6 +18,26 @@ impl super::Keyword for PatternPropertiesKeyword {
             compiler.compile_rel_key(|s| write!(s, "/patternProperties/{k}").unwrap(), v);
         }
     }
-    fn patch(&self, validator: crate::validator::Validator) {}
-    fn validate(&self, validator: crate::validator::Validator) {}
+    fn patch(&self, patcher: &mut Patcher, value: &mut Value) {
+        let Value::Object(map) = value else { return };
+        for (pattern, schema) in &self.map {
+            let Ok(regex) = Regex::new(pattern) else {
+                continue;
+            };
+            for (k, v) in map.iter_mut() {
+                if regex.is_match(k) {
+                    patcher.child(schema, v);
+                }
+            }
+        }
+    }
+    fn lower(&self, lowering: &mut Lowering) {
+        let mut patterns = vec![];
+        for (pattern, schema) in &self.map {
+            if let Some(regex) = lowering.regex("patternProperties", pattern) {
+                patterns.push((regex, lowering.subschema(schema)));
+            }
+        }
+        lowering.push(Op::PatternProperties(patterns.into_boxed_slice()));
+    }
 }
@@ -1,5 +1,8 @@
+use crate::patch::Patcher;
 use crate::schema::JsonSchema;
+use crate::tree::{Lowering, Op};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::fmt::Write;
 
 #[derive(Deserialize, Serialize)]
@@ -11,6 +14,14 @@ impl super::Keyword for PrefixItemsKeyword {
             compiler.compile_rel_key(|s| write!(s, "/prefixItems/{i}").unwrap(), schema);
         }
     }
-    fn patch(&self, validator: crate::validator::Validator) {}
-    fn validate(&self, validator: crate::validator::Validator) {}
+    fn patch(&self, patcher: &mut Patcher, value: &mut Value) {
+        let Value::Array(items) = value else { return };
+        for (schema, item) in self.0.iter().zip(items.iter_mut()) {
+            patcher.child(schema, item);
+        }
+    }
+    fn lower(&self, lowering: &mut Lowering) {
+        let nodes = lowering.subschemas(&self.0);
+        lowering.push(Op::PrefixItems(nodes));
+    }
 }
@@ -1,5 +1,8 @@
+use crate::patch::Patcher;
 use crate::schema::JsonSchema;
+use crate::tree::{Lowering, Op};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::{collections::HashMap, fmt::Write};
 
 #[derive(Deserialize, Serialize, Default)]
@@ -14,6 +17,28 @@ impl super::Keyword for PropertiesKeyword {
             compiler.compile_rel_key(|s| write!(s, "/properties/{k}").unwrap(), v);
         }
     }
-    fn patch(&self, validator: crate::validator::Validator) {}
-    fn validate(&self, validator: crate::validator::Validator) {}
+    fn patch(&self, patcher: &mut Patcher, value: &mut Value) {
+        let Value::Object(map) = value else { return };
+        for (k, schema) in &self.map {
+            match map.get_mut(k) {
+                Some(v) => patcher.child(schema, v),
+                None if patcher.defaults => {
+                    if let Some(mut default) = patcher.default(schema) {
+                        patcher.child(schema, &mut default);
+                        map.insert(k.clone(), default);
+                    }
+                }
+                None => {}
+            }
+        }
+    }
+    fn lower(&self, lowering: &mut Lowering) {
+        let mut properties: Vec<_> = self
+            .map
+            .iter()
+            .map(|(k, schema)| (k.clone(), lowering.subschema(schema)))
+            .collect();
+        properties.sort_by(|(l, _), (r, _)| l.cmp(r));
+        lowering.push(Op::Properties(properties.into_boxed_slice()));
+    }
 }
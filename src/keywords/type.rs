@@ -1,4 +1,7 @@
+use crate::patch::{coerce, is_type, Patcher};
+use crate::tree::{type_mask, Lowering, Op};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Deserialize, Serialize)]
 #[serde(untagged)]
@@ -9,6 +12,25 @@ pub enum TypeKeyword {
 
 impl super::Keyword for TypeKeyword {
     fn compile(&mut self, compiler: &mut crate::context::Compiler) {}
-    fn patch(&self, validator: crate::validator::Validator) {}
-    fn validate(&self, validator: crate::validator::Validator) {}
+    fn patch(&self, patcher: &mut Patcher, value: &mut Value) {
+        let mask = self.mask();
+        if patcher.coerce && !is_type(value, mask) {
+            if let Some(coerced) = coerce(value, mask) {
+                *value = coerced;
+            }
+        }
+    }
+    fn lower(&self, lowering: &mut Lowering) {
+        lowering.push(Op::Type(self.mask()));
+    }
+}
+
+impl TypeKeyword {
+    /// The bits of the allowed types, see [`type_mask`].
+    pub fn mask(&self) -> u8 {
+        match self {
+            TypeKeyword::Single(ty) => type_mask(ty),
+            TypeKeyword::Multiple(types) => types.iter().fold(0, |mask, ty| mask | type_mask(ty)),
+        }
+    }
 }
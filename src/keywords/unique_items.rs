@@ -1,3 +1,4 @@
+use crate::tree::{Lowering, Op};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize)]
@@ -5,6 +6,10 @@ pub struct UniqueItemsKeyword(pub bool);
 
 impl super::Keyword for UniqueItemsKeyword {
     fn compile(&mut self, compiler: &mut crate::context::Compiler) {}
-    fn patch(&self, validator: crate::validator::Validator) {}
-    fn validate(&self, validator: crate::validator::Validator) {}
+    fn patch(&self, patcher: &mut crate::patch::Patcher, value: &mut serde_json::Value) {}
+    fn lower(&self, lowering: &mut Lowering) {
+        if self.0 {
+            lowering.push(Op::UniqueItems);
+        }
+    }
 }
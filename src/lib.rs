@@ -1,14 +1,30 @@
+pub mod batch;
 pub mod builder;
+pub mod canonical;
 pub mod codegen;
+pub mod compiled;
 pub mod context;
+pub mod deserialize;
+pub mod diff;
+pub mod fake;
+pub mod graph;
+pub mod infer;
 pub mod keywords;
+pub mod lint;
 pub mod macros;
+pub mod migrate;
+pub mod patch;
 pub mod pointer;
 pub mod resolver;
 pub mod schema;
+pub mod serialize;
+pub mod simplify;
+pub mod stream;
+pub mod subsumption;
+pub mod tree;
 pub mod typedef;
 pub mod validator;
 pub mod value;
 
-// #[cfg(tests)]
+#[cfg(test)]
 mod tests;
@@ -0,0 +1,625 @@
+//! Lints for schemas that can never be satisfied or likely do not mean what they say.
+//!
+//! [`Keywords::illogical`] checks the keywords of a single schema on their own, [`lint`] adds the
+//! checks that need the subschemas of a compiled [`Context`], [`lint_annotations`] validates
+//! `default` and `examples` against their schema and [`lint_document`] looks for mistakes in a
+//! schema document that are lost once it is parsed, like `required` written inside `properties`.
+
+use crate::{
+    context::Context,
+    keywords::{Data, Keywords},
+    migrate::SUBSCHEMA_MAPS,
+    patch::is_type,
+    pointer::AbsoluteJsonPointer,
+    schema::{JsonSchema, ResolvedJsonSchema},
+    tree::{SchemaTree, ARRAY, INTEGER, NUMBER, OBJECT, STRING},
+    validator::{compare_numbers, escape, is_multiple_of, json_eq, ValidationError, Validator},
+};
+use regex::Regex;
+use serde_json::{Number, Value};
+use std::{
+    cmp::Ordering,
+    fmt::{self, Write},
+};
+use url::Url;
+
+/// A problem found by the linter, `pointer` is the json pointer of the offending keyword.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IllogicalSchema {
+    /// the lower bound of numbers is above the upper bound, e.g. `minimum` > `maximum`.
+    EmptyRange {
+        pointer: String,
+        minimum: &'static str,
+        maximum: &'static str,
+    },
+    /// a minimum count or length is above its maximum, e.g. `minLength` > `maxLength`.
+    EmptyLength {
+        pointer: String,
+        minimum: &'static str,
+        maximum: &'static str,
+    },
+    /// a required property is forbidden by `properties` or `additionalProperties`.
+    ForbiddenRequired { pointer: String, property: String },
+    /// `const` is not of a type allowed by `type`.
+    ConstType { pointer: String },
+    /// an entry of `enum` fails a sibling keyword.
+    EnumValue {
+        pointer: String,
+        keyword: &'static str,
+    },
+    /// `type` and the types of the entries of `allOf` have no type in common.
+    DisjointTypes { pointer: String },
+    /// `required` written as a property of `properties`.
+    RequiredInProperties { pointer: String },
+    /// a keyword that does not apply to any of the types allowed by `type`.
+    InapplicableKeyword {
+        pointer: String,
+        keyword: &'static str,
+    },
+    /// keywords next to `$ref`, which are ignored before draft 2019-09.
+    RefSiblings {
+        pointer: String,
+        keywords: Vec<String>,
+    },
+    /// a `default` or an entry of `examples` that is not valid against its own schema.
+    InvalidAnnotation {
+        pointer: String,
+        keyword: &'static str,
+        /// the errors of validating the value, relative to the value.
+        errors: Vec<ValidationError>,
+    },
+}
+
+impl IllogicalSchema {
+    pub fn pointer(&self) -> &str {
+        match self {
+            IllogicalSchema::EmptyRange { pointer, .. }
+            | IllogicalSchema::EmptyLength { pointer, .. }
+            | IllogicalSchema::ForbiddenRequired { pointer, .. }
+            | IllogicalSchema::ConstType { pointer }
+            | IllogicalSchema::EnumValue { pointer, .. }
+            | IllogicalSchema::DisjointTypes { pointer }
+            | IllogicalSchema::RequiredInProperties { pointer }
+            | IllogicalSchema::InapplicableKeyword { pointer, .. }
+            | IllogicalSchema::RefSiblings { pointer, .. }
+            | IllogicalSchema::InvalidAnnotation { pointer, .. } => pointer,
+        }
+    }
+
+    /// whether keywords contradict each other, so some or all instances can never be valid.
+    /// Keywords that do nothing and misplaced keywords are mistakes but not contradictions.
+    pub fn is_contradiction(&self) -> bool {
+        match self {
+            IllogicalSchema::EmptyRange { .. }
+            | IllogicalSchema::EmptyLength { .. }
+            | IllogicalSchema::ForbiddenRequired { .. }
+            | IllogicalSchema::ConstType { .. }
+            | IllogicalSchema::EnumValue { .. }
+            | IllogicalSchema::DisjointTypes { .. } => true,
+            IllogicalSchema::RequiredInProperties { .. }
+            | IllogicalSchema::InapplicableKeyword { .. }
+            | IllogicalSchema::RefSiblings { .. }
+            | IllogicalSchema::InvalidAnnotation { .. } => false,
+        }
+    }
+
+    fn pointer_mut(&mut self) -> &mut String {
+        match self {
+            IllogicalSchema::EmptyRange { pointer, .. }
+            | IllogicalSchema::EmptyLength { pointer, .. }
+            | IllogicalSchema::ForbiddenRequired { pointer, .. }
+            | IllogicalSchema::ConstType { pointer }
+            | IllogicalSchema::EnumValue { pointer, .. }
+            | IllogicalSchema::DisjointTypes { pointer }
+            | IllogicalSchema::RequiredInProperties { pointer }
+            | IllogicalSchema::InapplicableKeyword { pointer, .. }
+            | IllogicalSchema::RefSiblings { pointer, .. }
+            | IllogicalSchema::InvalidAnnotation { pointer, .. } => pointer,
+        }
+    }
+}
+
+impl fmt::Display for IllogicalSchema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.pointer())?;
+        match self {
+            IllogicalSchema::EmptyRange {
+                minimum, maximum, ..
+            } => write!(f, "{minimum} is above {maximum}, no number is valid"),
+            IllogicalSchema::EmptyLength {
+                minimum, maximum, ..
+            } => write!(f, "{minimum} is greater than {maximum}"),
+            IllogicalSchema::ForbiddenRequired { property, .. } => {
+                write!(f, "required property {property} is not allowed")
+            }
+            IllogicalSchema::ConstType { .. } => write!(f, "const is not of an allowed type"),
+            IllogicalSchema::EnumValue { keyword, .. } => {
+                write!(f, "enum value is not valid against {keyword}")
+            }
+            IllogicalSchema::DisjointTypes { .. } => {
+                write!(f, "the schemas have no type in common")
+            }
+            IllogicalSchema::RequiredInProperties { .. } => {
+                write!(f, "required belongs next to properties, not inside it")
+            }
+            IllogicalSchema::InapplicableKeyword { keyword, .. } => {
+                write!(f, "{keyword} does not apply to any allowed type")
+            }
+            IllogicalSchema::RefSiblings { keywords, .. } => {
+                write!(f, "{} are ignored next to $ref", keywords.join(", "))
+            }
+            IllogicalSchema::InvalidAnnotation {
+                keyword, errors, ..
+            } => {
+                write!(f, "{keyword} value is not valid against its schema")?;
+                for error in errors {
+                    write!(f, "\n{error}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A bound of numbers by keyword, the limit if it is not a `$data` reference and whether it is
+/// exclusive.
+type Bound<'k> = (&'static str, Option<&'k Number>, bool);
+
+fn literal<T>(data: Option<&Data<T>>) -> Option<&T> {
+    data.and_then(Data::value)
+}
+
+impl Keywords {
+    /// Contradictions and mistakes between the keywords of this schema, the pointers are relative
+    /// to the schema. Subschemas are not looked at, see [`lint`].
+    pub fn illogical(&self) -> Vec<IllogicalSchema> {
+        let mut problems = vec![];
+        self.lint_ranges(&mut problems);
+        self.lint_lengths(&mut problems);
+        let mask = self.type_.as_ref().map(|type_| type_.mask());
+        if let (Some(mask), Some(constant)) = (mask, literal(self.constant.as_ref().map(|k| &k.0)))
+        {
+            if !is_type(constant, mask) {
+                problems.push(IllogicalSchema::ConstType {
+                    pointer: "/const".to_owned(),
+                });
+            }
+        }
+        if let Some(values) = literal(self.enum_.as_ref().map(|k| &k.0)) {
+            for (i, value) in values.iter().enumerate() {
+                if let Some(keyword) = self.rejects(value) {
+                    problems.push(IllogicalSchema::EnumValue {
+                        pointer: format!("/enum/{i}"),
+                        keyword,
+                    });
+                }
+            }
+        }
+        if let Some(mask) = mask {
+            for (keyword, present, types) in self.applicability() {
+                if present && types & mask == 0 {
+                    problems.push(IllogicalSchema::InapplicableKeyword {
+                        pointer: format!("/{keyword}"),
+                        keyword,
+                    });
+                }
+            }
+        }
+        problems
+    }
+
+    /// The lower and upper bounds of numbers, whether they are exclusive.
+    fn bounds(&self) -> ([Bound<'_>; 2], [Bound<'_>; 2]) {
+        (
+            [
+                ("minimum", literal(self.min.as_ref().map(|k| &k.0)), false),
+                (
+                    "exclusiveMinimum",
+                    literal(self.excl_min.as_ref().map(|k| &k.0)),
+                    true,
+                ),
+            ],
+            [
+                ("maximum", literal(self.max.as_ref().map(|k| &k.0)), false),
+                (
+                    "exclusiveMaximum",
+                    literal(self.excl_max.as_ref().map(|k| &k.0)),
+                    true,
+                ),
+            ],
+        )
+    }
+
+    fn lint_ranges(&self, problems: &mut Vec<IllogicalSchema>) {
+        let (lower, upper) = self.bounds();
+        for (minimum, min, min_exclusive) in lower {
+            for (maximum, max, max_exclusive) in upper {
+                let (Some(min), Some(max)) = (min, max) else {
+                    continue;
+                };
+                let empty = match compare_numbers(min, max) {
+                    Some(Ordering::Greater) => true,
+                    Some(Ordering::Equal) => min_exclusive || max_exclusive,
+                    _ => false,
+                };
+                if empty {
+                    problems.push(IllogicalSchema::EmptyRange {
+                        pointer: format!("/{minimum}"),
+                        minimum,
+                        maximum,
+                    });
+                }
+            }
+        }
+    }
+
+    /// The pairs of minimum and maximum counts and lengths.
+    fn limits(&self) -> [(&'static str, Option<u64>, &'static str, Option<u64>); 4] {
+        let limit = |data: Option<&Data<Number>>| literal(data).and_then(Number::as_u64);
+        [
+            (
+                "minLength",
+                limit(self.min_length.as_ref().map(|k| &k.0)),
+                "maxLength",
+                limit(self.max_length.as_ref().map(|k| &k.0)),
+            ),
+            (
+                "minItems",
+                limit(self.min_items.as_ref().map(|k| &k.0)),
+                "maxItems",
+                limit(self.max_items.as_ref().map(|k| &k.0)),
+            ),
+            (
+                "minProperties",
+                limit(self.min_props.as_ref().map(|k| &k.0)),
+                "maxProperties",
+                limit(self.max_props.as_ref().map(|k| &k.0)),
+            ),
+            (
+                "minContains",
+                self.min_contains.as_ref().and_then(|k| k.0.as_u64()),
+                "maxContains",
+                self.max_contains.as_ref().and_then(|k| k.0.as_u64()),
+            ),
+        ]
+    }
+
+    fn lint_lengths(&self, problems: &mut Vec<IllogicalSchema>) {
+        for (minimum, min, maximum, max) in self.limits() {
+            if let (Some(min), Some(max)) = (min, max) {
+                if min > max {
+                    problems.push(IllogicalSchema::EmptyLength {
+                        pointer: format!("/{minimum}"),
+                        minimum,
+                        maximum,
+                    });
+                }
+            }
+        }
+    }
+
+    /// The first sibling keyword of `enum` a value fails, only keywords without subschemas are
+    /// checked.
+    fn rejects(&self, value: &Value) -> Option<&'static str> {
+        if let Some(type_) = &self.type_ {
+            if !is_type(value, type_.mask()) {
+                return Some("type");
+            }
+        }
+        if let Some(constant) = literal(self.constant.as_ref().map(|k| &k.0)) {
+            if !json_eq(value, constant) {
+                return Some("const");
+            }
+        }
+        if let Value::Number(number) = value {
+            let (lower, upper) = self.bounds();
+            for (keyword, limit, exclusive) in lower {
+                let Some(limit) = limit else { continue };
+                match compare_numbers(number, limit) {
+                    Some(Ordering::Less) => return Some(keyword),
+                    Some(Ordering::Equal) if exclusive => return Some(keyword),
+                    _ => {}
+                }
+            }
+            for (keyword, limit, exclusive) in upper {
+                let Some(limit) = limit else { continue };
+                match compare_numbers(number, limit) {
+                    Some(Ordering::Greater) => return Some(keyword),
+                    Some(Ordering::Equal) if exclusive => return Some(keyword),
+                    _ => {}
+                }
+            }
+            if let Some(divisor) = literal(self.multiple.as_ref().map(|k| &k.0)) {
+                if !is_multiple_of(number, divisor) {
+                    return Some("multipleOf");
+                }
+            }
+        }
+        let len = match value {
+            Value::String(string) => Some((0, string.chars().count() as u64)),
+            Value::Array(items) => Some((1, items.len() as u64)),
+            Value::Object(map) => Some((2, map.len() as u64)),
+            _ => None,
+        };
+        if let Some((i, len)) = len {
+            let (minimum, min, maximum, max) = self.limits()[i];
+            if min.is_some_and(|min| len < min) {
+                return Some(minimum);
+            }
+            if max.is_some_and(|max| len > max) {
+                return Some(maximum);
+            }
+        }
+        if let (Value::String(string), Some(pattern)) =
+            (value, literal(self.pat.as_ref().map(|k| &k.0)))
+        {
+            if Regex::new(pattern).is_ok_and(|regex| !regex.is_match(string)) {
+                return Some("pattern");
+            }
+        }
+        if let (Value::Object(map), Some(required)) = (value, &self.required) {
+            if required.0.iter().any(|name| !map.contains_key(name)) {
+                return Some("required");
+            }
+        }
+        None
+    }
+
+    /// The keywords that only apply to some types, whether they are present and their types.
+    fn applicability(&self) -> [(&'static str, bool, u8); 24] {
+        let numbers = NUMBER | INTEGER;
+        [
+            ("multipleOf", self.multiple.is_some(), numbers),
+            ("minimum", self.min.is_some(), numbers),
+            ("maximum", self.max.is_some(), numbers),
+            ("exclusiveMinimum", self.excl_min.is_some(), numbers),
+            ("exclusiveMaximum", self.excl_max.is_some(), numbers),
+            ("minLength", self.min_length.is_some(), STRING),
+            ("maxLength", self.max_length.is_some(), STRING),
+            ("pattern", self.pat.is_some(), STRING),
+            ("items", self.items.is_some(), ARRAY),
+            ("prefixItems", self.prefix_items.is_some(), ARRAY),
+            ("contains", self.contains.is_some(), ARRAY),
+            ("minItems", self.min_items.is_some(), ARRAY),
+            ("maxItems", self.max_items.is_some(), ARRAY),
+            ("uniqueItems", self.unique_items.is_some(), ARRAY),
+            ("unevaluatedItems", self.unevaluated_items.is_some(), ARRAY),
+            ("properties", self.props.is_some(), OBJECT),
+            ("patternProperties", self.pat_props.is_some(), OBJECT),
+            (
+                "additionalProperties",
+                self.additional_properties.is_some(),
+                OBJECT,
+            ),
+            ("required", self.required.is_some(), OBJECT),
+            ("minProperties", self.min_props.is_some(), OBJECT),
+            ("maxProperties", self.max_props.is_some(), OBJECT),
+            ("propertyNames", self.prop_names.is_some(), OBJECT),
+            (
+                "dependentRequired",
+                self.dependent_required.is_some(),
+                OBJECT,
+            ),
+            ("dependentSchemas", self.dependent_schemas.is_some(), OBJECT),
+        ]
+    }
+}
+
+/// Lint every schema of a compiled context. Each problem is paired with the url of its document,
+/// its pointer is relative to the document.
+pub fn lint(context: &Context) -> Vec<(Url, IllogicalSchema)> {
+    each_schema(context, |_, keywords| {
+        let mut found = keywords.illogical();
+        lint_required(context, keywords, &mut found);
+        lint_all_of(context, keywords, &mut found);
+        found
+    })
+}
+
+/// Validate every `default` and every entry of `examples` of a compiled context against the
+/// schema declaring it. Each problem is paired with the url of its document like with [`lint`].
+pub fn lint_annotations(context: &Context) -> Vec<(Url, IllogicalSchema)> {
+    let tree = SchemaTree::new(context);
+    each_schema(context, |url, keywords| {
+        let Some(node) = tree.node_index(url) else {
+            return vec![];
+        };
+        let defaults = keywords
+            .default
+            .iter()
+            .map(|default| ("default", "/default".to_owned(), &default.0));
+        let examples = keywords.examples.iter().flat_map(|examples| {
+            let examples = examples.0.iter().enumerate();
+            examples.map(|(i, example)| ("examples", format!("/examples/{i}"), example))
+        });
+        let mut found = vec![];
+        for (keyword, pointer, value) in defaults.chain(examples) {
+            let mut validator = Validator::new(&tree);
+            if !validator.validate(node, value) {
+                found.push(IllogicalSchema::InvalidAnnotation {
+                    pointer,
+                    keyword,
+                    errors: validator.errors,
+                });
+            }
+        }
+        found
+    })
+}
+
+/// Run `lint` on the keywords of every schema of the context in order of their urls, the
+/// pointers it returns are made relative to the document of the schema.
+fn each_schema(
+    context: &Context,
+    mut lint: impl FnMut(&Url, &Keywords) -> Vec<IllogicalSchema>,
+) -> Vec<(Url, IllogicalSchema)> {
+    let mut urls: Vec<&Url> = context.schemas.keys().collect();
+    urls.sort();
+    let mut problems = vec![];
+    for url in urls {
+        let ResolvedJsonSchema::Object(keywords) = &context.schemas[url] else {
+            continue;
+        };
+        let fragment = url.fragment().unwrap_or_default();
+        let prefix = match AbsoluteJsonPointer::from_fragment(fragment) {
+            Ok(pointer) => pointer.to_string(),
+            // anchors are not pointers, keep them as they are
+            Err(_) => fragment.to_owned(),
+        };
+        let mut document = url.clone();
+        document.set_fragment(None);
+        for mut problem in lint(url, keywords) {
+            problem.pointer_mut().insert_str(0, &prefix);
+            problems.push((document.clone(), problem));
+        }
+    }
+    problems
+}
+
+pub(crate) fn is_false(context: &Context, schema: &JsonSchema) -> bool {
+    match schema {
+        JsonSchema::Bool(bool) => !bool,
+        JsonSchema::Resolved(url) => {
+            matches!(context.schema(url), Some(ResolvedJsonSchema::Bool(false)))
+        }
+        _ => false,
+    }
+}
+
+fn subschema_keywords<'c>(context: &'c Context, schema: &'c JsonSchema) -> Option<&'c Keywords> {
+    match schema {
+        JsonSchema::Object(keywords) => Some(keywords),
+        JsonSchema::Resolved(url) => match context.schema(url)? {
+            ResolvedJsonSchema::Object(keywords) => Some(keywords),
+            ResolvedJsonSchema::Bool(_) => None,
+        },
+        _ => None,
+    }
+}
+
+/// Required properties whose subschema is `false`, either directly or through
+/// `additionalProperties`.
+fn lint_required(context: &Context, keywords: &Keywords, problems: &mut Vec<IllogicalSchema>) {
+    let Some(required) = &keywords.required else {
+        return;
+    };
+    for (i, property) in required.0.iter().enumerate() {
+        let forbidden = match keywords.props.as_ref().and_then(|k| k.map.get(property)) {
+            Some(schema) => is_false(context, schema),
+            None => {
+                let patterns = keywords.pat_props.iter().flat_map(|k| k.map.keys());
+                let matched = patterns
+                    .filter_map(|pattern| Regex::new(pattern).ok())
+                    .any(|regex| regex.is_match(property));
+                let additional = keywords.additional_properties.as_ref();
+                !matched && additional.is_some_and(|k| is_false(context, &k.0))
+            }
+        };
+        if forbidden {
+            problems.push(IllogicalSchema::ForbiddenRequired {
+                pointer: format!("/required/{i}"),
+                property: property.clone(),
+            });
+        }
+    }
+}
+
+/// `type` and the `type`s of the entries of `allOf` that have no type in common.
+fn lint_all_of(context: &Context, keywords: &Keywords, problems: &mut Vec<IllogicalSchema>) {
+    let Some(all_of) = &keywords.all_of else {
+        return;
+    };
+    let masks: Vec<u8> = keywords
+        .type_
+        .iter()
+        .map(|type_| type_.mask())
+        .chain(all_of.0.iter().filter_map(|schema| {
+            let type_ = subschema_keywords(context, schema)?.type_.as_ref()?;
+            Some(type_.mask())
+        }))
+        .collect();
+    if masks.len() > 1 && masks.iter().fold(u8::MAX, |l, r| l & r) == 0 {
+        problems.push(IllogicalSchema::DisjointTypes {
+            pointer: "/allOf".to_owned(),
+        });
+    }
+}
+
+/// Mistakes in a schema document that are lost once it is parsed: `required` written inside
+/// `properties` and, for documents declaring a draft older than 2019-09, keywords next to `$ref`.
+pub fn lint_document(document: &Value) -> Vec<IllogicalSchema> {
+    let draft = document
+        .get("$schema")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let legacy = ["draft-03", "draft-04", "draft-06", "draft-07"]
+        .iter()
+        .any(|name| draft.contains(name));
+    let mut problems = vec![];
+    walk(document, true, &mut String::new(), legacy, &mut problems);
+    problems
+}
+
+/// Walk a `value` of the document, `schema` is whether its keys are keywords rather than the
+/// names of the entries of a map like `properties`.
+fn walk(
+    value: &Value,
+    schema: bool,
+    pointer: &mut String,
+    legacy: bool,
+    problems: &mut Vec<IllogicalSchema>,
+) {
+    let truncate = pointer.len();
+    match value {
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                let _ = write!(pointer, "/{i}");
+                walk(item, true, pointer, legacy, problems);
+                pointer.truncate(truncate);
+            }
+        }
+        Value::Object(map) if !schema => {
+            for (k, v) in map {
+                pointer.push('/');
+                pointer.push_str(&escape(k));
+                walk(v, true, pointer, legacy, problems);
+                pointer.truncate(truncate);
+            }
+        }
+        Value::Object(map) => {
+            if legacy && map.contains_key("$ref") {
+                let keywords: Vec<String> = map
+                    .keys()
+                    // definitions next to a root `$ref` are still reachable by pointer
+                    .filter(|k| !matches!(k.as_str(), "$ref" | "definitions" | "$defs"))
+                    .cloned()
+                    .collect();
+                if !keywords.is_empty() {
+                    problems.push(IllogicalSchema::RefSiblings {
+                        pointer: format!("{pointer}/$ref"),
+                        keywords,
+                    });
+                }
+            }
+            if let Some(Value::Object(properties)) = map.get("properties") {
+                if let Some(Value::Array(_)) = properties.get("required") {
+                    problems.push(IllogicalSchema::RequiredInProperties {
+                        pointer: format!("{pointer}/properties/required"),
+                    });
+                }
+            }
+            for (k, v) in map {
+                // instance values, not schemas
+                if matches!(k.as_str(), "const" | "enum" | "default" | "examples") {
+                    continue;
+                }
+                pointer.push('/');
+                pointer.push_str(&escape(k));
+                let map = SUBSCHEMA_MAPS.contains(&k.as_str());
+                walk(v, !map, pointer, legacy, problems);
+                pointer.truncate(truncate);
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,81 @@
+use json_schema::{
+    batch::{self, BatchOptions},
+    compiled::CompiledSchema,
+    context::Context,
+    schema::JsonSchema,
+};
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+    process::ExitCode,
+};
+use url::Url;
+
+const USAGE: &str = "usage: json-schema validate-lines <schema> [file] [--workers <n>]
+
+Validates every line of a JSON Lines file (stdin if omitted) against the schema and writes one
+result per line to stdout. Exits with 1 if any line is invalid.";
+
+fn main() -> ExitCode {
+    match run(std::env::args().skip(1).collect()) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run(args: Vec<String>) -> Result<bool, Box<dyn std::error::Error>> {
+    let mut args = args.into_iter();
+    if args.next().as_deref() != Some("validate-lines") {
+        return Err(USAGE.into());
+    }
+    let mut options = BatchOptions::default();
+    let mut paths = vec![];
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--workers" => {
+                options.workers = args.next().ok_or(USAGE)?.parse()?;
+            }
+            _ => paths.push(arg),
+        }
+    }
+    let (schema, input) = match paths.as_slice() {
+        [schema] => (schema, None),
+        [schema, input] => (schema, Some(input)),
+        _ => return Err(USAGE.into()),
+    };
+
+    let location = match Url::parse(schema) {
+        Ok(url) => url,
+        Err(_) => Url::from_file_path(std::fs::canonicalize(schema)?)
+            .map_err(|_| format!("invalid schema path {schema}"))?,
+    };
+    let mut context = Context::new();
+    let schema = futures::executor::block_on(context.compile_url(location.clone()))?;
+    // a schema that is a `$ref` is compiled at the url it refers to
+    let location = match schema {
+        JsonSchema::Resolved(url) => url,
+        _ => location,
+    };
+    let schema = CompiledSchema::new(context, location).ok_or("schema was not compiled")?;
+
+    let mut stdout = BufWriter::new(io::stdout().lock());
+    let mut valid = true;
+    let sink = |result: batch::LineResult| {
+        valid &= result.valid;
+        serde_json::to_writer(&mut stdout, &result)?;
+        stdout.write_all(b"\n")
+    };
+    match input {
+        Some(input) => {
+            let reader = BufReader::new(File::open(input)?);
+            batch::validate_lines(&schema, reader, &options, sink)?
+        }
+        None => batch::validate_lines(&schema, io::stdin().lock(), &options, sink)?,
+    }
+    stdout.flush()?;
+    Ok(valid)
+}
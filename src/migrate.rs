@@ -0,0 +1,424 @@
+//! Upgrade schema documents of draft-04 to 2019-09 to draft 2020-12.
+//!
+//! Older documents use keywords that the [`Keywords`](crate::keywords::Keywords) model does not
+//! parse, like the array form of `items` or a boolean `exclusiveMinimum`, so the migration works
+//! on the document before it is parsed. Every rewrite is recorded in the change log of the
+//! [`Migration`], with the pointer of the keyword in the original document.
+
+use crate::{schema::JsonSchema, validator::escape};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fmt::{self, Write};
+
+pub const DRAFT_2020_12: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// A migrated document and what was changed to get there.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Migration {
+    pub schema: Value,
+    pub changes: Vec<MigrationChange>,
+}
+
+impl Migration {
+    /// Parse the migrated document.
+    pub fn into_schema(self) -> Result<JsonSchema, serde_json::Error> {
+        serde_json::from_value(self.schema)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationChange {
+    /// json pointer to the keyword in the original document.
+    pub pointer: String,
+    /// the keyword before the change.
+    pub from: String,
+    /// the keyword after the change, `None` if it was removed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    pub note: String,
+}
+
+impl fmt::Display for MigrationChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.to {
+            Some(to) => write!(
+                f,
+                "{}: {} -> {}, {}",
+                self.pointer, self.from, to, self.note
+            ),
+            None => write!(f, "{}: {} removed, {}", self.pointer, self.from, self.note),
+        }
+    }
+}
+
+/// Rewrite a schema document to draft 2020-12:
+/// - `id` becomes `$id`, or `$anchor` for a plain name fragment.
+/// - `definitions` becomes `$defs`, `$ref` pointers into them are rewritten.
+/// - the array form of `items` becomes `prefixItems` and `additionalItems` becomes `items`.
+/// - `dependencies` is split into `dependentRequired` and `dependentSchemas`.
+/// - a boolean `exclusiveMinimum`/`exclusiveMaximum` takes the number of its bound.
+/// - `$recursiveRef` and `$recursiveAnchor` become `$dynamicRef` and `$dynamicAnchor`.
+/// - keywords next to `$ref`, which draft-07 and older ignore, are removed.
+pub fn migrate(document: &Value) -> Migration {
+    let draft = document
+        .get("$schema")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let mut migrator = Migrator {
+        legacy: ["draft-03", "draft-04", "draft-06", "draft-07"]
+            .iter()
+            .any(|name| draft.contains(name)),
+        recursive_anchor: has_recursive_anchor(document),
+        changes: vec![],
+    };
+    let mut schema = document.clone();
+    migrator.schema(&mut schema, &mut String::new());
+    if let Value::Object(map) = &mut schema {
+        if map.get("$schema").and_then(Value::as_str) != Some(DRAFT_2020_12) {
+            map.insert(
+                "$schema".to_owned(),
+                Value::String(DRAFT_2020_12.to_owned()),
+            );
+            migrator.change("", "$schema", Some("$schema"), "declares draft 2020-12");
+        }
+    }
+    Migration {
+        schema,
+        changes: migrator.changes,
+    }
+}
+
+/// Keywords with a single subschema.
+pub(crate) const SUBSCHEMA: [&str; 12] = [
+    "additionalItems",
+    "additionalProperties",
+    "contains",
+    "contentSchema",
+    "else",
+    "if",
+    "items",
+    "not",
+    "propertyNames",
+    "then",
+    "unevaluatedItems",
+    "unevaluatedProperties",
+];
+
+/// Keywords with an array of subschemas.
+pub(crate) const SUBSCHEMAS: [&str; 5] = ["allOf", "anyOf", "items", "oneOf", "prefixItems"];
+
+/// Keywords with subschemas by name, the values of `dependencies` may also be arrays of names.
+pub(crate) const SUBSCHEMA_MAPS: [&str; 6] = [
+    "$defs",
+    "definitions",
+    "dependencies",
+    "dependentSchemas",
+    "patternProperties",
+    "properties",
+];
+
+struct Migrator {
+    /// whether the document declares draft-07 or older.
+    legacy: bool,
+    /// whether `$recursiveAnchor: true` is used anywhere in the document.
+    recursive_anchor: bool,
+    changes: Vec<MigrationChange>,
+}
+
+impl Migrator {
+    fn change(&mut self, pointer: &str, from: &str, to: Option<&str>, note: &str) {
+        let mut location = pointer.to_owned();
+        if !from.is_empty() {
+            location.push('/');
+            location.push_str(&escape(from));
+        }
+        self.changes.push(MigrationChange {
+            pointer: location,
+            from: from.to_owned(),
+            to: to.map(str::to_owned),
+            note: note.to_owned(),
+        });
+    }
+
+    /// Migrate a schema and its subschemas, `pointer` is its location in the original document.
+    fn schema(&mut self, schema: &mut Value, pointer: &mut String) {
+        let Value::Object(map) = schema else {
+            return;
+        };
+        if self.legacy && map.contains_key("$ref") {
+            self.ref_siblings(map, pointer);
+        }
+        let truncate = pointer.len();
+        // the subschemas first, their locations are still the original ones
+        for (keyword, value) in map.iter_mut() {
+            let keyword = keyword.as_str();
+            pointer.push('/');
+            pointer.push_str(&escape(keyword));
+            match value {
+                Value::Array(schemas) if SUBSCHEMAS.contains(&keyword) => {
+                    for (i, schema) in schemas.iter_mut().enumerate() {
+                        let truncate = pointer.len();
+                        let _ = write!(pointer, "/{i}");
+                        self.schema(schema, pointer);
+                        pointer.truncate(truncate);
+                    }
+                }
+                Value::Object(schemas) if SUBSCHEMA_MAPS.contains(&keyword) => {
+                    for (name, schema) in schemas.iter_mut() {
+                        let truncate = pointer.len();
+                        pointer.push('/');
+                        pointer.push_str(&escape(name));
+                        self.schema(schema, pointer);
+                        pointer.truncate(truncate);
+                    }
+                }
+                value if SUBSCHEMA.contains(&keyword) => self.schema(value, pointer),
+                _ => {}
+            }
+            pointer.truncate(truncate);
+        }
+
+        self.id(map, pointer);
+        if let Some(definitions) = map.remove("definitions") {
+            let defs = map
+                .entry("$defs")
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let (Value::Object(defs), Value::Object(definitions)) = (defs, definitions) {
+                for (name, schema) in definitions {
+                    defs.entry(name).or_insert(schema);
+                }
+            }
+            self.change(pointer, "definitions", Some("$defs"), "renamed");
+        }
+        self.items(map, pointer);
+        self.dependencies(map, pointer);
+        self.exclusive(map, pointer, "exclusiveMinimum", "minimum");
+        self.exclusive(map, pointer, "exclusiveMaximum", "maximum");
+        self.recursive(map, pointer);
+        if let Some(Value::String(reference)) = map.get_mut("$ref") {
+            if let Some(rewritten) = rewrite_ref(reference) {
+                *reference = rewritten;
+                self.change(
+                    pointer,
+                    "$ref",
+                    Some("$ref"),
+                    "points into the renamed keywords",
+                );
+            }
+        }
+    }
+
+    /// Keywords next to `$ref` are ignored before 2019-09 and would apply after the migration.
+    fn ref_siblings(&mut self, map: &mut Map<String, Value>, pointer: &str) {
+        let ignored: Vec<String> = map
+            .keys()
+            .filter(|k| {
+                // identifiers and definitions next to `$ref` are still used to resolve references
+                !matches!(
+                    k.as_str(),
+                    "$ref" | "$schema" | "$id" | "id" | "definitions" | "$defs" | "$comment"
+                )
+            })
+            .cloned()
+            .collect();
+        for keyword in ignored {
+            map.remove(&keyword);
+            self.change(
+                pointer,
+                &keyword,
+                None,
+                "ignored next to $ref before 2019-09",
+            );
+        }
+    }
+
+    fn id(&mut self, map: &mut Map<String, Value>, pointer: &str) {
+        if let Some(Value::String(id)) = map.get("id") {
+            if map.contains_key("$id") {
+                return;
+            }
+            let id = id.clone();
+            map.remove("id");
+            self.rename_id(map, pointer, "id", id);
+        } else if let Some(Value::String(id)) = map.get("$id") {
+            if id.starts_with('#') {
+                let id = id.clone();
+                map.remove("$id");
+                self.rename_id(map, pointer, "$id", id);
+            }
+        }
+    }
+
+    /// A plain name fragment as an id is an `$anchor` since 2019-09.
+    fn rename_id(&mut self, map: &mut Map<String, Value>, pointer: &str, from: &str, id: String) {
+        match id.strip_prefix('#') {
+            Some(anchor) if !anchor.is_empty() && !anchor.starts_with('/') => {
+                map.insert("$anchor".to_owned(), Value::String(anchor.to_owned()));
+                self.change(pointer, from, Some("$anchor"), "a plain name fragment");
+            }
+            _ => {
+                map.insert("$id".to_owned(), Value::String(id));
+                self.change(pointer, from, Some("$id"), "renamed");
+            }
+        }
+    }
+
+    fn items(&mut self, map: &mut Map<String, Value>, pointer: &str) {
+        if let Some(Value::Array(_)) = map.get("items") {
+            let items = map.remove("items").unwrap_or_default();
+            map.insert("prefixItems".to_owned(), items);
+            self.change(pointer, "items", Some("prefixItems"), "array form of items");
+            if let Some(additional) = map.remove("additionalItems") {
+                map.insert("items".to_owned(), additional);
+                self.change(
+                    pointer,
+                    "additionalItems",
+                    Some("items"),
+                    "items after the prefix",
+                );
+            }
+        } else if map.remove("additionalItems").is_some() {
+            let note = "only applies next to the array form of items";
+            self.change(pointer, "additionalItems", None, note);
+        }
+    }
+
+    fn dependencies(&mut self, map: &mut Map<String, Value>, pointer: &str) {
+        let Some(Value::Object(dependencies)) = map.remove("dependencies") else {
+            return;
+        };
+        let (mut required, mut schemas) = (false, false);
+        for (name, dependency) in dependencies {
+            let keyword = match dependency {
+                Value::Array(_) => {
+                    required = true;
+                    "dependentRequired"
+                }
+                _ => {
+                    schemas = true;
+                    "dependentSchemas"
+                }
+            };
+            let entry = map
+                .entry(keyword)
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let Value::Object(entry) = entry {
+                entry.entry(name).or_insert(dependency);
+            }
+        }
+        for (split, keyword) in [
+            (required, "dependentRequired"),
+            (schemas, "dependentSchemas"),
+        ] {
+            if split {
+                self.change(
+                    pointer,
+                    "dependencies",
+                    Some(keyword),
+                    "split by the kind of dependency",
+                );
+            }
+        }
+    }
+
+    /// The boolean form of draft-04, `"minimum": 5, "exclusiveMinimum": true`.
+    fn exclusive(
+        &mut self,
+        map: &mut Map<String, Value>,
+        pointer: &str,
+        keyword: &str,
+        bound: &str,
+    ) {
+        let Some(Value::Bool(exclusive)) = map.get(keyword) else {
+            return;
+        };
+        let exclusive = *exclusive;
+        map.remove(keyword);
+        match (exclusive, map.get(bound).cloned()) {
+            (true, Some(number @ Value::Number(_))) => {
+                map.remove(bound);
+                map.insert(keyword.to_owned(), number);
+                self.change(
+                    pointer,
+                    keyword,
+                    Some(keyword),
+                    &format!("takes the number of {bound}"),
+                );
+            }
+            (true, _) => self.change(pointer, keyword, None, &format!("without {bound}")),
+            (false, _) => self.change(pointer, keyword, None, "false is the default"),
+        }
+    }
+
+    fn recursive(&mut self, map: &mut Map<String, Value>, pointer: &str) {
+        if let Some(anchor) = map.remove("$recursiveAnchor") {
+            if anchor == Value::Bool(true) {
+                map.insert(
+                    "$dynamicAnchor".to_owned(),
+                    Value::String("meta".to_owned()),
+                );
+                self.change(
+                    pointer,
+                    "$recursiveAnchor",
+                    Some("$dynamicAnchor"),
+                    "named meta",
+                );
+            } else {
+                self.change(pointer, "$recursiveAnchor", None, "false is the default");
+            }
+        }
+        if let Some(reference) = map.remove("$recursiveRef") {
+            // without a recursive anchor the reference is resolved like `$ref`
+            let reference = match (reference.as_str(), self.recursive_anchor) {
+                (Some("#"), true) => Value::String("#meta".to_owned()),
+                _ => reference,
+            };
+            map.insert("$dynamicRef".to_owned(), reference);
+            self.change(pointer, "$recursiveRef", Some("$dynamicRef"), "renamed");
+        }
+    }
+}
+
+fn has_recursive_anchor(value: &Value) -> bool {
+    match value {
+        Value::Object(map) => {
+            map.get("$recursiveAnchor") == Some(&Value::Bool(true))
+                || map.values().any(has_recursive_anchor)
+        }
+        Value::Array(items) => items.iter().any(has_recursive_anchor),
+        _ => false,
+    }
+}
+
+/// Rewrite the json pointer fragment of a reference through renamed keywords, `None` if it does
+/// not change. Segments after keywords with names or indexes are not keywords themselves.
+fn rewrite_ref(reference: &str) -> Option<String> {
+    let (base, fragment) = reference.split_once('#')?;
+    let segments: Vec<&str> = fragment.strip_prefix('/')?.split('/').collect();
+    let mut rewritten = vec![];
+    let mut i = 0;
+    while i < segments.len() {
+        let (segment, next) = (segments[i], segments.get(i + 1));
+        let index = next.is_some_and(|next| next.parse::<usize>().is_ok());
+        let (keyword, named) = match segment {
+            "definitions" => ("$defs", true),
+            "dependencies" => ("dependentSchemas", true),
+            "items" if index => ("prefixItems", true),
+            "additionalItems" => ("items", false),
+            keyword if SUBSCHEMA_MAPS.contains(&keyword) || SUBSCHEMAS.contains(&keyword) => {
+                (keyword, keyword != "items")
+            }
+            keyword => (keyword, false),
+        };
+        rewritten.push(keyword);
+        if let (true, Some(next)) = (named, next) {
+            rewritten.push(next);
+            i += 1;
+        }
+        i += 1;
+    }
+    let rewritten = format!("{base}#/{}", rewritten.join("/"));
+    (rewritten != reference).then_some(rewritten)
+}
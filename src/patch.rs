@@ -0,0 +1,132 @@
+//! The patch phase, keywords alter a document before it is validated.
+//!
+//! `properties` inserts the `default` of properties that are missing and `type` coerces scalars
+//! that are not of an allowed type, e.g. `"42"` to `42` for `"type": "integer"`. Applicators
+//! without a single branch to follow (`anyOf`, `oneOf`, `not`, `if`) are not patched through.
+
+use crate::{
+    context::Context,
+    keywords::Keywords,
+    schema::{JsonSchema, ResolvedJsonSchema},
+    tree::{type_mask, BOOLEAN, INTEGER, NULL, NUMBER, STRING},
+    validator::type_name,
+};
+use serde_json::{Number, Value};
+use url::Url;
+
+/// Applies the patch phase of the keywords of a [`Context`] to a document.
+pub struct Patcher<'a> {
+    context: &'a Context,
+    /// insert the `default` of missing properties.
+    pub defaults: bool,
+    /// coerce scalars to a type allowed by `type`.
+    pub coerce: bool,
+    /// schemas applied to the current value, a schema that reaches itself through `allOf` or
+    /// `$ref` without descending into the value is only applied once.
+    applying: Vec<Url>,
+}
+
+impl<'a> Patcher<'a> {
+    pub fn new(context: &'a Context) -> Self {
+        Self {
+            context,
+            defaults: true,
+            coerce: true,
+            applying: vec![],
+        }
+    }
+
+    /// Patch a value with the schema at `url`.
+    pub fn patch(&mut self, url: &Url, value: &mut Value) {
+        self.apply(&JsonSchema::Resolved(url.clone()), value);
+    }
+
+    /// Patch a value with a schema applying to the value itself, e.g. an entry of `allOf`.
+    pub fn apply(&mut self, schema: &JsonSchema, value: &mut Value) {
+        match schema {
+            JsonSchema::Resolved(url) => {
+                if self.applying.contains(url) {
+                    return;
+                }
+                let Some(ResolvedJsonSchema::Object(keywords)) = self.context.schema(url) else {
+                    return;
+                };
+                self.applying.push(url.clone());
+                keywords.patch(self, value);
+                self.applying.pop();
+            }
+            JsonSchema::Object(keywords) => keywords.patch(self, value),
+            JsonSchema::Bool(_) | JsonSchema::Ref { .. } | JsonSchema::Mod { .. } => {}
+        }
+    }
+
+    /// Patch a property or item of the current value with a subschema.
+    pub fn child(&mut self, schema: &JsonSchema, value: &mut Value) {
+        let applying = std::mem::take(&mut self.applying);
+        self.apply(schema, value);
+        self.applying = applying;
+    }
+
+    /// The keywords of the schema being applied, for keywords that depend on their siblings.
+    pub fn keywords(&self) -> Option<&'a Keywords> {
+        match self.context.schema(self.applying.last()?)? {
+            ResolvedJsonSchema::Object(keywords) => Some(keywords),
+            ResolvedJsonSchema::Bool(_) => None,
+        }
+    }
+
+    /// The `default` of a subschema.
+    pub fn default(&self, schema: &JsonSchema) -> Option<Value> {
+        let keywords = match schema {
+            JsonSchema::Resolved(url) => match self.context.schema(url)? {
+                ResolvedJsonSchema::Object(keywords) => keywords,
+                ResolvedJsonSchema::Bool(_) => return None,
+            },
+            JsonSchema::Object(keywords) => keywords,
+            _ => return None,
+        };
+        keywords.default.as_ref().map(|default| default.0.clone())
+    }
+}
+
+/// Convert a scalar to one of the types of `mask`, returns `None` if there is no lossless
+/// conversion.
+pub fn coerce(value: &Value, mask: u8) -> Option<Value> {
+    match value {
+        Value::String(string) => {
+            let string = string.trim();
+            if mask & INTEGER != 0 {
+                if let Ok(int) = string.parse::<i64>() {
+                    return Some(Value::from(int));
+                }
+                if let Ok(int) = string.parse::<u64>() {
+                    return Some(Value::from(int));
+                }
+            }
+            if mask & NUMBER != 0 {
+                if let Some(number) = string.parse::<f64>().ok().and_then(Number::from_f64) {
+                    return Some(Value::Number(number));
+                }
+            }
+            match string {
+                "true" if mask & BOOLEAN != 0 => Some(Value::Bool(true)),
+                "false" if mask & BOOLEAN != 0 => Some(Value::Bool(false)),
+                "" | "null" if mask & NULL != 0 => Some(Value::Null),
+                _ => None,
+            }
+        }
+        Value::Number(_) | Value::Bool(_) if mask & STRING != 0 => {
+            Some(Value::String(value.to_string()))
+        }
+        _ => None,
+    }
+}
+
+/// Whether a value is of one of the types of `mask`.
+pub fn is_type(value: &Value, mask: u8) -> bool {
+    match type_name(value) {
+        // the mask of "number" includes integers, a value is only ever one of the two
+        "number" => mask & NUMBER != 0,
+        name => type_mask(name) & mask != 0,
+    }
+}
@@ -1,4 +1,10 @@
+use crate::{
+    validator::escape,
+    value::{ArenaNode, Instance, Kind, Location},
+};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
 use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
 use url::Url;
 
 /// A JSON pointer. This can take three forms.
@@ -15,17 +21,23 @@ pub enum JsonPointer {
 
 impl JsonPointer {
     /// Converts a relative json pointer into an absolute pointer of some base Url.
-    /// This will allocate a new Url if the underlying value is relative.
+    /// This will allocate a new Url if the underlying value is relative. An empty fragment refers
+    /// to the document itself and is dropped.
     pub fn to_absolute(&self, base: &Url) -> Result<Url, url::ParseError> {
-        Ok(match self {
+        let mut url = match self {
             JsonPointer::Absolute(url) => url.clone(),
             JsonPointer::Relative(url) => base.join(url)?,
-        })
+        };
+        if url.fragment() == Some("") {
+            url.set_fragment(None);
+        }
+        Ok(url)
     }
-    pub fn absolute(&self) -> &Url {
+    /// The url of an absolute pointer.
+    pub fn absolute(&self) -> Result<&Url, PointerError> {
         match self {
-            JsonPointer::Absolute(url) => url,
-            _ => panic!("url is not absolute"),
+            JsonPointer::Absolute(url) => Ok(url),
+            JsonPointer::Relative(url) => Err(PointerError::NotAbsolute(url.clone())),
         }
     }
 }
@@ -36,14 +48,419 @@ impl From<Url> for JsonPointer {
     }
 }
 
+/// Characters that are percent-encoded when a pointer is written as a uri fragment.
+const FRAGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'[')
+    .add(b'\\')
+    .add(b']')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+/// An error of parsing or evaluating a json pointer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PointerError {
+    /// a pointer that is not empty must start with `/`.
+    MissingSlash,
+    /// `~` must be followed by `0` or `1`.
+    InvalidEscape,
+    /// the percent-encoding of a uri fragment does not decode to utf-8.
+    InvalidEncoding,
+    /// a relative pointer must start with a non-negative integer without leading zeros.
+    InvalidPrefix,
+    /// the url of a [`JsonPointer`] is relative.
+    NotAbsolute(String),
+    /// the value has no property or item for the segment.
+    NotFound(String),
+    /// a relative pointer goes up further than the document.
+    OutOfDocument,
+    /// index manipulation on a value that is not an item of an array.
+    NotAnItem,
+    /// `#` on the document, which is not found in a parent.
+    NoLocation,
+}
+
+impl fmt::Display for PointerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PointerError::MissingSlash => write!(f, "json pointer must start with /"),
+            PointerError::InvalidEscape => write!(f, "~ must be followed by 0 or 1"),
+            PointerError::InvalidEncoding => write!(f, "fragment is not percent-encoded utf-8"),
+            PointerError::InvalidPrefix => {
+                write!(
+                    f,
+                    "relative json pointer must start with a non-negative integer"
+                )
+            }
+            PointerError::NotAbsolute(url) => write!(f, "{url} is not absolute"),
+            PointerError::NotFound(segment) => write!(f, "no value found at {segment}"),
+            PointerError::OutOfDocument => write!(f, "pointer goes up past the document"),
+            PointerError::NotAnItem => write!(f, "value is not an item of an array"),
+            PointerError::NoLocation => write!(f, "the document has no key or index"),
+        }
+    }
+}
+
+impl std::error::Error for PointerError {}
+
+/// A json pointer as defined by RFC 6901, e.g. `/properties/a~1b/0`. The segments are stored
+/// unescaped.
+#[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct AbsoluteJsonPointer {
+    segments: Vec<String>,
+}
+
+impl AbsoluteJsonPointer {
+    /// The empty pointer, refers to the whole document.
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(pointer: &str) -> Result<Self, PointerError> {
+        if pointer.is_empty() {
+            return Ok(Self::root());
+        }
+        let segments = pointer
+            .strip_prefix('/')
+            .ok_or(PointerError::MissingSlash)?
+            .split('/')
+            .map(unescape)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { segments })
+    }
+
+    /// Parse the fragment of a uri, e.g. `#/definitions/a%20b`. The leading `#` is optional.
+    pub fn from_fragment(fragment: &str) -> Result<Self, PointerError> {
+        let fragment = fragment.strip_prefix('#').unwrap_or(fragment);
+        let pointer = percent_decode_str(fragment)
+            .decode_utf8()
+            .map_err(|_| PointerError::InvalidEncoding)?;
+        Self::parse(&pointer)
+    }
+
+    /// The pointer as a percent-encoded uri fragment, without the leading `#`.
+    pub fn to_fragment(&self) -> String {
+        utf8_percent_encode(&self.to_string(), FRAGMENT).to_string()
+    }
+
+    pub fn segments(&self) -> &[String] {
+        &self.segments
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    pub fn push(&mut self, segment: impl Into<String>) {
+        self.segments.push(segment.into());
+    }
+
+    pub fn pop(&mut self) -> Option<String> {
+        self.segments.pop()
+    }
+
+    /// This pointer followed by the segments of `other`.
+    pub fn join(&self, other: &AbsoluteJsonPointer) -> Self {
+        let mut segments = self.segments.clone();
+        segments.extend(other.segments.iter().cloned());
+        Self { segments }
+    }
+
+    /// The value the pointer refers to, `value` is either a `&serde_json::Value` or an
+    /// [`ArenaNode`].
+    pub fn resolve<'v, I: Instance<'v>>(&self, value: I) -> Result<I, PointerError> {
+        let mut value = value;
+        for segment in &self.segments {
+            let next = match value.kind() {
+                Kind::Object => value.get(segment),
+                Kind::Array => parse_index(segment).and_then(|index| value.item(index)),
+                _ => None,
+            };
+            value = next.ok_or_else(|| PointerError::NotFound(segment.clone()))?;
+        }
+        Ok(value)
+    }
+}
+
+impl fmt::Display for AbsoluteJsonPointer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in &self.segments {
+            write!(f, "/{}", escape(segment))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for AbsoluteJsonPointer {
+    type Err = PointerError;
+
+    fn from_str(pointer: &str) -> Result<Self, Self::Err> {
+        Self::parse(pointer)
+    }
+}
+
+impl TryFrom<String> for AbsoluteJsonPointer {
+    type Error = PointerError;
+
+    fn try_from(pointer: String) -> Result<Self, Self::Error> {
+        Self::parse(&pointer)
+    }
+}
+
+impl From<AbsoluteJsonPointer> for String {
+    fn from(pointer: AbsoluteJsonPointer) -> Self {
+        pointer.to_string()
+    }
+}
+
+fn unescape(segment: &str) -> Result<String, PointerError> {
+    if !segment.contains('~') {
+        return Ok(segment.to_owned());
+    }
+    let mut unescaped = String::with_capacity(segment.len());
+    let mut chars = segment.chars();
+    while let Some(c) = chars.next() {
+        unescaped.push(match c {
+            '~' => match chars.next() {
+                Some('0') => '~',
+                Some('1') => '/',
+                _ => return Err(PointerError::InvalidEscape),
+            },
+            c => c,
+        });
+    }
+    Ok(unescaped)
+}
+
+/// Parse a non-negative integer without leading zeros.
+fn parse_index(segment: &str) -> Option<usize> {
+    let digits = !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit());
+    if !digits || (segment.len() > 1 && segment.starts_with('0')) {
+        return None;
+    }
+    segment.parse().ok()
+}
+
+/// What a [`RelativeJsonPointer`] evaluates to after going up.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RelativeTarget {
+    /// `#`, the key or index of the value in its parent.
+    Location,
+    /// a json pointer resolved from the value.
+    Pointer(AbsoluteJsonPointer),
+}
+
+/// A relative json pointer, e.g. `0#` or `1/foo`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
 pub struct RelativeJsonPointer {
-    source: String,
-    traverse: usize,
+    /// how many parents to go up from the current value.
+    pub traverse: usize,
+    /// index manipulation, e.g. `+1` moves to the next item of the same array.
+    pub shift: Option<isize>,
+    pub target: RelativeTarget,
+}
+
+/// The result of evaluating a [`RelativeJsonPointer`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RelativeValue<'a, I> {
+    Value(I),
+    Location(Location<'a>),
+}
+
+impl<'v, I: Instance<'v>> RelativeValue<'_, I> {
+    /// The value, or the key or index as a json string or number.
+    pub fn to_value(self) -> serde_json::Value {
+        match self {
+            RelativeValue::Value(value) => value.to_value(),
+            RelativeValue::Location(Location::Key(key)) => key.into(),
+            RelativeValue::Location(Location::Index(index)) => index.into(),
+        }
+    }
+}
+
+impl RelativeJsonPointer {
+    pub fn parse(pointer: &str) -> Result<Self, PointerError> {
+        let end = pointer
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(pointer.len());
+        let traverse = parse_index(&pointer[..end]).ok_or(PointerError::InvalidPrefix)?;
+        let mut rest = &pointer[end..];
+        let shift = match rest.chars().next() {
+            Some(sign @ ('+' | '-')) => {
+                let end = rest[1..]
+                    .find(|c: char| !c.is_ascii_digit())
+                    .map_or(rest.len(), |i| i + 1);
+                let amount = parse_index(&rest[1..end]).ok_or(PointerError::InvalidPrefix)?;
+                let amount = isize::try_from(amount).map_err(|_| PointerError::InvalidPrefix)?;
+                rest = &rest[end..];
+                Some(if sign == '-' { -amount } else { amount })
+            }
+            _ => None,
+        };
+        let target = match rest {
+            "#" => RelativeTarget::Location,
+            pointer => RelativeTarget::Pointer(AbsoluteJsonPointer::parse(pointer)?),
+        };
+        Ok(Self {
+            traverse,
+            shift,
+            target,
+        })
+    }
+
+    /// Evaluate the pointer from a value of a [`ValueArena`](crate::value::ValueArena).
+    pub fn evaluate<'a>(
+        &self,
+        node: ArenaNode<'a>,
+    ) -> Result<RelativeValue<'a, ArenaNode<'a>>, PointerError> {
+        let mut node = node;
+        for _ in 0..self.traverse {
+            node = node.parent().ok_or(PointerError::OutOfDocument)?;
+        }
+        if let Some(shift) = self.shift {
+            let (Some(parent), Some(Location::Index(index))) = (node.parent(), node.location())
+            else {
+                return Err(PointerError::NotAnItem);
+            };
+            let shifted = index
+                .checked_add_signed(shift)
+                .ok_or_else(|| PointerError::NotFound(format!("{index}{shift:+}")))?;
+            node = parent
+                .item(shifted)
+                .ok_or_else(|| PointerError::NotFound(shifted.to_string()))?;
+        }
+        match &self.target {
+            RelativeTarget::Location => node
+                .location()
+                .map(RelativeValue::Location)
+                .ok_or(PointerError::NoLocation),
+            RelativeTarget::Pointer(pointer) => pointer.resolve(node).map(RelativeValue::Value),
+        }
+    }
+
+    /// Evaluate the pointer from the value at `location` in `document`, `document` is either a
+    /// `&serde_json::Value` or an [`ArenaNode`].
+    pub fn evaluate_at<'v, 'p, I: Instance<'v>>(
+        &self,
+        document: I,
+        location: &'p AbsoluteJsonPointer,
+    ) -> Result<RelativeValue<'p, I>, PointerError> {
+        let len = location
+            .segments
+            .len()
+            .checked_sub(self.traverse)
+            .ok_or(PointerError::OutOfDocument)?;
+        let mut base = AbsoluteJsonPointer {
+            segments: location.segments[..len].to_vec(),
+        };
+        let last = base.pop();
+        let parent = match &last {
+            Some(_) => Some(base.resolve(document)?),
+            None => None,
+        };
+        let in_array = parent.is_some_and(|parent| matches!(parent.kind(), Kind::Array));
+        let last = match (last, self.shift) {
+            (last, None) => last,
+            (Some(last), Some(shift)) if in_array => {
+                let index = parse_index(&last).ok_or(PointerError::NotAnItem)?;
+                let shifted = index
+                    .checked_add_signed(shift)
+                    .ok_or_else(|| PointerError::NotFound(format!("{index}{shift:+}")))?;
+                Some(shifted.to_string())
+            }
+            _ => return Err(PointerError::NotAnItem),
+        };
+        if let Some(last) = &last {
+            base.push(last.clone());
+        }
+        match &self.target {
+            RelativeTarget::Location => {
+                let last = last.ok_or(PointerError::NoLocation)?;
+                // the value must exist for its key or index to be meaningful
+                base.resolve(document)?;
+                match parse_index(&last).filter(|_| in_array) {
+                    Some(index) => Ok(RelativeValue::Location(Location::Index(index))),
+                    None => Ok(RelativeValue::Location(Location::Key(
+                        &location.segments[len - 1],
+                    ))),
+                }
+            }
+            RelativeTarget::Pointer(pointer) => base
+                .join(pointer)
+                .resolve(document)
+                .map(RelativeValue::Value),
+        }
+    }
+}
+
+impl fmt::Display for RelativeJsonPointer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.traverse)?;
+        if let Some(shift) = self.shift {
+            write!(f, "{shift:+}")?;
+        }
+        match &self.target {
+            RelativeTarget::Location => write!(f, "#"),
+            RelativeTarget::Pointer(pointer) => write!(f, "{pointer}"),
+        }
+    }
+}
+
+impl FromStr for RelativeJsonPointer {
+    type Err = PointerError;
+
+    fn from_str(pointer: &str) -> Result<Self, Self::Err> {
+        Self::parse(pointer)
+    }
+}
+
+impl TryFrom<String> for RelativeJsonPointer {
+    type Error = PointerError;
+
+    fn try_from(pointer: String) -> Result<Self, Self::Error> {
+        Self::parse(&pointer)
+    }
 }
 
-pub struct AbsoluteJsonPointer {}
+impl From<RelativeJsonPointer> for String {
+    fn from(pointer: RelativeJsonPointer) -> Self {
+        pointer.to_string()
+    }
+}
 
+/// Either kind of json pointer, relative pointers start with a digit.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum JsonPointerFragment {
     Absolute(AbsoluteJsonPointer),
     Relative(RelativeJsonPointer),
 }
+
+impl FromStr for JsonPointerFragment {
+    type Err = PointerError;
+
+    fn from_str(pointer: &str) -> Result<Self, Self::Err> {
+        match pointer.starts_with(|c: char| c.is_ascii_digit()) {
+            true => RelativeJsonPointer::parse(pointer).map(JsonPointerFragment::Relative),
+            false => AbsoluteJsonPointer::parse(pointer).map(JsonPointerFragment::Absolute),
+        }
+    }
+}
+
+impl fmt::Display for JsonPointerFragment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonPointerFragment::Absolute(pointer) => write!(f, "{pointer}"),
+            JsonPointerFragment::Relative(pointer) => write!(f, "{pointer}"),
+        }
+    }
+}
@@ -0,0 +1,448 @@
+//! Validation of any [`Serialize`] value through a [`Serializer`] that feeds the evaluator
+//! directly, without converting the value to a [`serde_json::Value`] first.
+//!
+//! Values are mapped to json the same way `serde_json` does, so the errors are the ones that
+//! validating `serde_json::to_value(value)` would report. Objects and arrays are validated entry
+//! by entry as in [`stream`](crate::stream), only the ones a node has to see as a whole are
+//! serialized to a [`Value`].
+
+use crate::{
+    stream::{Children, Container, Stream, StreamError, Target},
+    tree::{SchemaTree, ARRAY, OBJECT},
+    validator::Validator,
+};
+use serde::{
+    ser::{
+        self, SerializeMap as _, SerializeSeq as _, SerializeStructVariant as _,
+        SerializeTupleVariant as _,
+    },
+    Serialize, Serializer,
+};
+use serde_json::{value::Serializer as ValueSerializer, Error, Value};
+
+type ValueSeq = <ValueSerializer as Serializer>::SerializeSeq;
+type ValueMap = <ValueSerializer as Serializer>::SerializeMap;
+type ValueTupleVariant = <ValueSerializer as Serializer>::SerializeTupleVariant;
+type ValueStructVariant = <ValueSerializer as Serializer>::SerializeStructVariant;
+
+/// Validate a value against a node of the tree.
+pub fn validate<T: Serialize + ?Sized>(
+    tree: &SchemaTree,
+    node: usize,
+    value: &T,
+) -> Result<(), StreamError<Error>> {
+    // `$data` references can point anywhere in the document, it has to be buffered as a whole
+    if tree.has_data() {
+        let value = serde_json::to_value(value).map_err(StreamError::Source)?;
+        let mut validator = Validator::new(tree);
+        return match validator.validate(node, &value) {
+            true => Ok(()),
+            false => Err(StreamError::Invalid(validator.errors)),
+        };
+    }
+    let mut stream = Stream::new(tree);
+    let targets = [Target {
+        node,
+        keyword: String::new(),
+        quiet: false,
+    }];
+    let valid = value
+        .serialize(Validate {
+            stream: &mut stream,
+            targets: &targets,
+        })
+        .map_err(StreamError::Source)?;
+    match valid[0] {
+        true => Ok(()),
+        false => Err(StreamError::Invalid(stream.into_errors())),
+    }
+}
+
+/// Serializes a value by validating it against the targets, the result is whether the value is
+/// valid against each of them.
+struct Validate<'s, 'a> {
+    stream: &'s mut Stream<'a>,
+    targets: &'s [Target],
+}
+
+impl Validate<'_, '_> {
+    fn value(self, value: Result<Value, Error>) -> Result<Vec<bool>, Error> {
+        Ok(self.stream.validate_value(self.targets, &value?))
+    }
+}
+
+/// Scalars are converted by `serde_json` so numbers, bytes and chars map to the same json.
+macro_rules! scalars {
+    ($($method:ident($ty:ty)),* $(,)?) => {
+        $(
+            fn $method(self, v: $ty) -> Result<Self::Ok, Self::Error> {
+                self.value(ValueSerializer.$method(v))
+            }
+        )*
+    };
+}
+
+impl<'s, 'a> Serializer for Validate<'s, 'a> {
+    type Ok = Vec<bool>;
+    type Error = Error;
+    type SerializeSeq = Compound<'s, 'a>;
+    type SerializeTuple = Compound<'s, 'a>;
+    type SerializeTupleStruct = Compound<'s, 'a>;
+    type SerializeTupleVariant = Compound<'s, 'a>;
+    type SerializeMap = Compound<'s, 'a>;
+    type SerializeStruct = Compound<'s, 'a>;
+    type SerializeStructVariant = Compound<'s, 'a>;
+
+    scalars! {
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        self.value(Ok(Value::Null))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        self.value(Ok(Value::Null))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        self.value(Ok(Value::Null))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        self.value(Ok(Value::String(variant.to_owned())))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    /// Externally tagged, i.e. `{ variant: value }`.
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        let Some(mut object) = self.stream.begin(self.targets, OBJECT) else {
+            let value = ValueSerializer.serialize_newtype_variant(name, index, variant, value);
+            return self.value(value);
+        };
+        let children = self.stream.key(&mut object, variant);
+        let valid = value.serialize(Validate {
+            stream: self.stream,
+            targets: &children.targets,
+        })?;
+        self.stream.finish(&mut object, &children, valid);
+        Ok(self.stream.end(object))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        let state = match self.stream.begin(self.targets, ARRAY) {
+            Some(array) => State::Stream(array, None),
+            None => State::Seq(ValueSerializer.serialize_seq(len)?),
+        };
+        Ok(Compound::new(self, state))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    /// Externally tagged, i.e. `{ variant: [items] }`.
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        let state = match self.stream.begin_variant(self.targets, variant, ARRAY) {
+            Some((object, children, array)) => State::Stream(array, Some((object, children))),
+            None => State::TupleVariant(
+                ValueSerializer.serialize_tuple_variant(name, index, variant, len)?,
+            ),
+        };
+        Ok(Compound::new(self, state))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        let state = match self.stream.begin(self.targets, OBJECT) {
+            Some(object) => State::Stream(object, None),
+            None => State::Map(ValueSerializer.serialize_map(len)?),
+        };
+        Ok(Compound::new(self, state))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        self.serialize_map(Some(len))
+    }
+
+    /// Externally tagged, i.e. `{ variant: { fields } }`.
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        let state = match self.stream.begin_variant(self.targets, variant, OBJECT) {
+            Some((outer, children, object)) => State::Stream(object, Some((outer, children))),
+            None => State::StructVariant(
+                ValueSerializer.serialize_struct_variant(name, index, variant, len)?,
+            ),
+        };
+        Ok(Compound::new(self, state))
+    }
+}
+
+enum State {
+    /// the container being validated and, for enum variants, the object wrapping it.
+    Stream(Container, Option<(Container, Children)>),
+    Seq(ValueSeq),
+    Map(ValueMap),
+    TupleVariant(ValueTupleVariant),
+    StructVariant(ValueStructVariant),
+}
+
+pub struct Compound<'s, 'a> {
+    stream: &'s mut Stream<'a>,
+    targets: &'s [Target],
+    state: State,
+    /// the nodes that apply to the value of the last key.
+    children: Option<Children>,
+}
+
+impl<'s, 'a> Compound<'s, 'a> {
+    fn new(validate: Validate<'s, 'a>, state: State) -> Self {
+        Self {
+            stream: validate.stream,
+            targets: validate.targets,
+            state,
+            children: None,
+        }
+    }
+
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        match &mut self.state {
+            State::Stream(array, _) => {
+                let children = self.stream.item(array);
+                let valid = value.serialize(Validate {
+                    stream: self.stream,
+                    targets: &children.targets,
+                })?;
+                self.stream.finish(array, &children, valid);
+                Ok(())
+            }
+            State::Seq(seq) => seq.serialize_element(value),
+            State::TupleVariant(variant) => variant.serialize_field(value),
+            State::Map(_) | State::StructVariant(_) => unreachable!(),
+        }
+    }
+
+    fn key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        match &mut self.state {
+            State::Stream(object, _) => {
+                let key = match key.serialize(ValueSerializer)? {
+                    Value::String(key) => key,
+                    Value::Number(key) => key.to_string(),
+                    Value::Bool(key) => key.to_string(),
+                    _ => return Err(ser::Error::custom("key must be a string")),
+                };
+                self.children = Some(self.stream.key(object, &key));
+                Ok(())
+            }
+            State::Map(map) => map.serialize_key(key),
+            _ => unreachable!(),
+        }
+    }
+
+    fn value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        match &mut self.state {
+            State::Stream(object, _) => {
+                let children = self
+                    .children
+                    .take()
+                    .expect("value serialized before its key");
+                let valid = value.serialize(Validate {
+                    stream: self.stream,
+                    targets: &children.targets,
+                })?;
+                self.stream.finish(object, &children, valid);
+                Ok(())
+            }
+            State::Map(map) => map.serialize_value(value),
+            _ => unreachable!(),
+        }
+    }
+
+    fn field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+        match &mut self.state {
+            State::StructVariant(variant) => variant.serialize_field(key, value),
+            _ => {
+                self.key(key)?;
+                self.value(value)
+            }
+        }
+    }
+
+    fn end(self) -> Result<Vec<bool>, Error> {
+        let value = match self.state {
+            State::Stream(container, None) => return Ok(self.stream.end(container)),
+            State::Stream(container, Some((mut outer, children))) => {
+                let valid = self.stream.end(container);
+                self.stream.finish(&mut outer, &children, valid);
+                return Ok(self.stream.end(outer));
+            }
+            State::Seq(seq) => seq.end(),
+            State::Map(map) => map.end(),
+            State::TupleVariant(variant) => variant.end(),
+            State::StructVariant(variant) => variant.end(),
+        };
+        Ok(self.stream.validate_value(self.targets, &value?))
+    }
+}
+
+impl ser::SerializeSeq for Compound<'_, '_> {
+    type Ok = Vec<bool>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<Vec<bool>, Error> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeTuple for Compound<'_, '_> {
+    type Ok = Vec<bool>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<Vec<bool>, Error> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for Compound<'_, '_> {
+    type Ok = Vec<bool>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<Vec<bool>, Error> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeTupleVariant for Compound<'_, '_> {
+    type Ok = Vec<bool>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<Vec<bool>, Error> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeMap for Compound<'_, '_> {
+    type Ok = Vec<bool>;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.key(key)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.value(value)
+    }
+
+    fn end(self) -> Result<Vec<bool>, Error> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeStruct for Compound<'_, '_> {
+    type Ok = Vec<bool>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<Vec<bool>, Error> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeStructVariant for Compound<'_, '_> {
+    type Ok = Vec<bool>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<Vec<bool>, Error> {
+        Compound::end(self)
+    }
+}
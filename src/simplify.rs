@@ -0,0 +1,487 @@
+//! Simplification of compiled schemas by merging the members of `allOf` into the schema itself.
+//!
+//! A member is merged keyword by keyword: bounds are intersected, `required` is unioned,
+//! `properties` are merged and `enum` is intersected. Where both sides have a subschema for the
+//! same keyword, e.g. the same property, a new schema with an `allOf` of the two takes its place
+//! and is simplified in turn. A member that can't be merged without changing what the schema
+//! accepts, like one with `additionalProperties` next to properties on the other side, stays in
+//! `allOf` untouched. Schemas found to be unsatisfiable are replaced with `false`.
+
+use crate::{
+    context::Context,
+    keywords::Keywords,
+    patch::is_type,
+    schema::{JsonSchema, ResolvedJsonSchema},
+    tree::{type_mask, ARRAY, INTEGER, NUMBER, OBJECT, STRING, TYPES},
+    validator::{compare_numbers, escape, json_eq},
+};
+use serde_json::{Map, Value};
+use std::{cmp::Ordering, collections::HashSet};
+use url::Url;
+
+/// Keywords that have no effect on validation once a schema is compiled.
+const IGNORED: [&str; 4] = ["$schema", "$comment", "$defs", "definitions"];
+
+/// Keywords that depend on where they are in the document, a member with them is never merged.
+const POSITIONAL: [&str; 6] = [
+    "$id",
+    "$anchor",
+    "$dynamicAnchor",
+    "$dynamicRef",
+    "unevaluatedItems",
+    "unevaluatedProperties",
+];
+
+/// Keywords that only have a meaning together, they are merged only when one side has them.
+const GROUPS: [&[&str]; 2] = [
+    &["contains", "minContains", "maxContains"],
+    &["if", "then", "else"],
+];
+
+#[derive(Debug, Default)]
+pub struct Simplification {
+    /// schemas that were rewritten, sorted.
+    pub simplified: Vec<Url>,
+    /// schemas that can never be satisfied and were replaced with `false`, sorted.
+    pub unsatisfiable: Vec<Url>,
+}
+
+/// Why two schemas can't be merged.
+enum Conflict {
+    /// the merged schema would not mean the same.
+    Unsafe,
+    /// no value is valid against both.
+    Unsatisfiable,
+}
+
+/// Simplify every schema in the context in place.
+pub fn simplify(context: &mut Context) -> Simplification {
+    let mut urls: Vec<Url> = context.schemas.keys().cloned().collect();
+    urls.sort();
+    let mut simplifier = Simplifier {
+        context,
+        done: HashSet::new(),
+        active: HashSet::new(),
+        report: Simplification::default(),
+    };
+    for url in &urls {
+        simplifier.schema(url);
+    }
+    let mut report = simplifier.report;
+    report.simplified.sort();
+    report.unsatisfiable.sort();
+    report
+}
+
+struct Simplifier<'c> {
+    context: &'c mut Context,
+    done: HashSet<Url>,
+    /// schemas being simplified, a member that is one of them is part of a cycle.
+    active: HashSet<Url>,
+    report: Simplification,
+}
+
+impl Simplifier<'_> {
+    fn schema(&mut self, url: &Url) {
+        if self.done.contains(url) || !self.active.insert(url.clone()) {
+            return;
+        }
+        let members: Vec<Url> = match self.context.schema(url) {
+            Some(ResolvedJsonSchema::Object(keywords)) => keywords
+                .all_of
+                .iter()
+                .flat_map(|all_of| &all_of.0)
+                .filter_map(|member| match member {
+                    JsonSchema::Resolved(url) => Some(url.clone()),
+                    _ => None,
+                })
+                .collect(),
+            _ => vec![],
+        };
+        // members are simplified first, so whatever is left of their `allOf` can't be merged
+        for member in &members {
+            self.schema(member);
+        }
+        if let Some(ResolvedJsonSchema::Object(keywords)) = self.context.schema(url) {
+            if let Ok(Value::Object(schema)) = serde_json::to_value(keywords) {
+                self.simplify(url, schema);
+            }
+        }
+        self.active.remove(url);
+        self.done.insert(url.clone());
+    }
+
+    fn simplify(&mut self, url: &Url, original: Map<String, Value>) {
+        let mut schema = original.clone();
+        let members = match schema.remove("allOf") {
+            Some(Value::Array(members)) => members,
+            _ => vec![],
+        };
+        let mut remaining: Vec<Value> = vec![];
+        let mut synthetic = vec![];
+        let mut result = Ok(());
+        for member in members {
+            let Some(member_url) = member.as_str().and_then(|url| Url::parse(url).ok()) else {
+                remaining.push(member);
+                continue;
+            };
+            let member_schema = match self.context.schema(&member_url) {
+                Some(ResolvedJsonSchema::Bool(true)) => continue,
+                Some(ResolvedJsonSchema::Bool(false)) => {
+                    result = Err(Conflict::Unsatisfiable);
+                    break;
+                }
+                Some(ResolvedJsonSchema::Object(keywords))
+                    if !self.active.contains(&member_url) =>
+                {
+                    serde_json::to_value(keywords)
+                }
+                _ => {
+                    remaining.push(member);
+                    continue;
+                }
+            };
+            let Ok(Value::Object(mut member_schema)) = member_schema else {
+                remaining.push(member);
+                continue;
+            };
+            let nested = member_schema.remove("allOf");
+            let mut merged = schema.clone();
+            let mut pending = vec![];
+            match self.merge(url, &mut merged, member_schema, &synthetic, &mut pending) {
+                Ok(()) => {
+                    schema = merged;
+                    synthetic.append(&mut pending);
+                    if let Some(Value::Array(nested)) = nested {
+                        remaining.extend(nested);
+                    }
+                }
+                Err(Conflict::Unsafe) => remaining.push(member),
+                Err(Conflict::Unsatisfiable) => {
+                    result = Err(Conflict::Unsatisfiable);
+                    break;
+                }
+            }
+        }
+        let mut seen = HashSet::new();
+        remaining.retain(|member| seen.insert(member.to_string()));
+        if !remaining.is_empty() {
+            schema.insert("allOf".to_owned(), Value::Array(remaining));
+        }
+
+        if let Err(Conflict::Unsatisfiable) = result.and_then(|()| tidy(&mut schema)) {
+            self.context
+                .schemas
+                .insert(url.clone(), ResolvedJsonSchema::Bool(false));
+            self.report.unsatisfiable.push(url.clone());
+            return;
+        }
+        if schema == original {
+            return;
+        }
+        // anything that doesn't survive the round trip is left as it was
+        let Ok(keywords) = serde_json::from_value::<Keywords>(Value::Object(schema)) else {
+            return;
+        };
+        self.context
+            .schemas
+            .insert(url.clone(), ResolvedJsonSchema::Object(keywords));
+        self.report.simplified.push(url.clone());
+        let mut added = vec![];
+        for (url, schema) in synthetic {
+            if let Ok(keywords) = serde_json::from_value::<Keywords>(Value::Object(schema)) {
+                self.context
+                    .schemas
+                    .insert(url.clone(), ResolvedJsonSchema::Object(keywords));
+                added.push(url);
+            }
+        }
+        for url in &added {
+            self.schema(url);
+        }
+    }
+
+    /// Merge the keywords of a member into the schema at `url`. New schemas for subschemas on
+    /// both sides are added to `pending`, `synthetic` holds those of earlier members.
+    fn merge(
+        &self,
+        url: &Url,
+        schema: &mut Map<String, Value>,
+        member: Map<String, Value>,
+        synthetic: &[(Url, Map<String, Value>)],
+        pending: &mut Vec<(Url, Map<String, Value>)>,
+    ) -> Result<(), Conflict> {
+        let has = |schema: &Map<String, Value>, keywords: &[&str]| {
+            keywords.iter().any(|keyword| schema.contains_key(*keyword))
+        };
+        for group in GROUPS {
+            if has(schema, group) && has(&member, group) {
+                return Err(Conflict::Unsafe);
+            }
+        }
+        // `items` applies after the `prefixItems` of its own schema and `additionalProperties` to
+        // what the `properties` of its own schema don't name
+        let dependents = [
+            ("prefixItems", ["prefixItems", "items"]),
+            ("additionalProperties", ["properties", "patternProperties"]),
+        ];
+        for (keyword, others) in dependents {
+            if schema.contains_key(keyword) && has(&member, &others)
+                || member.contains_key(keyword) && has(schema, &others)
+            {
+                return Err(Conflict::Unsafe);
+            }
+        }
+
+        for (keyword, value) in member {
+            if IGNORED.contains(&keyword.as_str()) {
+                continue;
+            }
+            if POSITIONAL.contains(&keyword.as_str()) {
+                return Err(Conflict::Unsafe);
+            }
+            let Some(current) = schema.get_mut(&keyword) else {
+                schema.insert(keyword, value);
+                continue;
+            };
+            if json_eq(&*current, &value) {
+                continue;
+            }
+            match keyword.as_str() {
+                // the annotations of the schema itself win
+                "title" | "description" | "default" | "examples" | "deprecated" | "readOnly"
+                | "writeOnly" => {}
+                "type" => {
+                    let mask = mask(current) & mask(&value);
+                    if mask == 0 {
+                        return Err(Conflict::Unsatisfiable);
+                    }
+                    *current = type_keyword(mask);
+                }
+                "minimum" | "exclusiveMinimum" | "minLength" | "minItems" | "minProperties" => {
+                    *current = bound(current, value, Ordering::Greater)?;
+                }
+                "maximum" | "exclusiveMaximum" | "maxLength" | "maxItems" | "maxProperties" => {
+                    *current = bound(current, value, Ordering::Less)?;
+                }
+                "multipleOf" => {
+                    let (Some(l), Some(r)) = (current.as_i64(), value.as_i64()) else {
+                        return Err(Conflict::Unsafe);
+                    };
+                    match (l, r) {
+                        (l, r) if r != 0 && l % r == 0 => {}
+                        (l, r) if l != 0 && r % l == 0 => *current = value,
+                        _ => return Err(Conflict::Unsafe),
+                    }
+                }
+                "const" if !is_data(current) && !is_data(&value) => {
+                    return Err(Conflict::Unsatisfiable)
+                }
+                "enum" => {
+                    let (Value::Array(values), Value::Array(other)) = (&mut *current, &value)
+                    else {
+                        return Err(Conflict::Unsafe);
+                    };
+                    values.retain(|value| other.iter().any(|other| json_eq(value, other)));
+                    if values.is_empty() {
+                        return Err(Conflict::Unsatisfiable);
+                    }
+                }
+                "required" => {
+                    let (Value::Array(names), Value::Array(other)) = (&mut *current, value) else {
+                        return Err(Conflict::Unsafe);
+                    };
+                    for name in other {
+                        if !names.contains(&name) {
+                            names.push(name);
+                        }
+                    }
+                }
+                "uniqueItems" => *current = Value::Bool(true),
+                "properties" | "patternProperties" => {
+                    let (Value::Object(current), Value::Object(other)) = (&mut *current, value)
+                    else {
+                        return Err(Conflict::Unsafe);
+                    };
+                    for (name, subschema) in other {
+                        let pointer = format!("/{keyword}/{}", escape(&name));
+                        match current.get_mut(&name) {
+                            None => {
+                                current.insert(name, subschema);
+                            }
+                            Some(existing) if *existing == subschema => {}
+                            Some(existing) => {
+                                let schemas = [existing.take(), subschema];
+                                *existing = self.all_of(url, &pointer, schemas, synthetic, pending);
+                            }
+                        }
+                    }
+                }
+                "items" | "propertyNames" | "additionalProperties" => {
+                    let pointer = format!("/{keyword}");
+                    let schemas = [current.take(), value];
+                    *current = self.all_of(url, &pointer, schemas, synthetic, pending);
+                }
+                _ => return Err(Conflict::Unsafe),
+            }
+        }
+        Ok(())
+    }
+
+    /// Add a schema with an `allOf` of two subschemas of the schema at `url`, at a location that
+    /// is not in use, and return its url.
+    fn all_of(
+        &self,
+        url: &Url,
+        pointer: &str,
+        schemas: [Value; 2],
+        synthetic: &[(Url, Map<String, Value>)],
+        pending: &mut Vec<(Url, Map<String, Value>)>,
+    ) -> Value {
+        let mut fragment = format!("{}{pointer}/allOf", url.fragment().unwrap_or_default());
+        let location = loop {
+            let mut location = url.clone();
+            location.set_fragment(Some(&fragment));
+            let taken = self.context.schemas.contains_key(&location)
+                || synthetic
+                    .iter()
+                    .chain(pending.iter())
+                    .any(|(url, _)| *url == location);
+            if !taken {
+                break location;
+            }
+            fragment.push_str("/allOf");
+        };
+        let mut schema = Map::new();
+        schema.insert("allOf".to_owned(), Value::Array(schemas.to_vec()));
+        pending.push((location.clone(), schema));
+        Value::String(location.to_string())
+    }
+}
+
+/// Remove keywords made redundant by others and look for conflicts within one schema.
+fn tidy(schema: &mut Map<String, Value>) -> Result<(), Conflict> {
+    let types = schema.get("type").map(mask);
+    if types == Some(0) {
+        return Err(Conflict::Unsatisfiable);
+    }
+
+    // an inclusive bound next to an exclusive one is redundant unless it is stricter
+    let bounds = [
+        ("minimum", "exclusiveMinimum", Ordering::Less),
+        ("maximum", "exclusiveMaximum", Ordering::Greater),
+    ];
+    for (inclusive, exclusive, looser) in bounds {
+        if let (Some(Value::Number(limit)), Some(Value::Number(exclusive_limit))) =
+            (schema.get(inclusive), schema.get(exclusive))
+        {
+            match compare_numbers(exclusive_limit, limit) {
+                Some(ordering) if ordering != looser => schema.remove(inclusive),
+                Some(_) => schema.remove(exclusive),
+                None => None,
+            };
+        }
+    }
+
+    if let Some(constant) = schema.get("const").filter(|constant| !is_data(constant)) {
+        if types.is_some_and(|types| !is_type(constant, types)) {
+            return Err(Conflict::Unsatisfiable);
+        }
+        if let Some(Value::Array(values)) = schema.get("enum") {
+            if !values.iter().any(|value| json_eq(value, constant)) {
+                return Err(Conflict::Unsatisfiable);
+            }
+            schema.remove("enum");
+        }
+    }
+    if let (Some(types), Some(Value::Array(values))) = (types, schema.get_mut("enum")) {
+        values.retain(|value| is_type(value, types));
+        if values.is_empty() {
+            return Err(Conflict::Unsatisfiable);
+        }
+    }
+
+    // bounds that no value of the only allowed kind is within
+    let lower = ["minimum", "exclusiveMinimum"]
+        .into_iter()
+        .find_map(|keyword| Some((schema.get(keyword)?.as_number()?, keyword != "minimum")));
+    let upper = ["maximum", "exclusiveMaximum"]
+        .into_iter()
+        .find_map(|keyword| Some((schema.get(keyword)?.as_number()?, keyword != "maximum")));
+    if let (Some((lower, lower_exclusive)), Some((upper, upper_exclusive))) = (lower, upper) {
+        let empty = match compare_numbers(lower, upper) {
+            Some(Ordering::Greater) => true,
+            Some(Ordering::Equal) => lower_exclusive || upper_exclusive,
+            _ => false,
+        };
+        if empty && types.is_some_and(|types| types & !(INTEGER | NUMBER) == 0) {
+            return Err(Conflict::Unsatisfiable);
+        }
+    }
+    let lengths = [
+        ("minLength", "maxLength", STRING),
+        ("minItems", "maxItems", ARRAY),
+        ("minProperties", "maxProperties", OBJECT),
+    ];
+    for (min, max, kind) in lengths {
+        if let (Some(Value::Number(min)), Some(Value::Number(max))) =
+            (schema.get(min), schema.get(max))
+        {
+            if compare_numbers(min, max) == Some(Ordering::Greater) && types == Some(kind) {
+                return Err(Conflict::Unsatisfiable);
+            }
+        }
+    }
+
+    // keywords at their default
+    for keyword in ["minLength", "minItems", "minProperties"] {
+        if schema.get(keyword).and_then(Value::as_u64) == Some(0) {
+            schema.remove(keyword);
+        }
+    }
+    if schema.get("uniqueItems") == Some(&Value::Bool(false)) {
+        schema.remove("uniqueItems");
+    }
+    Ok(())
+}
+
+/// The stricter of two bounds, `stricter` is the ordering of a stricter bound to a looser one.
+fn bound(current: &Value, value: Value, stricter: Ordering) -> Result<Value, Conflict> {
+    let (Value::Number(current_limit), Value::Number(limit)) = (current, &value) else {
+        return Err(Conflict::Unsafe);
+    };
+    match compare_numbers(limit, current_limit) {
+        Some(ordering) if ordering == stricter => Ok(value),
+        Some(_) => Ok(current.clone()),
+        None => Err(Conflict::Unsafe),
+    }
+}
+
+fn is_data(value: &Value) -> bool {
+    value.get("$data").is_some()
+}
+
+/// The mask of a serialized `type` keyword.
+fn mask(value: &Value) -> u8 {
+    match value {
+        Value::String(ty) => type_mask(ty),
+        Value::Array(types) => types
+            .iter()
+            .filter_map(Value::as_str)
+            .fold(0, |mask, ty| mask | type_mask(ty)),
+        _ => 0,
+    }
+}
+
+/// The `type` keyword for a mask, `number` covers `integer`.
+fn type_keyword(mask: u8) -> Value {
+    let mut types: Vec<Value> = TYPES
+        .iter()
+        .filter(|(name, bits)| mask & bits == *bits && !(*name == "integer" && mask & NUMBER != 0))
+        .map(|(name, _)| Value::String((*name).to_owned()))
+        .collect();
+    match types.len() {
+        1 => types.remove(0),
+        _ => Value::Array(types),
+    }
+}
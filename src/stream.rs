@@ -0,0 +1,704 @@
+//! Validation driven by a serde [`Deserializer`], without building a [`Value`] for the instance.
+//!
+//! Every value of the instance is visited with the nodes of the [`SchemaTree`] that apply to it.
+//! Objects and arrays are validated entry by entry, only the location and per node counters are
+//! kept, so memory stays proportional to the nesting depth. Nodes that need to look at a value as
+//! a whole (`uniqueItems`, `const`, `enum`, `dependent*`, `anyOf`, `oneOf`, `not`, `if` and
+//! `unevaluated*`) make the value they apply to be buffered and handed to the [`Validator`].
+
+use crate::{
+    tree::{Op, SchemaTree, ARRAY, OBJECT},
+    validator::{escape, type_message, ValidationError, Validator},
+};
+use serde::de::{
+    self, Deserialize, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor,
+};
+use serde_json::{Map, Number, Value};
+use std::fmt::{self, Write};
+
+#[derive(Debug)]
+pub enum StreamError<E> {
+    /// the deserializer or serializer failed, validation stopped at that point.
+    Source(E),
+    Invalid(Vec<ValidationError>),
+}
+
+impl<E: fmt::Display> fmt::Display for StreamError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamError::Source(error) => write!(f, "{error}"),
+            StreamError::Invalid(errors) => {
+                write!(f, "instance is invalid")?;
+                for error in errors {
+                    write!(f, "\n{error}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for StreamError<E> {}
+
+/// Validate the value produced by `deserializer` against a node of the tree.
+pub fn validate<'de, D: Deserializer<'de>>(
+    tree: &SchemaTree,
+    node: usize,
+    deserializer: D,
+) -> Result<(), StreamError<D::Error>> {
+    // `$data` references can point anywhere in the document, it has to be buffered as a whole
+    if tree.has_data() {
+        let value = Value::deserialize(deserializer).map_err(StreamError::Source)?;
+        let mut validator = Validator::new(tree);
+        return match validator.validate(node, &value) {
+            true => Ok(()),
+            false => Err(StreamError::Invalid(validator.errors)),
+        };
+    }
+    let mut stream = Stream::new(tree);
+    let targets = [Target {
+        node,
+        keyword: String::new(),
+        quiet: false,
+    }];
+    let valid = Seed {
+        stream: &mut stream,
+        targets: &targets,
+    }
+    .deserialize(deserializer)
+    .map_err(StreamError::Source)?;
+    match valid[0] {
+        true => Ok(()),
+        false => Err(StreamError::Invalid(stream.into_errors())),
+    }
+}
+
+/// A node applied to the current value.
+pub(crate) struct Target {
+    pub node: usize,
+    /// json pointer to the node from the root schema.
+    pub keyword: String,
+    /// errors are not recorded, e.g. for `contains`.
+    pub quiet: bool,
+}
+
+/// A node applied to the current value after following `allOf` and `$dynamicRef`.
+struct Applied {
+    /// the index of the target the node was reached from.
+    target: usize,
+    node: usize,
+    keyword: String,
+    quiet: bool,
+    valid: bool,
+    /// the number of properties or items seen so far.
+    count: u64,
+    /// the number of items that matched `contains`.
+    contains: u64,
+}
+
+/// The nodes that apply to a property or item, with the node each was reached from.
+#[derive(Default)]
+pub(crate) struct Children {
+    roles: Vec<(usize, Role)>,
+    pub targets: Vec<Target>,
+}
+
+impl Children {
+    fn push(&mut self, applied: usize, role: Role, target: Target) {
+        self.roles.push((applied, role));
+        self.targets.push(target);
+    }
+}
+
+/// An object or array that is validated entry by entry.
+pub(crate) struct Container {
+    kind: u8,
+    /// the number of targets the nodes were reached from.
+    targets: usize,
+    applied: Vec<Applied>,
+    /// which of the `required` properties of every node were seen.
+    seen: Vec<Vec<bool>>,
+    /// the length of the instance location of the container itself.
+    base: usize,
+    index: usize,
+}
+
+/// How the result of a child relates to the node it was applied from.
+#[derive(Clone, Copy, PartialEq)]
+enum Role {
+    Apply,
+    Contains,
+}
+
+pub(crate) struct Stream<'a> {
+    tree: &'a SchemaTree,
+    /// json pointer to the current value.
+    instance: String,
+    errors: Vec<ValidationError>,
+}
+
+impl<'a> Stream<'a> {
+    pub(crate) fn new(tree: &'a SchemaTree) -> Self {
+        Self {
+            tree,
+            instance: String::new(),
+            errors: vec![],
+        }
+    }
+
+    /// Start validating an object or array, returns `None` if a node has to see it as a whole
+    /// and it needs to be buffered.
+    pub(crate) fn begin(&mut self, targets: &[Target], kind: u8) -> Option<Container> {
+        let mut applied = self.apply(targets);
+        if applied.iter().any(|a| self.needs_buffer(a.node, kind)) {
+            return None;
+        }
+        self.start(&mut applied, kind);
+        let seen = applied
+            .iter()
+            .map(|a| {
+                let required = self.tree.node(a.node).ops.iter().find_map(|op| match op {
+                    Op::Required(properties) if kind == OBJECT => Some(properties.len()),
+                    _ => None,
+                });
+                vec![false; required.unwrap_or(0)]
+            })
+            .collect();
+        Some(Container {
+            kind,
+            targets: targets.len(),
+            applied,
+            seen,
+            base: self.instance.len(),
+            index: 0,
+        })
+    }
+
+    /// Start the object wrapping an externally tagged enum variant and the object or array inside
+    /// it, returns `None` if either of them needs to be buffered.
+    pub(crate) fn begin_variant(
+        &mut self,
+        targets: &[Target],
+        variant: &str,
+        kind: u8,
+    ) -> Option<(Container, Children, Container)> {
+        let errors = self.errors.len();
+        let mut outer = self.begin(targets, OBJECT)?;
+        let children = self.key(&mut outer, variant);
+        match self.begin(&children.targets, kind) {
+            Some(inner) => Some((outer, children, inner)),
+            None => {
+                // the buffered value is validated from the start, errors reported so far are dropped
+                self.errors.truncate(errors);
+                self.instance.truncate(outer.base);
+                None
+            }
+        }
+    }
+
+    /// Enter a property of an object, returns the nodes that apply to its value.
+    pub(crate) fn key(&mut self, object: &mut Container, key: &str) -> Children {
+        let tree = self.tree;
+        for (a, seen) in object.applied.iter().zip(object.seen.iter_mut()) {
+            for op in tree.node(a.node).ops.iter() {
+                if let Op::Required(properties) = op {
+                    if let Some(i) = properties.iter().position(|p| p == key) {
+                        seen[i] = true;
+                    }
+                }
+            }
+        }
+        self.instance.push('/');
+        self.instance.push_str(&escape(key));
+        self.property_name(&mut object.applied, key);
+        self.property_targets(&object.applied, key)
+    }
+
+    /// Enter the next item of an array, returns the nodes that apply to it.
+    pub(crate) fn item(&mut self, array: &mut Container) -> Children {
+        let _ = write!(self.instance, "/{}", array.index);
+        self.item_targets(&array.applied, array.index)
+    }
+
+    /// Leave a property or item with the validity of its value against each of the children.
+    pub(crate) fn finish(
+        &mut self,
+        container: &mut Container,
+        children: &Children,
+        valid: Vec<bool>,
+    ) {
+        Self::merge(&mut container.applied, children, valid);
+        container.index += 1;
+        self.instance.truncate(container.base);
+    }
+
+    /// Finish an object or array, returns whether it is valid against each target.
+    pub(crate) fn end(&mut self, mut container: Container) -> Vec<bool> {
+        self.instance.truncate(container.base);
+        match container.kind {
+            OBJECT => self.end_object(&mut container.applied, &container.seen),
+            _ => self.end_array(&mut container.applied),
+        }
+        let mut valid = vec![true; container.targets];
+        for applied in &container.applied {
+            valid[applied.target] &= applied.valid;
+        }
+        valid
+    }
+
+    pub(crate) fn into_errors(self) -> Vec<ValidationError> {
+        self.errors
+    }
+
+    fn fail(&mut self, quiet: bool, keyword: &str, name: &str, message: impl FnOnce() -> String) {
+        if !quiet {
+            self.errors.push(ValidationError {
+                instance_location: self.instance.clone(),
+                keyword_location: match name {
+                    "" => keyword.to_owned(),
+                    name => format!("{keyword}/{name}"),
+                },
+                message: message(),
+            });
+        }
+    }
+
+    /// Validate a value that is held in memory.
+    pub(crate) fn validate_value(&mut self, targets: &[Target], value: &Value) -> Vec<bool> {
+        targets
+            .iter()
+            .map(|target| {
+                let mut validator = match target.quiet {
+                    true => Validator::short_circuit(self.tree),
+                    false => Validator::new(self.tree),
+                };
+                let valid =
+                    validator.validate_at(target.node, value, &self.instance, &target.keyword);
+                self.errors.append(&mut validator.errors);
+                valid
+            })
+            .collect()
+    }
+
+    /// The nodes that apply to a value, following `allOf` and `$dynamicRef` to the nodes they
+    /// apply to the same value.
+    fn apply(&self, targets: &[Target]) -> Vec<Applied> {
+        let mut applied = vec![];
+        for (i, target) in targets.iter().enumerate() {
+            let mut pending = vec![(target.node, target.keyword.clone())];
+            while let Some((node, keyword)) = pending.pop() {
+                for op in self.tree.node(node).ops.iter() {
+                    match op {
+                        Op::AllOf(nodes) => {
+                            for (j, node) in nodes.iter().enumerate() {
+                                pending.push((*node, format!("{keyword}/allOf/{j}")));
+                            }
+                        }
+                        Op::DynamicRef(node) => {
+                            pending.push((*node, format!("{keyword}/$dynamicRef")))
+                        }
+                        _ => {}
+                    }
+                }
+                applied.push(Applied {
+                    target: i,
+                    node,
+                    keyword,
+                    quiet: target.quiet,
+                    valid: true,
+                    count: 0,
+                    contains: 0,
+                });
+            }
+        }
+        applied
+    }
+
+    /// Whether a node has to see an object or array as a whole.
+    fn needs_buffer(&self, node: usize, kind: u8) -> bool {
+        let node = self.tree.node(node);
+        node.collects
+            || node.ops.iter().any(|op| match op {
+                Op::Const(_)
+                | Op::Enum(_)
+                | Op::AnyOf(_)
+                | Op::OneOf(_)
+                | Op::Not(_)
+                | Op::IfThenElse { .. }
+                | Op::UnevaluatedItems(_)
+                | Op::UnevaluatedProperties(_) => true,
+                Op::UniqueItems => kind == ARRAY,
+                Op::DependentRequired(_) | Op::DependentSchemas(_) => kind == OBJECT,
+                _ => false,
+            })
+    }
+
+    /// Check the keywords that do not depend on the content of an object or array.
+    fn start(&mut self, applied: &mut [Applied], kind: u8) {
+        let tree = self.tree;
+        for applied in applied.iter_mut() {
+            for op in tree.node(applied.node).ops.iter() {
+                let (quiet, keyword) = (applied.quiet, applied.keyword.as_str());
+                let valid = match op {
+                    Op::False => {
+                        self.fail(quiet, keyword, "", || {
+                            "false schema does not allow any value".to_owned()
+                        });
+                        false
+                    }
+                    Op::Unresolved(url) => {
+                        self.fail(quiet, keyword, "$ref", || {
+                            format!("{url} could not be resolved")
+                        });
+                        false
+                    }
+                    Op::Invalid(name, message) => {
+                        self.fail(quiet, keyword, name, || message.clone());
+                        false
+                    }
+                    Op::Type(mask) if mask & kind == 0 => {
+                        let actual = if kind == OBJECT { "object" } else { "array" };
+                        self.fail(quiet, keyword, "type", || type_message(actual, *mask));
+                        false
+                    }
+                    _ => true,
+                };
+                applied.valid &= valid;
+            }
+        }
+    }
+
+    /// Check the keywords that count the properties of an object once all were seen.
+    fn end_object(&mut self, applied: &mut [Applied], seen: &[Vec<bool>]) {
+        let tree = self.tree;
+        for (applied, seen) in applied.iter_mut().zip(seen) {
+            let (quiet, keyword, count) = (applied.quiet, applied.keyword.as_str(), applied.count);
+            for op in tree.node(applied.node).ops.iter() {
+                let valid = match op {
+                    Op::MinProperties(limit) if count < *limit => {
+                        self.fail(quiet, keyword, "minProperties", || {
+                            format!("object has {count} properties, limit is {limit}")
+                        });
+                        false
+                    }
+                    Op::MaxProperties(limit) if count > *limit => {
+                        self.fail(quiet, keyword, "maxProperties", || {
+                            format!("object has {count} properties, limit is {limit}")
+                        });
+                        false
+                    }
+                    Op::Required(properties) => {
+                        let mut valid = true;
+                        for (property, seen) in properties.iter().zip(seen) {
+                            if !seen {
+                                self.fail(quiet, keyword, "required", || {
+                                    format!("missing required property {property}")
+                                });
+                                valid = false;
+                            }
+                        }
+                        valid
+                    }
+                    _ => true,
+                };
+                applied.valid &= valid;
+            }
+        }
+    }
+
+    /// Check the keywords that count the items of an array once all were seen.
+    fn end_array(&mut self, applied: &mut [Applied]) {
+        let tree = self.tree;
+        for applied in applied.iter_mut() {
+            let (quiet, keyword, count) = (applied.quiet, applied.keyword.as_str(), applied.count);
+            let contains = applied.contains;
+            for op in tree.node(applied.node).ops.iter() {
+                let valid = match op {
+                    Op::MinItems(limit) if count < *limit => {
+                        self.fail(quiet, keyword, "minItems", || {
+                            format!("array has {count} items, limit is {limit}")
+                        });
+                        false
+                    }
+                    Op::MaxItems(limit) if count > *limit => {
+                        self.fail(quiet, keyword, "maxItems", || {
+                            format!("array has {count} items, limit is {limit}")
+                        });
+                        false
+                    }
+                    Op::Contains { min, .. } if contains < *min => {
+                        self.fail(quiet, keyword, "contains", || {
+                            format!(
+                                "array contains {contains} matching items, expected at least {min}"
+                            )
+                        });
+                        false
+                    }
+                    Op::Contains { max: Some(max), .. } if contains > *max => {
+                        self.fail(quiet, keyword, "maxContains", || {
+                            format!(
+                                "array contains {contains} matching items, expected at most {max}"
+                            )
+                        });
+                        false
+                    }
+                    _ => true,
+                };
+                applied.valid &= valid;
+            }
+        }
+    }
+
+    /// The nodes that apply to the value of a property.
+    fn property_targets(&self, applied: &[Applied], key: &str) -> Children {
+        let mut children = Children::default();
+        for (i, applied) in applied.iter().enumerate() {
+            let mut child = |node: usize, keyword: String| {
+                let quiet = applied.quiet;
+                children.push(
+                    i,
+                    Role::Apply,
+                    Target {
+                        node,
+                        keyword,
+                        quiet,
+                    },
+                )
+            };
+            for op in self.tree.node(applied.node).ops.iter() {
+                match op {
+                    Op::Properties(properties) => {
+                        if let Ok(j) =
+                            properties.binary_search_by(|(name, _)| name.as_str().cmp(key))
+                        {
+                            let (name, node) = &properties[j];
+                            child(
+                                *node,
+                                format!("{}/properties/{}", applied.keyword, escape(name)),
+                            );
+                        }
+                    }
+                    Op::PatternProperties(patterns) => {
+                        for (regex, node) in patterns.iter() {
+                            if regex.is_match(key) {
+                                child(
+                                    *node,
+                                    format!(
+                                        "{}/patternProperties/{}",
+                                        applied.keyword,
+                                        escape(regex.as_str())
+                                    ),
+                                );
+                            }
+                        }
+                    }
+                    Op::AdditionalProperties {
+                        node,
+                        properties,
+                        patterns,
+                    } => {
+                        let in_props = properties
+                            .binary_search_by(|name| name.as_str().cmp(key))
+                            .is_ok();
+                        if !in_props && !patterns.iter().any(|regex| regex.is_match(key)) {
+                            child(*node, format!("{}/additionalProperties", applied.keyword));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        children
+    }
+
+    /// Validate a property name against `propertyNames`.
+    fn property_name(&mut self, applied: &mut [Applied], key: &str) {
+        let tree = self.tree;
+        let mut name = None;
+        for applied in applied.iter_mut() {
+            for op in tree.node(applied.node).ops.iter() {
+                if let Op::PropertyNames(node) = op {
+                    let name = name.get_or_insert_with(|| Value::String(key.to_owned()));
+                    let target = Target {
+                        node: *node,
+                        keyword: format!("{}/propertyNames", applied.keyword),
+                        quiet: applied.quiet,
+                    };
+                    applied.valid &= self.validate_value(&[target], name)[0];
+                }
+            }
+        }
+    }
+
+    /// The nodes that apply to the item at `index`.
+    fn item_targets(&self, applied: &[Applied], index: usize) -> Children {
+        let mut children = Children::default();
+        for (i, applied) in applied.iter().enumerate() {
+            for op in self.tree.node(applied.node).ops.iter() {
+                let (role, node, keyword) = match op {
+                    Op::PrefixItems(nodes) if index < nodes.len() => (
+                        Role::Apply,
+                        nodes[index],
+                        format!("{}/prefixItems/{index}", applied.keyword),
+                    ),
+                    Op::Items { node, skip } if index >= *skip => {
+                        (Role::Apply, *node, format!("{}/items", applied.keyword))
+                    }
+                    Op::Contains { node, .. } => (
+                        Role::Contains,
+                        *node,
+                        format!("{}/contains", applied.keyword),
+                    ),
+                    _ => continue,
+                };
+                let quiet = applied.quiet || role == Role::Contains;
+                children.push(
+                    i,
+                    role,
+                    Target {
+                        node,
+                        keyword,
+                        quiet,
+                    },
+                );
+            }
+        }
+        children
+    }
+
+    /// Fold the results of a property or item back into the nodes it was reached from.
+    fn merge(applied: &mut [Applied], children: &Children, valid: Vec<bool>) {
+        for applied in applied.iter_mut() {
+            applied.count += 1;
+        }
+        for ((i, role), valid) in children.roles.iter().zip(valid) {
+            match role {
+                Role::Apply => applied[*i].valid &= valid,
+                Role::Contains => applied[*i].contains += valid as u64,
+            }
+        }
+    }
+}
+
+struct Seed<'s, 'a> {
+    stream: &'s mut Stream<'a>,
+    targets: &'s [Target],
+}
+
+impl<'de> DeserializeSeed<'de> for Seed<'_, '_> {
+    /// whether the value is valid against each target.
+    type Value = Vec<bool>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Seed<'_, '_> {
+    type Value = Vec<bool>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any json value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(self.stream.validate_value(self.targets, &Value::Bool(v)))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(self.stream.validate_value(self.targets, &Value::from(v)))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(self.stream.validate_value(self.targets, &Value::from(v)))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        let number = Number::from_f64(v).ok_or_else(|| E::custom("number is not finite"))?;
+        Ok(self
+            .stream
+            .validate_value(self.targets, &Value::Number(number)))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(self
+            .stream
+            .validate_value(self.targets, &Value::String(v.to_owned())))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(self.stream.validate_value(self.targets, &Value::String(v)))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(self.stream.validate_value(self.targets, &Value::Null))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        self.visit_unit()
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let Seed { stream, targets } = self;
+        let Some(mut object) = stream.begin(targets, OBJECT) else {
+            let mut object = Map::new();
+            while let Some(key) = map.next_key::<String>()? {
+                object.insert(key, map.next_value()?);
+            }
+            return Ok(stream.validate_value(targets, &Value::Object(object)));
+        };
+        while let Some(key) = map.next_key::<String>()? {
+            let children = stream.key(&mut object, &key);
+            // values no node applies to are skipped without being buffered
+            let valid = if children.targets.is_empty() {
+                map.next_value::<IgnoredAny>()?;
+                vec![]
+            } else {
+                map.next_value_seed(Seed {
+                    stream,
+                    targets: &children.targets,
+                })?
+            };
+            stream.finish(&mut object, &children, valid);
+        }
+        Ok(stream.end(object))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let Seed { stream, targets } = self;
+        let Some(mut array) = stream.begin(targets, ARRAY) else {
+            let mut array = vec![];
+            while let Some(item) = seq.next_element()? {
+                array.push(item);
+            }
+            return Ok(stream.validate_value(targets, &Value::Array(array)));
+        };
+        loop {
+            let children = stream.item(&mut array);
+            let valid = if children.targets.is_empty() {
+                seq.next_element::<IgnoredAny>()?.map(|_| vec![])
+            } else {
+                seq.next_element_seed(Seed {
+                    stream,
+                    targets: &children.targets,
+                })?
+            };
+            match valid {
+                Some(valid) => stream.finish(&mut array, &children, valid),
+                None => break,
+            }
+        }
+        Ok(stream.end(array))
+    }
+}
@@ -0,0 +1,693 @@
+//! Decide whether every instance of one schema is valid under another, i.e. whether A ⊆ B.
+//!
+//! The check is sound rather than complete. [`Subsumption::Yes`] is only answered when it follows
+//! from the supported keywords: `type`, numeric and length bounds, `enum`/`const`, `required`,
+//! `properties`/`additionalProperties`, `items`/`prefixItems`, `allOf`/`anyOf` of B and `$ref`
+//! (references are followed through the compiled contexts). Keywords of A outside that subset
+//! only make A smaller and are ignored, keywords of B outside of it make the answer
+//! [`Subsumption::Unknown`]. [`Subsumption::No`] is only answered with a counterexample, a value
+//! that the validator accepts under the subschema of A at its location and rejects under the
+//! subschema of B there, inside a whole instance that A accepts and B rejects.
+
+use crate::{
+    compiled::CompiledSchema,
+    keywords::{Data, Keywords},
+    schema::{JsonSchema, ResolvedJsonSchema},
+    tree::{ARRAY, BOOLEAN, INTEGER, NULL, NUMBER, OBJECT, STRING},
+    validator::{compare_numbers, escape, is_multiple_of},
+};
+use serde_json::{json, Map, Number, Value};
+use std::{cmp::Ordering, collections::HashSet, fmt::Write};
+use url::Url;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Subsumption {
+    /// every instance of A is valid under B.
+    Yes,
+    /// an instance of A is not valid under B.
+    No(Counterexample),
+    /// neither could be shown, e.g. B uses a keyword outside of the supported subset.
+    Unknown {
+        /// json pointer to the value where the check gave up.
+        instance_location: String,
+        reason: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Counterexample {
+    /// json pointer to the value where the schemas differ.
+    pub instance_location: String,
+    /// a value for that location that is valid under the subschema of A there and invalid under
+    /// the subschema of B.
+    pub value: Value,
+    /// which keywords of the schemas differ.
+    pub reason: String,
+    /// the whole instance with `value` at `instance_location`, valid under A and invalid under B.
+    pub instance: Value,
+}
+
+/// Whether every instance that is valid under `a` is valid under `b`.
+pub fn is_subschema(a: &CompiledSchema, b: &CompiledSchema) -> Subsumption {
+    let empty = Keywords::default();
+    let mut checker = Checker {
+        a,
+        b,
+        empty: &empty,
+        assumed: HashSet::new(),
+    };
+    let mut path = String::new();
+    match checker.check(Side::Url(a.location()), Side::Url(b.location()), &mut path) {
+        Ok(()) => Subsumption::Yes,
+        // the keywords around the value, like `allOf` or `not` of A, may still decide it
+        Err(Failure {
+            path,
+            reason,
+            value: Some(value),
+            instance: Some(instance),
+        }) if a.is_valid(&instance) && !b.is_valid(&instance) => Subsumption::No(Counterexample {
+            instance_location: path,
+            value,
+            reason,
+            instance,
+        }),
+        Err(Failure {
+            path,
+            reason,
+            value: Some(_),
+            ..
+        }) => Subsumption::Unknown {
+            instance_location: path,
+            reason: format!(
+                "{reason}, but the whole instance built around the value does not show it"
+            ),
+        },
+        Err(Failure { path, reason, .. }) => Subsumption::Unknown {
+            instance_location: path,
+            reason,
+        },
+    }
+}
+
+/// A schema of one of the two sides, subschemas that are absent accept everything.
+#[derive(Clone, Copy)]
+enum Side<'s> {
+    True,
+    Url(&'s Url),
+}
+
+struct Failure {
+    path: String,
+    reason: String,
+    /// a value valid under A and invalid under B at `path`.
+    value: Option<Value>,
+    /// the value with what is around it so far, built up while the failure is returned from
+    /// the subschemas.
+    instance: Option<Value>,
+}
+
+enum View<'s> {
+    Bool(bool),
+    Keywords(&'s Keywords),
+}
+
+struct Checker<'s> {
+    a: &'s CompiledSchema,
+    b: &'s CompiledSchema,
+    /// the keywords of a schema that is `true`.
+    empty: &'s Keywords,
+    /// pairs of schemas being checked, a pair reached again through recursive references is
+    /// assumed to hold.
+    assumed: HashSet<(Url, Url)>,
+}
+
+impl<'s> Checker<'s> {
+    fn view(schema: &'s CompiledSchema, side: Side<'s>) -> View<'s> {
+        let Side::Url(url) = side else {
+            return View::Bool(true);
+        };
+        match schema.context().schema(url) {
+            Some(ResolvedJsonSchema::Bool(bool)) => View::Bool(*bool),
+            Some(ResolvedJsonSchema::Object(keywords)) => View::Keywords(keywords),
+            // unresolved schemas fail every value when validating
+            None => View::Bool(false),
+        }
+    }
+
+    fn side(schema: &'s JsonSchema) -> Option<Side<'s>> {
+        match schema {
+            JsonSchema::Resolved(url) => Some(Side::Url(url)),
+            JsonSchema::Bool(true) => Some(Side::True),
+            _ => None,
+        }
+    }
+
+    fn check(&mut self, a: Side<'s>, b: Side<'s>, path: &mut String) -> Result<(), Failure> {
+        if let (Side::Url(a), Side::Url(b)) = (a, b) {
+            if !self.assumed.insert((a.clone(), b.clone())) {
+                return Ok(());
+            }
+        }
+        let reason = match self.compare(a, b, path) {
+            Ok(()) => return Ok(()),
+            Err(failure) if failure.value.is_some() => return Err(failure),
+            Err(failure) => failure,
+        };
+        // a subschema validated on its own can't read the values around it with `$data`
+        if !path.is_empty() && (self.a.tree().has_data() || self.b.tree().has_data()) {
+            return Err(reason);
+        }
+        // the failure may not hold for an actual instance, look for one before answering no
+        match self.witness(a, b) {
+            Some(value) => Err(Failure {
+                path: path.clone(),
+                reason: match reason.path == *path {
+                    true => reason.reason,
+                    false => format!("{} at {}", reason.reason, reason.path),
+                },
+                instance: Some(value.clone()),
+                value: Some(value),
+            }),
+            None => Err(reason),
+        }
+    }
+
+    fn fail(path: &str, reason: impl Into<String>) -> Result<(), Failure> {
+        Err(Failure {
+            path: path.to_owned(),
+            reason: reason.into(),
+            value: None,
+            instance: None,
+        })
+    }
+
+    fn compare(&mut self, a: Side<'s>, b: Side<'s>, path: &mut String) -> Result<(), Failure> {
+        let (a_view, b_view) = (Self::view(self.a, a), Self::view(self.b, b));
+        let (a_keywords, b_keywords) = match (a_view, b_view) {
+            (_, View::Bool(true)) | (View::Bool(false), _) => return Ok(()),
+            (_, View::Bool(false)) => return Self::fail(path, "B does not allow any value"),
+            (View::Bool(true), View::Keywords(b)) => (None, b),
+            (View::Keywords(a), View::Keywords(b)) => (Some(a), b),
+        };
+        let a_keywords = a_keywords.unwrap_or(self.empty);
+
+        if let Some(keyword) = unsupported(b_keywords, a_keywords) {
+            return Self::fail(path, format!("{keyword} of B is not supported"));
+        }
+        // a finite A is decided by validating each of its values
+        if let Some(values) = finite(a_keywords) {
+            for value in values {
+                if self.valid(self.a, a, value) && !self.valid(self.b, b, value) {
+                    return Err(Failure {
+                        path: path.clone(),
+                        reason: "a value of the enum or const of A is not valid under B".to_owned(),
+                        value: Some(value.clone()),
+                        instance: Some(value.clone()),
+                    });
+                }
+            }
+            return Ok(());
+        }
+        if finite(b_keywords).is_some() {
+            return Self::fail(path, "B only allows a fixed set of values");
+        }
+
+        let a_types = a_keywords.type_.as_ref().map_or(u8::MAX, |t| t.mask());
+        let b_types = b_keywords.type_.as_ref().map_or(u8::MAX, |t| t.mask());
+        let missing = a_types & !b_types;
+        if missing != 0 {
+            return Self::fail(path, format!("B does not allow {}", type_names(missing)));
+        }
+        if a_types & (NUMBER | INTEGER) != 0 {
+            compare_numbers_of(a_keywords, b_keywords, path)?;
+        }
+        let limits = [
+            (STRING, "minLength", "maxLength"),
+            (ARRAY, "minItems", "maxItems"),
+            (OBJECT, "minProperties", "maxProperties"),
+        ];
+        for (types, minimum, maximum) in limits {
+            if a_types & types == 0 {
+                continue;
+            }
+            let (a_min, a_max) = (limit(a_keywords, minimum), limit(a_keywords, maximum));
+            let (b_min, b_max) = (limit(b_keywords, minimum), limit(b_keywords, maximum));
+            if b_min.unwrap_or(0) > a_min.unwrap_or(0) {
+                return Self::fail(path, format!("{minimum} of B is greater than of A"));
+            }
+            if let Some(b_max) = b_max {
+                if a_max.is_none_or(|a_max| a_max > b_max) {
+                    return Self::fail(path, format!("{maximum} of B is less than of A"));
+                }
+            }
+        }
+        if a_types & STRING != 0 {
+            if let Some(pattern) = b_keywords.pat.as_ref().map(|k| &k.0) {
+                if a_keywords.pat.as_ref().map(|k| &k.0) != Some(pattern) {
+                    return Self::fail(path, "pattern of B differs from A");
+                }
+            }
+        }
+        if a_types & ARRAY != 0 {
+            self.compare_items(a_keywords, b_keywords, path)?;
+        }
+        if a_types & OBJECT != 0 {
+            self.compare_properties(a_keywords, b_keywords, path)?;
+        }
+
+        if let Some(all_of) = &b_keywords.all_of {
+            for schema in &all_of.0 {
+                let Some(b) = Self::side(schema) else {
+                    return Self::fail(path, "allOf of B has a schema that is not compiled");
+                };
+                self.check(a, b, path)?;
+            }
+        }
+        if let Some(any_of) = &b_keywords.any_of {
+            // A is a subset of the union if it is a subset of one of its schemas
+            let assumed = self.assumed.clone();
+            let found = any_of.0.iter().filter_map(Self::side).any(|b| {
+                let found = self.check(a, b, path).is_ok();
+                if !found {
+                    self.assumed = assumed.clone();
+                }
+                found
+            });
+            if !found {
+                return Self::fail(path, "A is not contained in a single schema of anyOf of B");
+            }
+        }
+        Ok(())
+    }
+
+    fn compare_items(
+        &mut self,
+        a: &'s Keywords,
+        b: &'s Keywords,
+        path: &mut String,
+    ) -> Result<(), Failure> {
+        if b.unique_items.as_ref().is_some_and(|k| k.0)
+            && !a.unique_items.as_ref().is_some_and(|k| k.0)
+        {
+            return Self::fail(path, "B requires unique items");
+        }
+        let prefix = |keywords: &'s Keywords| keywords.prefix_items.as_ref().map(|k| &k.0[..]);
+        let items = |keywords: &'s Keywords| keywords.items.as_ref().map(|k| &*k.0);
+        let (a_prefix, b_prefix) = (prefix(a).unwrap_or_default(), prefix(b).unwrap_or_default());
+        let item = |prefix: &'s [JsonSchema], items: Option<&'s JsonSchema>, i: usize| {
+            prefix.get(i).or(items).map_or(Some(Side::True), Self::side)
+        };
+        let truncate = path.len();
+        // the items past both prefixes are all checked against `items`
+        for i in 0..=a_prefix.len().max(b_prefix.len()) {
+            let (Some(a_item), Some(b_item)) =
+                (item(a_prefix, items(a), i), item(b_prefix, items(b), i))
+            else {
+                return Self::fail(path, "items has a schema that is not compiled");
+            };
+            let _ = write!(path, "/{i}");
+            let result = self.check(a_item, b_item, path);
+            path.truncate(truncate);
+            result.map_err(|mut failure| {
+                // the items before it are set to values their schemas of A accept
+                failure.instance = failure.instance.take().map(|value| {
+                    let mut array: Vec<Value> = (0..i)
+                        .map(|j| self.filler(item(a_prefix, items(a), j)))
+                        .collect();
+                    array.push(value);
+                    Value::Array(array)
+                });
+                failure
+            })?;
+        }
+        Ok(())
+    }
+
+    fn compare_properties(
+        &mut self,
+        a: &'s Keywords,
+        b: &'s Keywords,
+        path: &mut String,
+    ) -> Result<(), Failure> {
+        let a_required = a.required.as_ref().map(|k| &k.0[..]).unwrap_or_default();
+        if let Some(b_required) = &b.required {
+            if let Some(name) = b_required.0.iter().find(|name| !a_required.contains(name)) {
+                return Self::fail(path, format!("B requires {name} which A does not"));
+            }
+        }
+        let properties = |keywords: &'s Keywords| keywords.props.as_ref().map(|k| &k.map);
+        let additional = |keywords: &'s Keywords| {
+            // names matching a pattern of A are subject to other schemas, nothing is assumed
+            if keywords
+                .pat_props
+                .as_ref()
+                .is_some_and(|k| !k.map.is_empty())
+            {
+                return Some(Side::True);
+            }
+            keywords
+                .additional_properties
+                .as_ref()
+                .map_or(Some(Side::True), |k| Self::side(&k.0))
+        };
+        let mut names: Vec<&String> = properties(a)
+            .into_iter()
+            .chain(properties(b))
+            .flat_map(|map| map.keys())
+            .collect();
+        names.sort();
+        names.dedup();
+        let truncate = path.len();
+        for name in names {
+            let a_property = match properties(a).and_then(|map| map.get(name)) {
+                Some(schema) => Self::side(schema),
+                None => additional(a),
+            };
+            let b_property = match properties(b).and_then(|map| map.get(name)) {
+                Some(schema) => Self::side(schema),
+                None => additional(b),
+            };
+            let (Some(a_property), Some(b_property)) = (a_property, b_property) else {
+                return Self::fail(path, "properties has a schema that is not compiled");
+            };
+            path.push('/');
+            path.push_str(&escape(name));
+            let result = self.check(a_property, b_property, path);
+            path.truncate(truncate);
+            result.map_err(|failure| self.wrap(failure, a, name))?;
+        }
+        let (Some(a_additional), Some(b_additional)) = (additional(a), additional(b)) else {
+            return Self::fail(path, "additionalProperties is not compiled");
+        };
+        path.push_str("/*");
+        let result = self.check(a_additional, b_additional, path);
+        path.truncate(truncate);
+        // a name that none of the properties of A or B have
+        let mut name = "additionalProperty".to_owned();
+        while [a, b]
+            .iter()
+            .any(|k| properties(k).is_some_and(|map| map.contains_key(&name)))
+        {
+            name.push('_');
+        }
+        result.map_err(|failure| self.wrap(failure, a, &name))
+    }
+
+    /// Put the value of `failure` in an object at `name`, with the properties `a` requires set
+    /// to values their schemas accept.
+    fn wrap(&self, mut failure: Failure, a: &'s Keywords, name: &str) -> Failure {
+        failure.instance = failure.instance.take().map(|value| {
+            let mut object = Map::new();
+            for required in a.required.iter().flat_map(|k| &k.0) {
+                let schema = a
+                    .props
+                    .as_ref()
+                    .and_then(|k| k.map.get(required))
+                    .and_then(Self::side);
+                object.insert(required.clone(), self.filler(schema));
+            }
+            object.insert(name.to_owned(), value);
+            Value::Object(object)
+        });
+        failure
+    }
+
+    /// A value `side` of A accepts, among a few simple ones.
+    fn filler(&self, side: Option<Side>) -> Value {
+        let side = side.unwrap_or(Side::True);
+        scalars()
+            .into_iter()
+            .find(|value| self.valid(self.a, side, value))
+            .unwrap_or(Value::Null)
+    }
+
+    fn valid(&self, schema: &CompiledSchema, side: Side, value: &Value) -> bool {
+        match side {
+            Side::True => true,
+            Side::Url(url) => schema
+                .schema(url.clone())
+                .is_some_and(|schema| schema.is_valid(value)),
+        }
+    }
+
+    /// A value valid under `a` and invalid under `b`, among values built from the bounds and
+    /// properties of both.
+    fn witness(&self, a: Side<'s>, b: Side<'s>) -> Option<Value> {
+        let keywords = |schema, side| match Self::view(schema, side) {
+            View::Keywords(keywords) => Some(keywords),
+            View::Bool(_) => None,
+        };
+        let (a_keywords, b_keywords) = (keywords(self.a, a), keywords(self.b, b));
+        let mut candidates = scalars();
+        for keywords in a_keywords.into_iter().chain(b_keywords) {
+            for bound in [
+                literal(keywords.min.as_ref().map(|k| &k.0)),
+                literal(keywords.max.as_ref().map(|k| &k.0)),
+                literal(keywords.excl_min.as_ref().map(|k| &k.0)),
+                literal(keywords.excl_max.as_ref().map(|k| &k.0)),
+                literal(keywords.multiple.as_ref().map(|k| &k.0)),
+            ]
+            .into_iter()
+            .flatten()
+            {
+                candidates.extend(around(bound));
+            }
+            for name in ["minLength", "maxLength", "minItems", "maxItems"] {
+                if let Some(limit) = limit(keywords, name) {
+                    for len in [limit.saturating_sub(1), limit, limit + 1] {
+                        let len = len.min(64) as usize;
+                        candidates.push(Value::String("a".repeat(len)));
+                        candidates.push(Value::Array(vec![Value::Null; len]));
+                    }
+                }
+            }
+        }
+        let scalars = scalars();
+        // objects with the required properties of A set to a value their schema accepts
+        let mut base = Map::new();
+        if let Some(a_keywords) = a_keywords {
+            for name in a_keywords.required.iter().flat_map(|k| &k.0) {
+                let schema = a_keywords
+                    .props
+                    .as_ref()
+                    .and_then(|k| k.map.get(name))
+                    .and_then(Self::side)
+                    .unwrap_or(Side::True);
+                let value = scalars.iter().find(|v| self.valid(self.a, schema, v));
+                base.insert(name.clone(), value.cloned().unwrap_or(Value::Null));
+            }
+        }
+        candidates.push(Value::Object(base.clone()));
+        let names = [a_keywords, b_keywords]
+            .into_iter()
+            .flatten()
+            .flat_map(|keywords| keywords.props.iter().flat_map(|k| k.map.keys()))
+            .map(String::as_str)
+            .chain(["additionalProperty"]);
+        for name in names {
+            for value in &scalars {
+                let mut object = base.clone();
+                object.insert(name.to_owned(), value.clone());
+                candidates.push(Value::Object(object));
+            }
+        }
+        candidates
+            .into_iter()
+            .find(|value| self.valid(self.a, a, value) && !self.valid(self.b, b, value))
+    }
+}
+
+fn literal<T>(data: Option<&Data<T>>) -> Option<&T> {
+    data.and_then(Data::value)
+}
+
+/// Whether a keyword reads its value from the instance, the check only knows literal values.
+fn reference<T>(data: Option<&Data<T>>) -> bool {
+    matches!(data, Some(Data::Reference(_)))
+}
+
+/// The values of an `enum` or `const`, `None` if the schema does not list its values.
+fn finite(keywords: &Keywords) -> Option<Vec<&Value>> {
+    if let Some(constant) = literal(keywords.constant.as_ref().map(|k| &k.0)) {
+        return Some(vec![constant]);
+    }
+    literal(keywords.enum_.as_ref().map(|k| &k.0)).map(|values| values.iter().collect())
+}
+
+/// The first keyword of `b` the check does not understand, keywords only compared to the same
+/// keyword of `a` count as understood when `a` has it as well.
+fn unsupported(b: &Keywords, a: &Keywords) -> Option<&'static str> {
+    let data = [
+        ("const", reference(b.constant.as_ref().map(|k| &k.0))),
+        ("enum", reference(b.enum_.as_ref().map(|k| &k.0))),
+        ("minimum", reference(b.min.as_ref().map(|k| &k.0))),
+        ("maximum", reference(b.max.as_ref().map(|k| &k.0))),
+        (
+            "exclusiveMinimum",
+            reference(b.excl_min.as_ref().map(|k| &k.0)),
+        ),
+        (
+            "exclusiveMaximum",
+            reference(b.excl_max.as_ref().map(|k| &k.0)),
+        ),
+        ("multipleOf", reference(b.multiple.as_ref().map(|k| &k.0))),
+        ("minLength", reference(b.min_length.as_ref().map(|k| &k.0))),
+        ("maxLength", reference(b.max_length.as_ref().map(|k| &k.0))),
+        ("minItems", reference(b.min_items.as_ref().map(|k| &k.0))),
+        ("maxItems", reference(b.max_items.as_ref().map(|k| &k.0))),
+        (
+            "minProperties",
+            reference(b.min_props.as_ref().map(|k| &k.0)),
+        ),
+        (
+            "maxProperties",
+            reference(b.max_props.as_ref().map(|k| &k.0)),
+        ),
+        ("pattern", reference(b.pat.as_ref().map(|k| &k.0))),
+    ];
+    let multiple_of = match (
+        literal(b.multiple.as_ref().map(|k| &k.0)),
+        literal(a.multiple.as_ref().map(|k| &k.0)),
+    ) {
+        (None, _) => false,
+        (Some(b), Some(a)) => !is_multiple_of(a, b),
+        (Some(_), None) => true,
+    };
+    let keywords = [
+        ("multipleOf", multiple_of),
+        ("contains", b.contains.is_some()),
+        ("minContains", b.min_contains.is_some()),
+        ("maxContains", b.max_contains.is_some()),
+        ("patternProperties", b.pat_props.is_some()),
+        ("propertyNames", b.prop_names.is_some()),
+        ("dependentRequired", b.dependent_required.is_some()),
+        ("dependentSchemas", b.dependent_schemas.is_some()),
+        ("oneOf", b.one_of.is_some()),
+        ("not", b.not.is_some()),
+        ("if", b.if_then_else.is_some()),
+        ("$dynamicRef", b.dynamic_ref.is_some()),
+        ("unevaluatedItems", b.unevaluated_items.is_some()),
+        ("unevaluatedProperties", b.unevaluated_props.is_some()),
+    ];
+    data.into_iter()
+        .chain(keywords)
+        .find(|(_, unsupported)| *unsupported)
+        .map(|(keyword, _)| keyword)
+}
+
+fn limit(keywords: &Keywords, name: &str) -> Option<u64> {
+    let data = match name {
+        "minLength" => keywords.min_length.as_ref().map(|k| &k.0),
+        "maxLength" => keywords.max_length.as_ref().map(|k| &k.0),
+        "minItems" => keywords.min_items.as_ref().map(|k| &k.0),
+        "maxItems" => keywords.max_items.as_ref().map(|k| &k.0),
+        "minProperties" => keywords.min_props.as_ref().map(|k| &k.0),
+        "maxProperties" => keywords.max_props.as_ref().map(|k| &k.0),
+        _ => None,
+    };
+    literal(data)?.as_u64()
+}
+
+/// The tightest lower or upper bound of numbers and whether it is exclusive.
+fn bound<'k>(
+    inclusive: Option<&'k Number>,
+    exclusive: Option<&'k Number>,
+    lower: bool,
+) -> Option<(&'k Number, bool)> {
+    match (inclusive, exclusive) {
+        (Some(inclusive), Some(exclusive)) => {
+            let tighter = match compare_numbers(exclusive, inclusive) {
+                Some(Ordering::Greater) => lower,
+                Some(Ordering::Less) => !lower,
+                _ => true,
+            };
+            Some(if tighter {
+                (exclusive, true)
+            } else {
+                (inclusive, false)
+            })
+        }
+        (Some(inclusive), None) => Some((inclusive, false)),
+        (None, Some(exclusive)) => Some((exclusive, true)),
+        (None, None) => None,
+    }
+}
+
+fn compare_numbers_of(a: &Keywords, b: &Keywords, path: &str) -> Result<(), Failure> {
+    fn lower(k: &Keywords) -> Option<(&Number, bool)> {
+        let inclusive = literal(k.min.as_ref().map(|k| &k.0));
+        bound(inclusive, literal(k.excl_min.as_ref().map(|k| &k.0)), true)
+    }
+    fn upper(k: &Keywords) -> Option<(&Number, bool)> {
+        let inclusive = literal(k.max.as_ref().map(|k| &k.0));
+        bound(inclusive, literal(k.excl_max.as_ref().map(|k| &k.0)), false)
+    }
+    for (a, b, outside, name) in [
+        (lower(a), lower(b), Ordering::Less, "lower"),
+        (upper(a), upper(b), Ordering::Greater, "upper"),
+    ] {
+        let Some((b_limit, b_exclusive)) = b else {
+            continue;
+        };
+        let contained = match a {
+            None => false,
+            Some((a_limit, a_exclusive)) => match compare_numbers(a_limit, b_limit) {
+                Some(Ordering::Equal) => a_exclusive || !b_exclusive,
+                Some(ordering) => ordering != outside,
+                None => false,
+            },
+        };
+        if !contained {
+            return Checker::fail(path, format!("the {name} bound of B excludes numbers of A"));
+        }
+    }
+    Ok(())
+}
+
+fn type_names(mask: u8) -> String {
+    let names = [
+        (NULL, "null"),
+        (BOOLEAN, "boolean"),
+        (INTEGER, "integer"),
+        (NUMBER, "number"),
+        (STRING, "string"),
+        (ARRAY, "array"),
+        (OBJECT, "object"),
+    ];
+    let names: Vec<_> = names
+        .into_iter()
+        .filter(|(bit, _)| mask & bit != 0)
+        .map(|(_, name)| name)
+        .collect();
+    names.join(", ")
+}
+
+fn scalars() -> Vec<Value> {
+    vec![
+        Value::Null,
+        json!(false),
+        json!(true),
+        json!(0),
+        json!(1),
+        json!(-1),
+        json!(0.5),
+        json!(""),
+        json!("a"),
+        json!([]),
+        json!({}),
+    ]
+}
+
+/// Numbers at and next to a bound.
+fn around(number: &Number) -> Vec<Value> {
+    let mut values = vec![Value::Number(number.clone())];
+    if let Some(int) = number.as_i64() {
+        values.extend([json!(int.saturating_sub(1)), json!(int.saturating_add(1))]);
+    }
+    if let Some(float) = number.as_f64() {
+        for delta in [-0.5, 0.5] {
+            values.extend(Number::from_f64(float + delta).map(Value::Number));
+        }
+    }
+    values
+}
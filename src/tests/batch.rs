@@ -0,0 +1,54 @@
+use super::compile;
+use crate::batch::{validate_lines, BatchOptions};
+use serde_json::json;
+use std::io::{self, Cursor};
+
+#[test]
+fn test_validate_lines() {
+    let schema = compile(json!({ "type": "object", "required": ["id"] }));
+    let mut input = String::new();
+    for i in 1..=1000 {
+        match i % 100 {
+            0 => input.push('\n'),
+            50 => input.push_str("{ not json\n"),
+            7 => input.push_str("{}\n"),
+            _ => input.push_str(&format!("{{\"id\": {i}}}\n")),
+        }
+    }
+    let options = BatchOptions {
+        workers: 4,
+        chunk_size: 3,
+    };
+    let mut results = vec![];
+    validate_lines(&schema, Cursor::new(&input), &options, |result| {
+        results.push(result);
+        Ok(())
+    })
+    .unwrap();
+
+    // blank lines are skipped but still counted
+    assert_eq!(results.len(), 990);
+    let lines: Vec<_> = results.iter().map(|result| result.line).collect();
+    let expected: Vec<_> = (1..=1000).filter(|i| i % 100 != 0).collect();
+    assert_eq!(lines, expected);
+    for result in &results {
+        match result.line % 100 {
+            50 => assert!(!result.valid && result.parse_error.is_some()),
+            7 => assert_eq!(result.errors[0].keyword_location, "/required"),
+            _ => assert!(result.valid && result.errors.is_empty()),
+        }
+    }
+
+    // an error from the sink stops the batch
+    let mut seen = 0;
+    let error = validate_lines(&schema, Cursor::new(&input), &options, |_| {
+        seen += 1;
+        match seen {
+            10 => Err(io::Error::other("closed")),
+            _ => Ok(()),
+        }
+    })
+    .unwrap_err();
+    assert_eq!(error.to_string(), "closed");
+    assert_eq!(seen, 10);
+}
@@ -0,0 +1,76 @@
+use super::compile_context;
+use crate::{
+    canonical::{CanonicalOptions, Canonicalizer},
+    context::Context,
+};
+use serde_json::json;
+use url::Url;
+
+#[test]
+fn test_equivalent_schemas_have_the_same_fingerprint() {
+    // the schemas share one context like the documents of a project
+    let mut context = Context::new();
+    let mut urls = vec![];
+    let schemas = [
+        json!({
+            "$id": "https://example.com/a.json",
+            "type": "object",
+            "title": "Node",
+            "required": ["value", "children"],
+            "properties": {
+                "value": { "type": ["integer"], "minimum": 1.0 },
+                "kind": { "enum": ["leaf"] },
+                "children": { "type": "array", "items": { "$ref": "#" } },
+                "tag": { "$ref": "#/$defs/tag" }
+            },
+            "$defs": { "tag": { "type": "string", "minLength": 0 } }
+        }),
+        json!({
+            "$id": "https://example.com/b.json",
+            "type": "object",
+            "title": "Tree",
+            "required": ["children", "value"],
+            "properties": {
+                "value": { "type": "integer", "minimum": 1 },
+                "kind": { "const": "leaf" },
+                "children": { "type": "array", "items": { "$ref": "#" } },
+                "tag": { "$ref": "#/definitions/label" }
+            },
+            "definitions": { "label": { "type": ["string"] } }
+        }),
+        json!({}),
+        json!(true),
+    ];
+    for (i, schema) in schemas.into_iter().enumerate() {
+        let url = Url::parse(&format!("file:///schemas/{i}.json")).unwrap();
+        compile_context(&mut context, schema, &url);
+        urls.push(url);
+    }
+    let canonicalizer = Canonicalizer::new(&context, CanonicalOptions::default());
+    assert_eq!(
+        canonicalizer.canonicalize(&urls[1]).unwrap()["properties"]["children"]["items"],
+        json!({ "$ref": "#" })
+    );
+    // the titles differ
+    assert_ne!(
+        canonicalizer.fingerprint(&urls[0]),
+        canonicalizer.fingerprint(&urls[1])
+    );
+    assert_eq!(
+        canonicalizer.fingerprint(&urls[2]),
+        canonicalizer.fingerprint(&urls[3])
+    );
+
+    let options = CanonicalOptions {
+        strip_annotations: true,
+    };
+    let canonicalizer = Canonicalizer::new(&context, options);
+    assert_eq!(
+        canonicalizer.canonicalize(&urls[0]),
+        canonicalizer.canonicalize(&urls[1])
+    );
+    assert_eq!(
+        canonicalizer.fingerprint(&urls[0]),
+        canonicalizer.fingerprint(&urls[1])
+    );
+}
@@ -0,0 +1,343 @@
+use super::compile_named;
+use crate::codegen::rust::{generate_rust_code, generate_rust_code_with, RustOptions};
+use serde_json::{json, Value};
+use std::{fs, path::Path};
+use trybuild::TestCases;
+
+fn generate(schema: Value) -> String {
+    let mut code = vec![];
+    generate_rust_code(&mut code, &compile_named("order", schema).0).unwrap();
+    String::from_utf8(code).unwrap()
+}
+
+fn generate_validated(schema: Value) -> String {
+    let options = RustOptions { validated: true };
+    let mut code = vec![];
+    generate_rust_code_with(&mut code, &compile_named("order", schema).0, &options).unwrap();
+    String::from_utf8(code).unwrap()
+}
+
+fn identifiers() -> Value {
+    json!({
+        "type": "object",
+        "required": ["$id", "type"],
+        "properties": {
+            "$id": { "type": "string", "format": "not-a-format" },
+            "type": { "enum": ["retail", "whole-sale"] },
+            "first-name": { "type": "string" },
+            "firstName": { "type": "string" },
+            "tags": { "type": ["string", "null"] },
+            "line": { "$ref": "#/$defs/line" },
+            "rel": { "enum": ["self", "next", "Self"] }
+        },
+        "$defs": {
+            "line": {
+                "title": "Option",
+                "type": "object",
+                "properties": { "self": { "type": "integer", "format": "u8" } }
+            }
+        }
+    })
+}
+
+#[test]
+fn test_generate_rust_code_sanitizes_identifiers() {
+    let schema = identifiers();
+    let code = generate(schema.clone());
+    // every generation names and orders the types the same
+    assert_eq!(code, generate(schema));
+    assert_eq!(
+        code,
+        r#"// Generated from JSON schemas, do not edit by hand.
+
+/// file:///schemas/order.json
+#[derive(Clone, Debug, ::serde::Deserialize, ::serde::Serialize)]
+pub struct Order {
+    #[serde(rename = "$id")]
+    pub id: String,
+    #[serde(rename = "first-name")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_name: Option<String>,
+    #[serde(rename = "firstName")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_name_2: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<Line>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rel: Option<Rel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Tags>,
+    pub r#type: Type,
+}
+
+/// Option
+///
+/// file:///schemas/order.json#/$defs/line
+#[derive(Clone, Debug, ::serde::Deserialize, ::serde::Serialize)]
+pub struct Line {
+    #[serde(rename = "self")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub self_: Option<u8>,
+}
+
+/// file:///schemas/order.json#/properties/rel
+#[derive(Clone, Debug, ::serde::Deserialize, ::serde::Serialize)]
+pub enum Rel {
+    #[serde(rename = "self")]
+    Self2,
+    #[serde(rename = "next")]
+    Next,
+    #[serde(rename = "Self")]
+    Self3,
+}
+
+/// file:///schemas/order.json#/properties/tags
+#[derive(Clone, Debug, ::serde::Deserialize, ::serde::Serialize)]
+#[serde(untagged)]
+pub enum Tags {
+    Null,
+    String(String),
+}
+
+/// file:///schemas/order.json#/properties/type
+#[derive(Clone, Debug, ::serde::Deserialize, ::serde::Serialize)]
+pub enum Type {
+    #[serde(rename = "retail")]
+    Retail,
+    #[serde(rename = "whole-sale")]
+    WholeSale,
+}
+"#
+    );
+}
+
+fn recursive() -> Value {
+    json!({
+        "type": "object",
+        "title": "Node",
+        "properties": {
+            "next": { "$ref": "#" },
+            "children": { "type": "array", "items": { "$ref": "#" } },
+            "labels": {
+                "type": "object",
+                "patternProperties": { "^x-": { "type": "string" } },
+                "additionalProperties": false
+            },
+            "leaf": {
+                "type": "object",
+                "properties": { "value": { "type": "integer" } },
+                "additionalProperties": false
+            }
+        },
+        "additionalProperties": { "type": "integer" }
+    })
+}
+
+#[test]
+fn test_generate_rust_code_boxes_recursive_types() {
+    let code = generate(recursive());
+    for expected in [
+        "    pub next: Option<Box<Node>>,\n",
+        "    pub children: Option<Vec<Node>>,\n",
+        "    // properties matching \"^x-\"\n    #[serde(flatten)]\n    pub extra: ::std::collections::BTreeMap<String, String>,\n",
+        "    #[serde(flatten)]\n    pub extra: ::std::collections::BTreeMap<String, i32>,\n}\n",
+        "#[serde(deny_unknown_fields)]\npub struct Leaf {\n",
+    ] {
+        assert!(code.contains(expected), "{expected} not in {code}");
+    }
+    assert_eq!(code.matches("deny_unknown_fields").count(), 1);
+}
+
+fn tagged() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "shape": { "oneOf": [{ "$ref": "#/$defs/circle" }, { "$ref": "#/$defs/square" }] },
+            "pet": {
+                "oneOf": [{ "$ref": "#/$defs/cat" }, { "$ref": "#/$defs/dog" }],
+                "discriminator": {
+                    "propertyName": "petType",
+                    "mapping": { "Doggo": "#/$defs/dog" }
+                }
+            },
+            "priority": { "enum": [1, -2, 1] },
+            "mixed": { "enum": [1, "a"] }
+        },
+        "$defs": {
+            "circle": {
+                "type": "object",
+                "required": ["kind", "radius"],
+                "properties": { "kind": { "const": "circle" }, "radius": { "type": "number" } }
+            },
+            "square": {
+                "type": "object",
+                "required": ["kind"],
+                "properties": { "kind": { "enum": ["square"] } }
+            },
+            "cat": { "type": "object", "properties": { "petType": { "type": "string" } } },
+            "dog": { "type": "object" }
+        }
+    })
+}
+
+#[test]
+fn test_generate_rust_code_tags_enums() {
+    let code = generate(tagged());
+    for expected in [
+        "#[serde(tag = \"kind\")]\npub enum Shape {\n    #[serde(rename = \"circle\")]\n    Circle(Circle),\n    #[serde(rename = \"square\")]\n    Square(Square),\n}\n",
+        "#[serde(tag = \"petType\")]\npub enum Pet {\n    #[serde(rename = \"cat\")]\n    Cat(Cat),\n    Doggo(Dog),\n}\n",
+        "pub struct Circle {\n    pub radius: f32,\n}\n",
+        "#[repr(i64)]\npub enum Priority {\n    Value1 = 1,\n    ValueMinus2 = -2,\n}\n",
+        "// only enums of strings or of integers are supported\npub type Mixed = ::serde_json::Value;\n",
+    ] {
+        assert!(code.contains(expected), "{expected} not in {code}");
+    }
+    // the tag is left to the enum
+    assert!(!code.contains("pub pet_type"));
+}
+
+fn shared() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "shape": { "oneOf": [{ "$ref": "#/$defs/circle" }, { "$ref": "#/$defs/square" }] },
+            "pet": { "oneOf": [{ "$ref": "#/$defs/cat" }, { "$ref": "#/$defs/dog" }] },
+            "favorite": { "$ref": "#/$defs/cat" }
+        },
+        "$defs": {
+            "circle": { "required": ["kind"], "properties": { "kind": { "const": "circle" } } },
+            "square": { "required": ["kind"], "properties": { "kind": { "const": "square" } } },
+            "cat": { "required": ["kind"], "properties": { "kind": { "const": "cat" } } },
+            "dog": { "required": ["kind"], "properties": { "kind": { "const": "dog" } } }
+        }
+    })
+}
+
+#[test]
+fn test_generate_rust_code_keeps_tags_of_shared_structs() {
+    let code = generate(shared());
+    for expected in [
+        "#[serde(tag = \"kind\")]\npub enum Shape {\n",
+        "pub struct Circle {}\n",
+        // `Cat` is also used on its own, so it keeps its tag and `Pet` is untagged
+        "pub struct Cat {\n    pub kind: Kind,\n}\n",
+        "#[serde(untagged)]\npub enum Pet {\n",
+    ] {
+        assert!(code.contains(expected), "{expected} not in {code}");
+    }
+}
+
+fn validated() -> Value {
+    json!({
+        "type": "object",
+        "required": ["name", "lines"],
+        "properties": {
+            "name": { "type": "string", "minLength": 1, "pattern": "^[a-z]+$" },
+            "quantity": { "type": "integer", "format": "u8", "minimum": 0, "multipleOf": 5 },
+            "batch": { "type": "integer", "multipleOf": 0 },
+            "note": { "type": ["string", "null"], "maxLength": 200 },
+            "lines": { "type": "array", "minItems": 1, "items": { "$ref": "#/$defs/line" } }
+        },
+        "$defs": { "line": { "type": "object" } }
+    })
+}
+
+#[test]
+fn test_generate_rust_code_validated_newtypes() {
+    let schema = validated();
+    // without the option constraints are not represented
+    assert!(generate(schema.clone()).contains("    pub name: String,\n"));
+
+    let code = generate_validated(schema);
+    for expected in [
+        "pub struct ValidationError {",
+        "    pub name: Name,\n",
+        "#[serde(transparent)]\npub struct Name(String);\n",
+        "impl ::std::convert::TryFrom<String> for Name {",
+        "        if value.is_empty() {\n",
+        "        const PATTERN: &str = \"^[a-z]+$\";\n",
+        "impl<'de> ::serde::Deserialize<'de> for Name {",
+        // every u8 is at least 0
+        "    fn try_from(value: u8) -> Result<Self, ValidationError> {\n        if value % 5 != 0 {\n",
+        "        if self.lines.is_empty() {\n",
+        "        // multipleOf 0 is not positive, no value is checked against it\n        Ok(Self(value))\n",
+        // deserializing checks the struct too
+        "#[serde(try_from = \"OrderUnchecked\")]\npub struct Order {\n",
+        "#[derive(::serde::Deserialize)]\nstruct OrderUnchecked {\n",
+        "        value.validate()?;\n        Ok(value)\n",
+        "        for item in self.lines.iter() {\n            item.validate()?;\n        }\n",
+        "impl Line {\n",
+        // a constrained type among several is not a newtype
+        "pub enum Note {\n    Null,\n    // the constraints of string values are not checked\n    String(String),\n",
+    ] {
+        assert!(code.contains(expected), "{expected} not in {code}");
+    }
+}
+
+fn annotated() -> Value {
+    json!({
+        "title": "Order",
+        "description": "An order.\n\n```\nlet x = 1;\n```",
+        "type": "object",
+        "required": ["id", "password"],
+        "properties": {
+            "id": { "type": "integer", "readOnly": true },
+            "password": { "type": "string", "writeOnly": true },
+            "count": { "type": "integer", "default": 5, "examples": [3] },
+            "status": { "$ref": "#/$defs/status" },
+            "legacy": { "type": "string", "deprecated": true }
+        },
+        "$defs": {
+            "status": { "enum": ["open", "closed"], "default": "closed", "deprecated": true },
+            "line": { "type": "object", "properties": { "sku": { "type": "string", "default": "" } } }
+        }
+    })
+}
+
+#[test]
+fn test_generate_rust_code_documents_annotations() {
+    let code = generate(annotated());
+    for expected in [
+        "/// Order\n///\n/// An order.\n///\n/// ```text\n/// let x = 1;\n/// ```\n///\n/// file:///schemas/order.json\n#[allow(deprecated)]\n",
+        "    #[serde(skip_deserializing)]\n    #[serde(skip_serializing_if = \"Option::is_none\")]\n    pub id: Option<i32>,\n",
+        "    #[serde(skip_serializing)]\n    pub password: String,\n",
+        "    /// # Examples\n    ///\n    /// ```json\n    /// 3\n    /// ```\n    #[serde(default = \"Order::default_count\")]\n    pub count: i32,\n",
+        "    fn default_count() -> i32 {\n        5\n    }\n",
+        "    #[deprecated]\n    #[serde(skip_serializing_if = \"Option::is_none\")]\n    pub legacy: Option<String>,\n",
+        "#[deprecated]\n#[allow(deprecated)]\n#[derive(Clone, Debug, Default, ::serde::Deserialize, ::serde::Serialize)]\npub enum Status {",
+        "    #[default]\n    #[serde(rename = \"closed\")]\n    Closed,\n",
+        "impl Default for Line {\n    fn default() -> Self {\n        Self {\n            sku: Self::default_sku(),\n        }\n    }\n}\n",
+    ] {
+        assert!(code.contains(expected), "{expected} not in {code}");
+    }
+    // the required fields have no default
+    assert!(!code.contains("impl Default for Order"));
+}
+
+#[test]
+fn test_generated_rust_code_compiles() {
+    // every fixture is built as a binary with serde, serde_json, serde_repr and regex available
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/codegen");
+    fs::create_dir_all(&dir).unwrap();
+    let cases = TestCases::new();
+    // a struct with checks can't be deserialized from a value that breaks them
+    let checked = r##"
+    let order = r#"{ "name": "a", "lines": [{}] }"#;
+    ::serde_json::from_str::<Order>(order).unwrap();
+    assert!(::serde_json::from_str::<Order>(r#"{ "name": "a", "lines": [] }"#).is_err());
+"##;
+    for (name, code, main) in [
+        ("identifiers", generate(identifiers()), ""),
+        ("recursive", generate(recursive()), ""),
+        ("tagged", generate(tagged()), ""),
+        ("shared", generate(shared()), ""),
+        ("validated", generate(validated()), ""),
+        ("validated_checks", generate_validated(validated()), checked),
+        ("annotated", generate(annotated()), ""),
+    ] {
+        let path = dir.join(format!("{name}.rs"));
+        let code = format!("#![allow(dead_code, deprecated)]\n\n{code}\nfn main() {{{main}}}\n");
+        fs::write(&path, code).unwrap();
+        cases.pass(path);
+    }
+}
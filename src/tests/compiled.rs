@@ -0,0 +1,177 @@
+use super::compile;
+use crate::compiled::CompiledSchema;
+use serde_json::json;
+use std::{sync::Arc, thread};
+
+fn person() -> CompiledSchema {
+    compile(json!({
+        "type": "object",
+        "required": ["name"],
+        "properties": {
+            "name": { "type": "string", "minLength": 1 },
+            "age": { "type": "integer", "minimum": 0 },
+            "friends": { "type": "array", "items": { "$ref": "#" } }
+        },
+        "unevaluatedProperties": false
+    }))
+}
+
+#[test]
+fn test_compiled_schema_is_shareable() {
+    fn assert_shareable<T: Send + Sync + Clone + 'static>() {}
+    assert_shareable::<CompiledSchema>();
+
+    let schema = Arc::new(person());
+    let handles: Vec<_> = (0..4)
+        .map(|i| {
+            let schema = schema.clone();
+            thread::spawn(move || schema.validate(&json!({ "name": "a", "age": i })).is_ok())
+        })
+        .collect();
+    for handle in handles {
+        assert!(handle.join().unwrap());
+    }
+}
+
+#[test]
+fn test_compiled_schema_errors() {
+    let schema = person();
+    assert!(schema
+        .validate(&json!({ "name": "a", "friends": [{ "name": "b" }] }))
+        .is_ok());
+
+    let errors = schema
+        .validate(&json!({ "age": 1.5, "friends": [{ "name": "" }], "extra": true }))
+        .unwrap_err();
+    let mut locations: Vec<_> = errors
+        .iter()
+        .map(|error| {
+            (
+                error.instance_location.as_str(),
+                error.keyword_location.as_str(),
+            )
+        })
+        .collect();
+    locations.sort();
+    assert_eq!(
+        locations,
+        vec![
+            ("", "/required"),
+            ("/age", "/properties/age/type"),
+            ("/extra", "/unevaluatedProperties"),
+            (
+                "/friends/0/name",
+                "/properties/friends/items/properties/name/minLength"
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_compiled_schema_is_valid() {
+    let schema = compile(json!({
+        "anyOf": [{ "type": "string" }, { "type": "integer" }],
+        "oneOf": [{ "minimum": 10 }, { "maximum": 20 }, { "type": "string" }],
+        "prefixItems": [true],
+        "contains": { "const": 1 },
+        "maxContains": 1,
+        "unevaluatedItems": false
+    }));
+    for (instance, valid) in [
+        (json!(5), true),
+        (json!(15), false),
+        (json!(null), false),
+        (json!("a"), false),
+        (json!([1]), false),
+        (json!([0, 1]), false),
+    ] {
+        assert_eq!(schema.is_valid(&instance), valid, "{instance}");
+        assert_eq!(schema.validate(&instance).is_ok(), valid, "{instance}");
+    }
+
+    let schema = compile(json!({
+        "prefixItems": [true],
+        "contains": { "const": 1 },
+        "maxContains": 1,
+        "unevaluatedItems": false
+    }));
+    for (instance, valid) in [
+        (json!([0, 1]), true),
+        (json!([0, 1, 1]), false),
+        (json!([0, 1, 2]), false),
+        (json!([1]), true),
+    ] {
+        assert_eq!(schema.is_valid(&instance), valid, "{instance}");
+        assert_eq!(schema.validate(&instance).is_ok(), valid, "{instance}");
+    }
+
+    // `i64::MIN % -1` overflows
+    compile(json!({ "multipleOf": -1 })).is_valid(&json!(i64::MIN));
+}
+
+#[test]
+fn test_compiled_schema_nested_unevaluated() {
+    let schema = compile(json!({
+        "$defs": { "all": { "unevaluatedItems": true } },
+        "properties": { "foo": {} },
+        "allOf": [{ "unevaluatedProperties": true }, { "$ref": "#/$defs/all" }],
+        "unevaluatedProperties": false,
+        "unevaluatedItems": false
+    }));
+    // the nested schemas evaluate every property and item
+    for (instance, valid) in [(json!({ "foo": 1, "bar": 2 }), true), (json!([1, 2]), true)] {
+        assert_eq!(schema.is_valid(&instance), valid, "{instance}");
+        assert_eq!(schema.validate(&instance).is_ok(), valid, "{instance}");
+    }
+
+    let schema = compile(json!({
+        "$defs": { "bar": { "properties": { "bar": true }, "unevaluatedProperties": false } },
+        "allOf": [{ "$ref": "#/$defs/bar" }],
+        "properties": { "foo": true },
+        "unevaluatedProperties": false
+    }));
+    for (instance, valid) in [
+        (json!({ "bar": 1 }), true),
+        // `foo` is not evaluated by the nested schema
+        (json!({ "foo": 1, "bar": 2 }), false),
+    ] {
+        assert_eq!(schema.is_valid(&instance), valid, "{instance}");
+        assert_eq!(schema.validate(&instance).is_ok(), valid, "{instance}");
+    }
+}
+
+#[test]
+fn test_compiled_schema_dependencies() {
+    let schema = compile(json!({
+        "dependentRequired": { "card": ["billing", "cvc"] },
+        "dependentSchemas": {
+            "billing": { "properties": { "billing": { "type": "string" } } }
+        },
+        "properties": { "card": true, "cvc": true },
+        "unevaluatedProperties": false
+    }));
+    assert!(schema.is_valid(&json!({})));
+    assert!(schema.is_valid(&json!({ "card": 1, "billing": "x", "cvc": 2 })));
+    let errors: Vec<_> = schema
+        .validate(&json!({ "card": 1, "billing": 2 }))
+        .unwrap_err()
+        .into_iter()
+        .map(|error| (error.keyword_location, error.message))
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            (
+                "/dependentRequired".to_owned(),
+                "missing property cvc required by card".to_owned()
+            ),
+            (
+                "/dependentSchemas/billing/properties/billing/type".to_owned(),
+                "integer is not of type string".to_owned()
+            ),
+        ]
+    );
+    // the dependent schema evaluates `billing`
+    assert!(schema.is_valid(&json!({ "billing": "x" })));
+    assert!(!schema.is_valid(&json!({ "billing": "x", "other": 1 })));
+}
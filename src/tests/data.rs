@@ -0,0 +1,111 @@
+use super::{compile, compile_with};
+use crate::{context::Context, value::ValueArena};
+use serde_json::json;
+
+/// A context that resolves `$data` references.
+pub fn data_context() -> Context {
+    let mut context = Context::new();
+    context.data_references = true;
+    context
+}
+
+#[test]
+fn test_data_references() {
+    let form = json!({
+        "properties": {
+            "startDate": { "type": "integer" },
+            "endDate": { "minimum": { "$data": "/startDate" } },
+            "password": { "type": "string" },
+            "confirmPassword": { "const": { "$data": "1/password" } },
+            "tags": {
+                "items": { "pattern": { "$data": "2/tagPattern" }, "minLength": { "$data": "0#" } }
+            }
+        }
+    });
+    let schema = compile_with(data_context(), form.clone());
+    for (document, locations) in [
+        (
+            json!({ "startDate": 5, "endDate": 7, "password": "a", "confirmPassword": "a" }),
+            vec![],
+        ),
+        (
+            json!({ "startDate": 5, "endDate": 3, "password": "a", "confirmPassword": "b" }),
+            vec!["/confirmPassword", "/endDate"],
+        ),
+        // referenced values that are not present do not apply
+        (json!({ "endDate": 3, "tags": ["x"] }), vec![]),
+        (
+            json!({ "tagPattern": "^a", "tags": ["a", "ab", "b"] }),
+            vec!["/tags/2", "/tags/2"],
+        ),
+        (
+            json!({ "startDate": "5", "endDate": 3 }),
+            vec!["/endDate", "/startDate"],
+        ),
+    ] {
+        let errors = schema.validate(&document).err().unwrap_or_default();
+        let mut found: Vec<_> = errors
+            .iter()
+            .map(|e| e.instance_location.as_str())
+            .collect();
+        found.sort();
+        assert_eq!(found, locations, "{document}");
+        let arena = ValueArena::from(document.clone());
+        assert_eq!(
+            schema.validate(arena.document()),
+            schema.validate(&document)
+        );
+        let streamed = schema
+            .validate_deserializer(&document)
+            .map_err(|e| e.to_string());
+        assert_eq!(streamed.is_ok(), locations.is_empty());
+    }
+
+    // without the opt-in a reference is an ordinary const value and other keywords fail
+    let schema = compile(form);
+    let reference = json!({ "confirmPassword": { "$data": "1/password" } });
+    assert!(schema.is_valid(&reference));
+    let errors = schema.validate(&json!({ "endDate": 3 })).unwrap_err();
+    assert_eq!(errors[0].keyword_location, "/properties/endDate/minimum");
+}
+
+#[test]
+fn test_single_value_enum_is_not_a_reference() {
+    let schema = compile_with(data_context(), json!({ "enum": ["a"] }));
+    assert!(schema.is_valid(&json!("a")));
+    assert!(!schema.is_valid(&json!({ "$data": "a" })));
+}
+
+#[test]
+fn test_arena_data_references_reach_parents() {
+    let schema = compile_with(data_context(), json!({ "minimum": { "$data": "2/min" } }));
+    let arena = ValueArena::from(json!({ "min": 5, "values": [7, 3] }));
+    // a node of the arena knows the values around it
+    assert!(schema.is_valid(arena.pointer("/values/0").unwrap()));
+    assert!(!schema.is_valid(arena.pointer("/values/1").unwrap()));
+    // a plain value is its own document, there is nothing above it
+    let errors = schema.validate(&json!(7)).unwrap_err();
+    assert!(
+        errors[0].message.contains("past the document"),
+        "{}",
+        errors[0].message
+    );
+}
+
+#[test]
+fn test_data_multiple_of_must_be_positive() {
+    let schema = compile_with(
+        data_context(),
+        json!({ "properties": { "n": { "multipleOf": { "$data": "1/by" } } } }),
+    );
+    assert!(schema.is_valid(&json!({ "n": 6, "by": 3 })));
+    for by in [json!(0), json!(-1), json!(-0.5)] {
+        let errors = schema
+            .validate(&json!({ "n": i64::MIN, "by": by }))
+            .unwrap_err();
+        assert_eq!(
+            errors[0].message,
+            format!("{by} is not a valid value of multipleOf")
+        );
+    }
+}
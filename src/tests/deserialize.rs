@@ -0,0 +1,73 @@
+use super::compile;
+use crate::deserialize::{from_str_validated, DeserializeError};
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Config {
+    name: String,
+    port: u16,
+    verbose: bool,
+    tags: Vec<String>,
+    limits: Limits,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Limits {
+    requests: u32,
+}
+
+#[test]
+fn test_from_str_validated() {
+    let schema = compile(json!({
+        "type": "object",
+        "required": ["name"],
+        "properties": {
+            "name": { "type": "string", "minLength": 1 },
+            "port": { "type": "integer", "maximum": 65535, "default": 8080 },
+            "verbose": { "type": "boolean", "default": false },
+            "tags": { "type": "array", "items": { "type": "string" }, "default": [] },
+            "limits": {
+                "type": "object",
+                "properties": { "requests": { "type": "integer", "default": 100 } },
+                "default": {}
+            }
+        }
+    }));
+
+    let config: Config =
+        from_str_validated(&schema, r#"{ "name": "api", "port": "9000", "tags": [1] }"#).unwrap();
+    assert_eq!(
+        config,
+        Config {
+            name: "api".to_owned(),
+            port: 9000,
+            verbose: false,
+            tags: vec!["1".to_owned()],
+            limits: Limits { requests: 100 },
+        }
+    );
+
+    match from_str_validated::<Config>(&schema, r#"{ "name": "", "port": "http" }"#) {
+        Err(error @ DeserializeError::Invalid(_)) => {
+            let mut locations = error.instance_locations();
+            locations.sort();
+            assert_eq!(locations, vec!["/name", "/port"]);
+        }
+        result => panic!("{result:?}"),
+    }
+
+    // valid against the schema but out of range for the type
+    match from_str_validated::<Config>(&schema, r#"{ "name": "a", "limits": { "requests": -1 } }"#)
+    {
+        Err(error @ DeserializeError::Type { .. }) => {
+            assert_eq!(error.instance_locations(), vec!["/limits/requests"]);
+        }
+        result => panic!("{result:?}"),
+    }
+
+    assert!(matches!(
+        from_str_validated::<Config>(&schema, "{"),
+        Err(DeserializeError::Syntax(_))
+    ));
+}
@@ -0,0 +1,205 @@
+use super::compile_named;
+use crate::diff::{diff, Change, ChangeKind, Compatibility, DiffReport};
+use serde_json::json;
+
+fn compare(old: serde_json::Value, new: serde_json::Value) -> DiffReport {
+    let (old, url) = compile_named("event", old);
+    let (new, _) = compile_named("event", new);
+    diff(&old, &url, &new, &url)
+}
+
+fn change(location: &str, kind: ChangeKind, compatibility: Compatibility) -> Change {
+    Change {
+        location: location.to_owned(),
+        kind,
+        compatibility,
+        old: None,
+        new: None,
+    }
+}
+
+#[test]
+fn test_diff_breaking_changes() {
+    let report = compare(
+        json!({
+            "type": "object",
+            "properties": {
+                "id": { "type": "string" },
+                "kind": { "enum": ["created", "deleted"] },
+                "note": { "type": "string", "maxLength": 100 },
+                "legacy": { "type": "string" }
+            },
+            "additionalProperties": false
+        }),
+        json!({
+            "type": "object",
+            "required": ["id"],
+            "properties": {
+                "id": { "type": "integer" },
+                "kind": { "enum": ["created"] },
+                "note": { "type": "string", "maxLength": 50 }
+            },
+            "additionalProperties": false
+        }),
+    );
+    let changes: Vec<_> = report
+        .changes
+        .iter()
+        .map(|change| Change {
+            old: None,
+            new: None,
+            ..change.clone()
+        })
+        .collect();
+    use ChangeKind::*;
+    use Compatibility::*;
+    assert_eq!(
+        changes,
+        vec![
+            change("/required", Added, BreaksProducers),
+            change("/properties/id/type", Added, BreaksConsumers),
+            change("/properties/id/type", Removed, BreaksProducers),
+            change("/properties/kind/enum", Removed, BreaksProducers),
+            change("/properties/legacy", Removed, BreaksProducers),
+            change("/properties/note/maxLength", Tightened, BreaksProducers),
+        ]
+    );
+    assert_eq!(report.changes[3].old, Some(json!("deleted")));
+    assert!(report.breaks_producers() && report.breaks_consumers());
+
+    let serialized = serde_json::to_value(&report.changes[5]).unwrap();
+    assert_eq!(
+        serialized,
+        json!({
+            "location": "/properties/note/maxLength",
+            "kind": "tightened",
+            "compatibility": "breaksProducers",
+            "old": 100,
+            "new": 50
+        })
+    );
+}
+
+#[test]
+fn test_diff_compatible_changes() {
+    let old = json!({
+        "type": "object",
+        "properties": {
+            "name": { "type": "string" },
+            "next": { "$ref": "#" }
+        }
+    });
+    let mut new = old.clone();
+    new["properties"]["email"] = json!({ "description": "where to reach the sender" });
+    let report = compare(old.clone(), new.clone());
+    assert!(report.is_compatible());
+    assert_eq!(
+        report.changes,
+        vec![Change {
+            new: Some(json!("email")),
+            ..change(
+                "/properties/email",
+                ChangeKind::Added,
+                Compatibility::Compatible
+            )
+        }]
+    );
+
+    // the new property rejects values the additional properties accepted, e.g. `"email": 5`
+    new["properties"]["email"] = json!({ "type": "string" });
+    let report = compare(old.clone(), new.clone());
+    assert!(report.breaks_producers() && !report.breaks_consumers());
+    assert!(report.changes.contains(&Change {
+        old: Some(json!("integer")),
+        ..change(
+            "/properties/email/type",
+            ChangeKind::Removed,
+            Compatibility::BreaksProducers
+        )
+    }));
+    // and removing it allows them again
+    let report = compare(new, old.clone());
+    assert!(report.breaks_consumers() && !report.breaks_producers());
+    assert!(compare(old.clone(), old).changes.is_empty());
+}
+
+#[test]
+fn test_diff_dependencies() {
+    let report = compare(
+        json!({
+            "dependentRequired": { "card": ["cvc"] },
+            "dependentSchemas": { "billing": { "required": ["address"] } }
+        }),
+        json!({ "dependentRequired": { "card": ["cvc", "expiry"] } }),
+    );
+    assert_eq!(
+        report.changes,
+        vec![
+            Change {
+                new: Some(json!("expiry")),
+                ..change(
+                    "/dependentRequired/card",
+                    ChangeKind::Added,
+                    Compatibility::BreaksProducers
+                )
+            },
+            Change {
+                old: Some(json!("address")),
+                ..change(
+                    "/dependentSchemas/billing/required",
+                    ChangeKind::Removed,
+                    Compatibility::BreaksConsumers
+                )
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_diff_one_of_and_if() {
+    // 50 matched one branch before and matches both now
+    let report = compare(
+        json!({ "oneOf": [{ "type": "integer" }, { "type": "number", "maximum": 10 }] }),
+        json!({ "oneOf": [{ "type": "integer" }, { "type": "number", "maximum": 100 }] }),
+    );
+    assert_eq!(
+        report.changes,
+        vec![Change {
+            old: Some(json!(10)),
+            new: Some(json!(100)),
+            ..change(
+                "/oneOf/1/maximum",
+                ChangeKind::Loosened,
+                Compatibility::Breaking
+            )
+        }]
+    );
+
+    let report = compare(
+        json!({ "oneOf": [{ "type": "integer" }] }),
+        json!({ "oneOf": [{ "type": "integer" }, { "minimum": 0 }] }),
+    );
+    assert_eq!(
+        report.changes,
+        vec![change(
+            "/oneOf/1",
+            ChangeKind::Added,
+            Compatibility::Breaking
+        )]
+    );
+
+    let report = compare(
+        json!({ "if": { "const": "a" }, "then": { "required": ["x"] } }),
+        json!({ "if": { "const": "b" }, "then": { "required": ["x"] } }),
+    );
+    assert_eq!(
+        report.changes,
+        vec![change("/if", ChangeKind::Changed, Compatibility::Breaking)]
+    );
+    // annotations do not change which branch applies
+    let report = compare(
+        json!({ "if": { "const": "a" }, "then": { "required": ["x"] } }),
+        json!({ "if": { "const": "a", "title": "A" }, "then": { "required": ["x"] } }),
+    );
+    assert!(report.changes.is_empty());
+}
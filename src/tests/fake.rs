@@ -0,0 +1,132 @@
+use super::compile_named;
+use crate::{
+    compiled::CompiledSchema,
+    fake::{FakeError, FakeOptions, Faker},
+};
+use serde_json::json;
+
+fn order() -> serde_json::Value {
+    json!({
+        "type": "object",
+        "required": ["id", "sku", "status", "quantity", "lines", "customer"],
+        "properties": {
+            "id": { "type": "string", "format": "uuid" },
+            "sku": { "type": "string", "pattern": "^[A-Z]{3}-\\d{4}$" },
+            "status": { "enum": ["open", "shipped", "closed"] },
+            "quantity": { "type": "integer", "minimum": 1, "exclusiveMaximum": 10, "multipleOf": 3 },
+            "price": { "type": "number", "minimum": 0.5, "maximum": 2 },
+            "note": { "type": "string", "minLength": 2, "maxLength": 4 },
+            "lines": { "type": "array", "minItems": 2, "uniqueItems": true, "items": { "type": "integer", "minimum": 0, "maximum": 5 } },
+            "customer": { "oneOf": [{ "type": "string" }, { "type": "object", "required": ["name"], "properties": { "name": { "type": "string" } } }] },
+            "parent": { "$ref": "#" }
+        },
+        "additionalProperties": false
+    })
+}
+
+#[test]
+fn test_fake_valid_instances() {
+    let (context, url) = compile_named("order", order());
+    let compiled = CompiledSchema::new(context, url.clone()).unwrap();
+    let mut faker = Faker::new(
+        compiled.context(),
+        FakeOptions {
+            seed: 7,
+            ..Default::default()
+        },
+    );
+    let mut instances = vec![];
+    for _ in 0..20 {
+        let instance = faker.generate(&url).unwrap();
+        assert_eq!(compiled.validate(&instance), Ok(()), "{instance}");
+        instances.push(instance);
+    }
+
+    // the same seed generates the same instances
+    let mut again = Faker::new(
+        compiled.context(),
+        FakeOptions {
+            seed: 7,
+            ..Default::default()
+        },
+    );
+    for instance in &instances {
+        assert_eq!(&again.generate(&url).unwrap(), instance);
+    }
+
+    let mut minimal = Faker::new(
+        compiled.context(),
+        FakeOptions {
+            minimal: true,
+            ..Default::default()
+        },
+    );
+    let instance = minimal.generate(&url).unwrap();
+    assert_eq!(compiled.validate(&instance), Ok(()), "{instance}");
+    assert_eq!(instance["quantity"], json!(3));
+    assert_eq!(instance["status"], json!("open"));
+    assert_eq!(instance["sku"], json!("AAA-0000"));
+    assert_eq!(instance.as_object().unwrap().len(), 6);
+}
+
+#[test]
+fn test_fake_invalid_instances() {
+    let (context, url) = compile_named("order", order());
+    let compiled = CompiledSchema::new(context, url.clone()).unwrap();
+    let mut faker = Faker::new(compiled.context(), FakeOptions::default());
+    for _ in 0..10 {
+        let instance = faker.generate_invalid(&url).unwrap();
+        assert!(!compiled.is_valid(&instance), "{instance}");
+    }
+
+    let (context, url) = compile_named("order", json!(true));
+    let mut faker = Faker::new(&context, FakeOptions::default());
+    assert_eq!(
+        faker.generate_invalid(&url),
+        Err(FakeError::NoInvalidInstance(url.clone()))
+    );
+    let (context, url) = compile_named("order", json!(false));
+    let mut faker = Faker::new(&context, FakeOptions::default());
+    assert_eq!(
+        faker.generate(&url),
+        Err(FakeError::NoValidInstance(url.clone()))
+    );
+}
+
+#[test]
+fn test_fake_wide_ranges() {
+    for schema in [
+        json!({ "type": "number", "minimum": -1e308, "maximum": 1e308 }),
+        json!({ "type": "integer", "minimum": -1e308, "maximum": 1e308 }),
+        json!({ "type": "number", "multipleOf": 0.5, "minimum": -1e308, "maximum": 1e308 }),
+    ] {
+        let (context, url) = compile_named("order", schema);
+        let compiled = CompiledSchema::new(context, url.clone()).unwrap();
+        let mut faker = Faker::new(compiled.context(), FakeOptions::default());
+        for _ in 0..10 {
+            let instance = faker.generate(&url).unwrap();
+            assert_eq!(compiled.validate(&instance), Ok(()), "{instance}");
+        }
+    }
+}
+
+#[test]
+fn test_fake_large_integers() {
+    for (schema, unsigned) in [
+        (json!({ "type": "integer", "minimum": 1e19 }), true),
+        (
+            json!({ "type": "integer", "minimum": 1e30, "maximum": 1e31 }),
+            false,
+        ),
+        (json!({ "type": "integer", "maximum": -1e30 }), false),
+    ] {
+        let (context, url) = compile_named("order", schema);
+        let compiled = CompiledSchema::new(context, url.clone()).unwrap();
+        let mut faker = Faker::new(compiled.context(), FakeOptions::default());
+        for _ in 0..10 {
+            let instance = faker.generate(&url).unwrap();
+            assert_eq!(instance.is_u64(), unsigned, "{instance}");
+            assert_eq!(compiled.validate(&instance), Ok(()), "{instance}");
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use super::compile_named;
+use crate::graph::ReferenceGraph;
+
+#[test]
+fn test_reference_graph() {
+    let (context, root) = compile_named(
+        "tree",
+        serde_json::json!({
+            "$defs": {
+                "node": {
+                    "type": "object",
+                    "properties": {
+                        "children": { "type": "array", "items": { "$ref": "#/$defs/node" } }
+                    }
+                },
+                "unused": { "type": "string" }
+            },
+            "properties": {
+                "root": { "$ref": "#/$defs/node" }
+            }
+        }),
+    );
+    let graph = ReferenceGraph::new(&context);
+    let node = root.join("#/$defs/node").unwrap();
+    let refs: Vec<_> = graph
+        .references()
+        .map(|edge| (edge.from.fragment(), edge.keyword_location.as_str()))
+        .collect();
+    assert_eq!(
+        refs,
+        vec![
+            (None, "/properties/root/$ref"),
+            (Some("/$defs/node/properties/children"), "/items/$ref"),
+        ]
+    );
+    assert!(graph.is_recursive(&node));
+    assert!(!graph.is_recursive(&root));
+    assert_eq!(graph.cycles().len(), 1);
+    assert_eq!(
+        graph.unused_defs(),
+        vec![&root.join("#/$defs/unused").unwrap()]
+    );
+
+    let mut dot = vec![];
+    graph.write_dot(&mut dot).unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+    assert!(dot.contains(r#"[label="/properties/root/$ref"];"#));
+    let mut mermaid = vec![];
+    graph.write_mermaid(&mut mermaid).unwrap();
+    assert!(String::from_utf8(mermaid)
+        .unwrap()
+        .starts_with("flowchart LR"));
+}
+
+#[test]
+fn test_reference_graph_unevaluated_properties() {
+    let (context, root) = compile_named(
+        "tree",
+        serde_json::json!({
+            "$defs": {
+                "node": { "unevaluatedProperties": { "$ref": "#/$defs/node" } },
+                "leaf": { "type": "string" }
+            },
+            "unevaluatedProperties": { "$ref": "#/$defs/leaf" }
+        }),
+    );
+    let graph = ReferenceGraph::new(&context);
+    assert!(graph.is_recursive(&root.join("#/$defs/node").unwrap()));
+    assert_eq!(graph.cycles().len(), 1);
+    // the node is only used by itself
+    assert_eq!(
+        graph.unused_defs(),
+        vec![&root.join("#/$defs/node").unwrap()]
+    );
+}
@@ -0,0 +1,98 @@
+use super::compile;
+use crate::infer::{infer, InferOptions, Inference};
+use serde_json::json;
+
+#[test]
+fn test_infer_schema() {
+    let samples = [
+        json!({
+            "id": "0b7d3c3e-8f4a-4c4e-9a57-1f2b3c4d5e6f",
+            "status": "active",
+            "createdAt": "2023-01-02T03:04:05Z",
+            "score": 1,
+            "home": { "street": "Main", "city": "Springfield" },
+            "work": { "street": "Side", "city": "Shelbyville" },
+            "tags": ["a", 1]
+        }),
+        json!({
+            "id": "9c1f4a6e-2b3d-4e5f-8a7b-6c5d4e3f2a1b",
+            "status": "inactive",
+            "createdAt": "2023-02-03T04:05:06.789+01:00",
+            "score": 2.5,
+            "home": { "street": "Elm", "city": "Ogdenville" },
+            "website": "https://example.com",
+            "tags": []
+        }),
+        json!({
+            "id": "3f2a1b0c-9d8e-4f7a-b6c5-d4e3f2a1b0c9",
+            "status": "active",
+            "createdAt": "2023-03-04T05:06:07Z",
+            "score": -3,
+            "home": { "street": "Oak", "city": "Capital City" },
+            "work": { "street": "Main", "city": "Springfield" },
+            "tags": [true]
+        }),
+        json!({
+            "id": "6e5d4c3b-2a1f-4e9d-8c7b-a6f5e4d3c2b1",
+            "status": "inactive",
+            "createdAt": "2023-04-05T06:07:08Z",
+            "score": 0,
+            "home": { "street": "Pine", "city": "North Haverbrook" },
+            "tags": []
+        }),
+    ];
+    let schema = serde_json::to_value(infer(&samples)).unwrap();
+    assert_eq!(
+        schema,
+        json!({
+            "type": "object",
+            "required": ["createdAt", "home", "id", "score", "status", "tags"],
+            "properties": {
+                "id": { "type": "string", "format": "uuid" },
+                "status": { "enum": ["active", "inactive"], "type": "string" },
+                "createdAt": { "type": "string", "format": "date-time" },
+                "score": { "type": "number", "minimum": -3, "maximum": 2.5 },
+                "home": { "$ref": "#/$defs/home" },
+                "work": { "$ref": "#/$defs/home" },
+                "website": { "type": "string", "format": "uri" },
+                "tags": { "type": "array", "items": { "type": ["boolean", "integer", "string"], "minimum": 1, "maximum": 1 } }
+            },
+            "$defs": {
+                "home": {
+                    "type": "object",
+                    "required": ["city", "street"],
+                    "properties": {
+                        "city": { "type": "string" },
+                        "street": { "type": "string" }
+                    }
+                }
+            }
+        })
+    );
+
+    // the inferred schema accepts its samples
+    let compiled = compile(schema);
+    for sample in &samples {
+        assert_eq!(compiled.validate(sample), Ok(()), "{sample}");
+    }
+}
+
+#[test]
+fn test_infer_options() {
+    let mut inference = Inference::new(InferOptions {
+        required_ratio: 0.5,
+        numeric_bounds: false,
+        ..Default::default()
+    });
+    for sample in [
+        json!({ "a": 1, "b": 1 }),
+        json!({ "a": 2 }),
+        json!({ "c": 3 }),
+    ] {
+        inference.add(&sample);
+    }
+    let schema = serde_json::to_value(inference.finish()).unwrap();
+    assert_eq!(schema["required"], json!(["a"]));
+    assert_eq!(schema["properties"]["a"], json!({ "type": "integer" }));
+    assert_eq!(serde_json::to_value(infer([])).unwrap(), json!(false));
+}
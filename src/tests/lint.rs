@@ -0,0 +1,161 @@
+use super::compile_named;
+use crate::{
+    keywords::{IllogicalSchema, Keywords},
+    lint::{lint, lint_annotations, lint_document},
+};
+use serde_json::json;
+
+#[test]
+fn test_illogical_keywords() {
+    let keywords: Keywords = serde_json::from_value(json!({
+        "type": "string",
+        "minLength": 3,
+        "maxLength": 2,
+        "minimum": 1,
+        "const": 4,
+        "enum": ["abc", "ab", 5]
+    }))
+    .unwrap();
+    assert!(!keywords.is_logical());
+    let pointers: Vec<_> = keywords
+        .illogical()
+        .iter()
+        .map(|problem| problem.to_string())
+        .collect();
+    assert_eq!(
+        pointers,
+        [
+            "/minLength: minLength is greater than maxLength",
+            "/const: const is not of an allowed type",
+            "/enum/0: enum value is not valid against const",
+            "/enum/1: enum value is not valid against const",
+            "/enum/2: enum value is not valid against type",
+            "/minimum: minimum does not apply to any allowed type",
+        ]
+    );
+
+    let keywords: Keywords =
+        serde_json::from_value(json!({ "exclusiveMinimum": 2, "maximum": 2 })).unwrap();
+    assert_eq!(
+        keywords.illogical(),
+        [IllogicalSchema::EmptyRange {
+            pointer: "/exclusiveMinimum".to_owned(),
+            minimum: "exclusiveMinimum",
+            maximum: "maximum",
+        }]
+    );
+    let keywords: Keywords =
+        serde_json::from_value(json!({ "type": "integer", "minimum": 1, "maximum": 2 })).unwrap();
+    assert!(keywords.is_logical());
+    // an inapplicable keyword does nothing but contradicts nothing either
+    let keywords: Keywords =
+        serde_json::from_value(json!({ "type": "string", "minimum": 1 })).unwrap();
+    assert!(keywords.is_logical());
+    assert!(!keywords.illogical().is_empty());
+}
+
+#[test]
+fn test_lint_context() {
+    let schema = json!({
+        "properties": {
+            "id": false,
+            "item": {
+                "allOf": [{ "type": "string" }, { "type": ["integer", "null"] }],
+                "required": ["sku", "name", "x-extra"],
+                "properties": { "name": true },
+                "patternProperties": { "^x-": true },
+                "additionalProperties": false
+            }
+        },
+        "required": ["id"]
+    });
+    let (context, _) = compile_named("order", schema);
+    let problems: Vec<_> = lint(&context)
+        .into_iter()
+        .map(|(document, problem)| (document.to_string(), problem.pointer().to_owned()))
+        .collect();
+    assert_eq!(
+        problems,
+        [
+            (
+                "file:///schemas/order.json".to_owned(),
+                "/required/0".to_owned()
+            ),
+            (
+                "file:///schemas/order.json".to_owned(),
+                "/properties/item/required/0".to_owned()
+            ),
+            (
+                "file:///schemas/order.json".to_owned(),
+                "/properties/item/allOf".to_owned()
+            ),
+        ]
+    );
+
+    let document = json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "properties": {
+            "a": { "$ref": "#/definitions/a", "description": "ignored" },
+            "b": { "type": "object", "properties": { "required": ["c"] } },
+            // schemas named like instance keywords are linted
+            "enum": { "properties": { "required": ["d"] } },
+            "$ref": { "type": "string" }
+        },
+        "const": { "$ref": "#", "x": 1 }
+    });
+    assert_eq!(
+        lint_document(&document),
+        [
+            IllogicalSchema::RefSiblings {
+                pointer: "/properties/a/$ref".to_owned(),
+                keywords: vec!["description".to_owned()],
+            },
+            IllogicalSchema::RequiredInProperties {
+                pointer: "/properties/b/properties/required".to_owned(),
+            },
+            IllogicalSchema::RequiredInProperties {
+                pointer: "/properties/enum/properties/required".to_owned(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_lint_annotations() {
+    let schema = json!({
+        "properties": {
+            "mode": { "enum": ["fast", "safe"], "default": "quick" },
+            "retries": { "type": "integer", "default": 3, "examples": [1, "2", 2.5] }
+        },
+        "default": { "mode": "fast" },
+        "examples": [{ "retries": -1.5 }]
+    });
+    let (context, _) = compile_named("settings", schema);
+    let problems: Vec<_> = lint_annotations(&context)
+        .into_iter()
+        .map(|(_, problem)| match problem {
+            IllogicalSchema::InvalidAnnotation {
+                pointer, errors, ..
+            } => {
+                let locations: Vec<_> = errors.into_iter().map(|e| e.instance_location).collect();
+                (pointer, locations)
+            }
+            problem => panic!("unexpected {problem}"),
+        })
+        .collect();
+    assert_eq!(
+        problems,
+        [
+            ("/examples/0".to_owned(), vec!["/retries".to_owned()]),
+            ("/properties/mode/default".to_owned(), vec!["".to_owned()]),
+            (
+                "/properties/retries/examples/1".to_owned(),
+                vec!["".to_owned()]
+            ),
+            (
+                "/properties/retries/examples/2".to_owned(),
+                vec!["".to_owned()]
+            ),
+        ]
+    );
+}
@@ -0,0 +1,93 @@
+use super::compile;
+use crate::migrate::{migrate, MigrationChange, DRAFT_2020_12};
+use serde_json::json;
+
+#[test]
+fn test_migrate_draft_04() {
+    let document = json!({
+        "$schema": "http://json-schema.org/draft-04/schema#",
+        "id": "http://example.com/order.json",
+        "type": "object",
+        "properties": {
+            "id": { "type": "string" },
+            "total": { "type": "number", "minimum": 0, "exclusiveMinimum": true },
+            "point": {
+                "type": "array",
+                "items": [{ "type": "number" }, { "type": "number" }],
+                "additionalItems": false
+            },
+            "shipping": { "$ref": "#/definitions/address", "description": "ignored" },
+            "first": { "$ref": "#/properties/point/items/0" }
+        },
+        "dependencies": {
+            "shipping": ["total"],
+            "billing": { "required": ["shipping"] }
+        },
+        "definitions": {
+            "address": { "id": "#address", "type": "object" }
+        }
+    });
+    let migration = migrate(&document);
+    assert_eq!(
+        migration.schema,
+        json!({
+            "$schema": DRAFT_2020_12,
+            "$id": "http://example.com/order.json",
+            "type": "object",
+            "properties": {
+                "id": { "type": "string" },
+                "total": { "type": "number", "exclusiveMinimum": 0 },
+                "point": {
+                    "type": "array",
+                    "prefixItems": [{ "type": "number" }, { "type": "number" }],
+                    "items": false
+                },
+                "shipping": { "$ref": "#/$defs/address" },
+                "first": { "$ref": "#/properties/point/prefixItems/0" }
+            },
+            "dependentRequired": { "shipping": ["total"] },
+            "dependentSchemas": { "billing": { "required": ["shipping"] } },
+            "$defs": {
+                "address": { "$anchor": "address", "type": "object" }
+            }
+        })
+    );
+    let log: Vec<String> = migration.changes.iter().map(ToString::to_string).collect();
+    assert!(log.contains(&"/properties/total/exclusiveMinimum: exclusiveMinimum -> exclusiveMinimum, takes the number of minimum".to_owned()), "{log:#?}");
+    assert!(migration.changes.contains(&MigrationChange {
+        pointer: "/properties/shipping/description".to_owned(),
+        from: "description".to_owned(),
+        to: None,
+        note: "ignored next to $ref before 2019-09".to_owned(),
+    }));
+    assert_eq!(migration.changes.len(), 12, "{log:#?}");
+
+    let compiled = compile(migration.schema);
+    assert!(compiled.is_valid(&json!({ "total": 1, "point": [1, 2] })));
+    assert!(!compiled.is_valid(&json!({ "total": 0 })));
+    assert!(!compiled.is_valid(&json!({ "point": [1, 2, 3] })));
+    // the split dependencies still apply
+    assert!(!compiled.is_valid(&json!({ "shipping": {} })));
+    assert!(!compiled.is_valid(&json!({ "billing": 1 })));
+    assert!(compiled.is_valid(&json!({ "billing": 1, "shipping": {}, "total": 1 })));
+}
+
+#[test]
+fn test_migrate_recursive_ref() {
+    let document = json!({
+        "$schema": "https://json-schema.org/draft/2019-09/schema",
+        "$recursiveAnchor": true,
+        "properties": { "children": { "items": { "$recursiveRef": "#" } } }
+    });
+    let migration = migrate(&document);
+    assert_eq!(
+        migration.schema,
+        json!({
+            "$schema": DRAFT_2020_12,
+            "$dynamicAnchor": "meta",
+            "properties": { "children": { "items": { "$dynamicRef": "#meta" } } }
+        })
+    );
+    // already migrated documents are left as they are
+    assert!(migrate(&migration.schema).changes.is_empty());
+}
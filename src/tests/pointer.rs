@@ -0,0 +1,127 @@
+use crate::{
+    pointer::{
+        AbsoluteJsonPointer, JsonPointer, JsonPointerFragment, PointerError, RelativeJsonPointer,
+        RelativeValue,
+    },
+    value::{Location, ValueArena},
+};
+use serde_json::json;
+
+#[test]
+fn test_absolute_json_pointer() {
+    let document = json!({ "foo": ["bar", "baz"], "a/b": 1, "m~n": 8, "c%d": 2, " ": 7 });
+    for (pointer, fragment, expected) in [
+        ("", "", json!(document)),
+        ("/foo/0", "/foo/0", json!("bar")),
+        ("/a~1b", "/a~1b", json!(1)),
+        ("/m~0n", "/m~0n", json!(8)),
+        ("/c%d", "/c%25d", json!(2)),
+        ("/ ", "/%20", json!(7)),
+    ] {
+        let parsed = AbsoluteJsonPointer::parse(pointer).unwrap();
+        assert_eq!(parsed.to_string(), pointer);
+        assert_eq!(parsed.to_fragment(), fragment);
+        assert_eq!(
+            AbsoluteJsonPointer::from_fragment(&format!("#{fragment}")),
+            Ok(parsed.clone())
+        );
+        assert_eq!(parsed.resolve(&document), Ok(&expected));
+    }
+
+    let mut pointer = AbsoluteJsonPointer::root();
+    pointer.push("a/b");
+    assert_eq!(pointer.to_string(), "/a~1b");
+    assert_eq!(pointer.pop().as_deref(), Some("a/b"));
+    assert!(pointer.is_root());
+
+    assert_eq!(
+        AbsoluteJsonPointer::parse("foo"),
+        Err(PointerError::MissingSlash)
+    );
+    assert_eq!(
+        AbsoluteJsonPointer::parse("/~2"),
+        Err(PointerError::InvalidEscape)
+    );
+    let missing = AbsoluteJsonPointer::parse("/foo/01").unwrap();
+    assert_eq!(
+        missing.resolve(&document),
+        Err(PointerError::NotFound("01".into()))
+    );
+
+    let relative = JsonPointer::Relative("#/foo".into());
+    assert_eq!(
+        relative.absolute(),
+        Err(PointerError::NotAbsolute("#/foo".into()))
+    );
+}
+
+#[test]
+fn test_relative_json_pointer() {
+    let document = json!({ "foo": ["bar", "baz"], "highly": { "nested": { "objects": true } } });
+    let arena = ValueArena::from(document.clone());
+    let baz = AbsoluteJsonPointer::parse("/foo/1").unwrap();
+    let objects = AbsoluteJsonPointer::parse("/highly/nested").unwrap();
+    for (location, pointer, expected) in [
+        (&baz, "0", Ok(RelativeValue::Value(json!("baz")))),
+        (&baz, "1/0", Ok(RelativeValue::Value(json!("bar")))),
+        (&baz, "0-1", Ok(RelativeValue::Value(json!("bar")))),
+        (
+            &baz,
+            "2/highly/nested/objects",
+            Ok(RelativeValue::Value(json!(true))),
+        ),
+        (&baz, "0#", Ok(RelativeValue::Location(Location::Index(1)))),
+        (
+            &baz,
+            "0-1#",
+            Ok(RelativeValue::Location(Location::Index(0))),
+        ),
+        (
+            &baz,
+            "1#",
+            Ok(RelativeValue::Location(Location::Key("foo"))),
+        ),
+        (&objects, "0/objects", Ok(RelativeValue::Value(json!(true)))),
+        (
+            &objects,
+            "1/nested/objects",
+            Ok(RelativeValue::Value(json!(true))),
+        ),
+        (
+            &objects,
+            "0#",
+            Ok(RelativeValue::Location(Location::Key("nested"))),
+        ),
+        (&objects, "2#", Err(PointerError::NoLocation)),
+        (&objects, "3", Err(PointerError::OutOfDocument)),
+        (&objects, "0+1", Err(PointerError::NotAnItem)),
+        (&baz, "0+1", Err(PointerError::NotFound("2".into()))),
+    ] {
+        let relative = RelativeJsonPointer::parse(pointer).unwrap();
+        assert_eq!(relative.to_string(), pointer);
+        let node = arena.pointer(&location.to_string()).unwrap();
+        let from_arena = relative.evaluate(node).map(|value| match value {
+            RelativeValue::Value(node) => RelativeValue::Value(node.pack()),
+            RelativeValue::Location(location) => RelativeValue::Location(location),
+        });
+        let from_value = relative
+            .evaluate_at(&document, location)
+            .map(|value| match value {
+                RelativeValue::Value(value) => RelativeValue::Value(value.clone()),
+                RelativeValue::Location(location) => RelativeValue::Location(location),
+            });
+        assert_eq!(from_arena, expected, "{pointer} from {location}");
+        assert_eq!(from_value, expected, "{pointer} from {location}");
+    }
+
+    for invalid in ["", "01", "-1", "0+", "0foo", "0/~"] {
+        assert!(RelativeJsonPointer::parse(invalid).is_err(), "{invalid}");
+    }
+    assert!(matches!(
+        "1/foo".parse(),
+        Ok(JsonPointerFragment::Relative(RelativeJsonPointer {
+            traverse: 1,
+            ..
+        }))
+    ));
+}
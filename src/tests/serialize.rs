@@ -0,0 +1,89 @@
+use super::compile;
+use crate::stream::StreamError;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+#[derive(Serialize)]
+struct Order {
+    id: u64,
+    customer: Option<String>,
+    lines: Vec<Line>,
+    status: Status,
+    meta: BTreeMap<u32, f64>,
+}
+
+#[derive(Serialize)]
+struct Line(String, i32);
+
+#[derive(Serialize)]
+enum Status {
+    Open,
+    Shipped { carrier: String },
+    Held(String, u8),
+}
+
+#[test]
+fn test_serialize_matches_value() {
+    let schema = compile(json!({
+        "type": "object",
+        "required": ["id", "customer", "lines"],
+        "properties": {
+            "id": { "minimum": 1 },
+            "customer": { "type": "string" },
+            "lines": {
+                "type": "array",
+                "items": { "prefixItems": [{ "pattern": "^[A-Z]+$" }, { "exclusiveMinimum": 0 }] },
+                "uniqueItems": true
+            },
+            "status": {
+                "oneOf": [
+                    { "const": "Open" },
+                    { "type": "object", "required": ["Shipped"], "properties": { "Shipped": { "required": ["carrier"] } } },
+                    { "type": "object", "required": ["Held"], "properties": { "Held": { "items": { "maxLength": 3 } } } }
+                ]
+            },
+            "meta": { "propertyNames": { "pattern": "^[0-9]$" }, "additionalProperties": { "maximum": 1 } }
+        }
+    }));
+    let orders = [
+        Order {
+            id: 1,
+            customer: Some("a".to_owned()),
+            lines: vec![Line("AB".to_owned(), 1)],
+            status: Status::Open,
+            meta: BTreeMap::from([(1, 0.5)]),
+        },
+        Order {
+            id: 0,
+            customer: None,
+            lines: vec![Line("ab".to_owned(), 0), Line("ab".to_owned(), 0)],
+            status: Status::Held("long".to_owned(), 1),
+            meta: BTreeMap::from([(10, 2.0)]),
+        },
+        Order {
+            id: 2,
+            customer: Some("b".to_owned()),
+            lines: vec![],
+            status: Status::Shipped {
+                carrier: "x".to_owned(),
+            },
+            meta: BTreeMap::new(),
+        },
+    ];
+    for order in &orders {
+        let value = serde_json::to_value(order).unwrap();
+        let mut expected = schema.validate(&value).err();
+        let mut actual = match schema.validate_serialize(order) {
+            Ok(()) => None,
+            Err(StreamError::Invalid(errors)) => Some(errors),
+            Err(StreamError::Source(error)) => panic!("{error}"),
+        };
+        for errors in [&mut expected, &mut actual].into_iter().flatten() {
+            errors.sort_by_key(|error| error.to_string());
+        }
+        assert_eq!(actual, expected, "{value}");
+    }
+    assert!(schema.validate_serialize(&orders[0]).is_ok());
+    assert!(schema.validate_serialize(&orders[1]).is_err());
+}
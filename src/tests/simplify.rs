@@ -0,0 +1,103 @@
+use super::compile_named;
+use crate::{
+    canonical::{CanonicalOptions, Canonicalizer},
+    compiled::CompiledSchema,
+    context::Context,
+    simplify::simplify,
+};
+use serde_json::{json, Value};
+use url::Url;
+
+fn canonical(context: &Context, url: &Url) -> Value {
+    let canonicalizer = Canonicalizer::new(context, CanonicalOptions::default());
+    canonicalizer.canonicalize(url).unwrap()
+}
+
+#[test]
+fn test_simplify_merges_all_of() {
+    let schema = json!({
+        "allOf": [
+            {
+                "type": ["object", "null"],
+                "required": ["id"],
+                "properties": {
+                    "id": { "type": "integer", "minimum": 0 },
+                    "status": { "enum": ["open", "closed", "void"] }
+                }
+            },
+            {
+                "type": "object",
+                "required": ["status", "id"],
+                "properties": {
+                    "id": { "type": "number", "maximum": 100 },
+                    "status": { "enum": ["closed", "open"] }
+                }
+            },
+            {
+                "properties": { "note": { "type": "string" } },
+                "additionalProperties": false
+            }
+        ]
+    });
+    let (mut context, url) = compile_named("order", schema.clone());
+    let instances = [
+        json!({ "id": 1, "status": "open" }),
+        json!({ "id": 101, "status": "open" }),
+        json!({ "id": 1, "status": "void" }),
+        json!({ "id": 1, "status": "open", "note": "" }),
+        json!(null),
+    ];
+    let before: Vec<bool> = {
+        let (context, url) = compile_named("order", schema);
+        let schema = CompiledSchema::new(context, url).unwrap();
+        instances.iter().map(|i| schema.is_valid(i)).collect()
+    };
+
+    let report = simplify(&mut context);
+    assert_eq!(report.simplified.first(), Some(&url));
+    assert!(report.unsatisfiable.is_empty());
+    let simplified = canonical(&context, &url);
+    assert_eq!(simplified["type"], json!("object"));
+    assert_eq!(simplified["required"], json!(["id", "status"]));
+    assert_eq!(
+        simplified["properties"],
+        json!({
+            "id": { "type": "integer", "minimum": 0, "maximum": 100 },
+            "status": { "enum": ["closed", "open"] }
+        })
+    );
+    // the member with `additionalProperties` would also forbid the merged properties
+    assert_eq!(simplified["allOf"].as_array().map(Vec::len), Some(1));
+
+    let schema = CompiledSchema::new(context, url).unwrap();
+    let after: Vec<bool> = instances.iter().map(|i| schema.is_valid(i)).collect();
+    assert_eq!(before, after);
+}
+
+#[test]
+fn test_simplify_detects_unsatisfiable_schemas() {
+    let (mut context, url) = compile_named(
+        "order",
+        json!({
+            "properties": {
+                "count": { "allOf": [{ "type": "integer", "minimum": 10 }, { "maximum": 5 }] },
+                "kind": { "allOf": [{ "const": "a" }, { "enum": ["b", "c"] }] },
+                "name": { "allOf": [{ "type": "string" }, { "minLength": 1 }] }
+            }
+        }),
+    );
+    let report = simplify(&mut context);
+    let property = |name: &str| url.join(&format!("#/properties/{name}")).unwrap();
+    assert_eq!(
+        report.unsatisfiable,
+        vec![property("count"), property("kind")]
+    );
+    assert_eq!(
+        canonical(&context, &url)["properties"],
+        json!({
+            "count": false,
+            "kind": false,
+            "name": { "type": "string", "minLength": 1 }
+        })
+    );
+}
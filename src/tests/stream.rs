@@ -0,0 +1,67 @@
+use super::compile;
+use crate::{stream::StreamError, validator::ValidationError};
+use serde_json::json;
+
+fn sorted(mut errors: Vec<ValidationError>) -> Vec<(String, String)> {
+    errors.sort_by(|l, r| {
+        (&l.instance_location, &l.keyword_location)
+            .cmp(&(&r.instance_location, &r.keyword_location))
+    });
+    errors
+        .into_iter()
+        .map(|error| (error.instance_location, error.keyword_location))
+        .collect()
+}
+
+#[test]
+fn test_stream_matches_value() {
+    let schema = compile(json!({
+        "type": "object",
+        "required": ["id", "tags"],
+        "maxProperties": 4,
+        "properties": {
+            "id": { "type": "integer" },
+            "tags": {
+                "type": "array",
+                "prefixItems": [{ "const": "first" }],
+                "items": { "type": "string", "maxLength": 3 },
+                "contains": { "const": "a" },
+                "uniqueItems": true
+            },
+            "children": { "type": "array", "items": { "$ref": "#" }, "maxItems": 1 }
+        },
+        "patternProperties": { "^x-": { "type": "boolean" } },
+        "additionalProperties": false,
+        "propertyNames": { "maxLength": 8 },
+        "dependentRequired": { "other": ["id"] },
+        "dependentSchemas": { "children": { "maxProperties": 2 } },
+        "allOf": [{ "properties": { "id": { "minimum": 1 } } }]
+    }));
+    for instance in [
+        json!({ "id": 1, "tags": ["first", "a"], "x-y": true }),
+        json!({ "id": 0, "tags": ["first", "abcd", "abcd"], "x-y": 1 }),
+        json!({ "tags": ["b"], "other": null, "children": [{ "id": "1", "tags": [] }, {}] }),
+        json!({ "id": 1.5, "tags": {}, "x-long-name": false }),
+        json!([1, 2]),
+    ] {
+        let expected = schema.validate(&instance).err().map(sorted);
+        let text = instance.to_string();
+        let mut deserializer = serde_json::Deserializer::from_str(&text);
+        let actual = match schema.validate_deserializer(&mut deserializer) {
+            Ok(()) => None,
+            Err(StreamError::Invalid(errors)) => Some(sorted(errors)),
+            Err(StreamError::Source(error)) => panic!("{error}"),
+        };
+        assert_eq!(actual, expected, "{instance}");
+    }
+
+    let yaml = "id: 1\ntags: [first, a, ab]\nx-y: true\n";
+    let result = schema.validate_deserializer(serde_yaml::Deserializer::from_str(yaml));
+    assert!(result.is_ok());
+
+    let mut deserializer = serde_json::Deserializer::from_str(r#"{ "id": 1, "tags": [ "#);
+    assert!(matches!(
+        schema.validate_deserializer(&mut deserializer),
+        Err(StreamError::Source(_))
+    ));
+}
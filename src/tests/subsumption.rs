@@ -0,0 +1,141 @@
+use super::{compile, compile_with, data::data_context};
+use crate::subsumption::{is_subschema, Counterexample, Subsumption};
+use serde_json::json;
+
+#[test]
+fn test_subsumption_holds() {
+    let old = compile(json!({
+        "type": "object",
+        "required": ["name"],
+        "properties": {
+            "name": { "type": "string", "maxLength": 10 },
+            "age": { "type": "integer", "minimum": 0, "maximum": 100 }
+        },
+        "additionalProperties": false
+    }));
+    let new = compile(json!({
+        "type": "object",
+        "properties": {
+            "name": { "type": "string", "maxLength": 20 },
+            "age": { "type": "number", "minimum": 0 },
+            "email": { "type": "string" }
+        },
+        "additionalProperties": false
+    }));
+    assert_eq!(is_subschema(&old, &new), Subsumption::Yes);
+    assert!(matches!(is_subschema(&new, &old), Subsumption::No(_)));
+}
+
+#[test]
+fn test_subsumption_counterexample() {
+    let a = compile(json!({
+        "type": "object",
+        "properties": { "size": { "type": "object", "properties": { "width": { "type": "integer", "maximum": 10 } } } }
+    }));
+    let b = compile(json!({
+        "type": "object",
+        "properties": { "size": { "type": "object", "properties": { "width": { "type": "integer", "maximum": 5 } } } }
+    }));
+    let Subsumption::No(Counterexample {
+        instance_location,
+        value,
+        instance,
+        ..
+    }) = is_subschema(&a, &b)
+    else {
+        panic!("expected a counterexample");
+    };
+    assert_eq!(instance_location, "/size/width");
+    assert!(value.as_i64().is_some_and(|width| width > 5 && width <= 10));
+    assert_eq!(instance, json!({ "size": { "width": value } }));
+
+    // the properties A requires around the value are filled in
+    let a = compile(json!({
+        "required": ["id"],
+        "properties": { "id": { "type": "integer" }, "tags": { "items": { "type": "string" } } }
+    }));
+    let b = compile(json!({ "properties": { "tags": { "items": { "maxLength": 0 } } } }));
+    let Subsumption::No(Counterexample { instance, .. }) = is_subschema(&a, &b) else {
+        panic!("expected a counterexample");
+    };
+    assert_eq!(instance, json!({ "id": 0, "tags": ["a"] }));
+
+    let a = compile(json!({ "type": "object" }));
+    let b = compile(json!({ "type": "object", "required": ["id"] }));
+    assert!(matches!(
+        is_subschema(&a, &b),
+        Subsumption::No(Counterexample { value, .. }) if value == json!({})
+    ));
+
+    let a = compile(json!({ "enum": ["a", "b", "c"] }));
+    let b = compile(json!({ "enum": ["a", "b"] }));
+    assert!(matches!(
+        is_subschema(&a, &b),
+        Subsumption::No(Counterexample { value, .. }) if value == json!("c")
+    ));
+    assert_eq!(is_subschema(&b, &a), Subsumption::Yes);
+}
+
+#[test]
+fn test_subsumption_unknown() {
+    let a = compile(json!({ "type": "string", "pattern": "^a+$" }));
+    let b = compile(json!({ "type": "string", "pattern": "^[ab]+$" }));
+    assert!(matches!(is_subschema(&a, &b), Subsumption::Unknown { .. }));
+
+    // allOf of A rejects the value that the property of A alone accepts
+    let a = compile(json!({
+        "properties": { "x": { "type": "string" } },
+        "allOf": [{ "properties": { "x": { "maxLength": 0 } } }]
+    }));
+    let b = compile(json!({ "properties": { "x": { "maxLength": 0 } } }));
+    assert!(matches!(
+        is_subschema(&a, &b),
+        Subsumption::Unknown { instance_location, .. } if instance_location == "/x"
+    ));
+}
+
+#[test]
+fn test_subsumption_data_references() {
+    let a = compile(json!(true));
+    for keyword in [
+        "maxLength",
+        "exclusiveMinimum",
+        "multipleOf",
+        "minItems",
+        "maxProperties",
+    ] {
+        // the value of the keyword is only known for an instance
+        let b = compile_with(
+            data_context(),
+            json!({ "properties": { "a": { keyword: { "$data": "1/n" } } } }),
+        );
+        assert!(
+            matches!(is_subschema(&a, &b), Subsumption::Unknown { .. }),
+            "{keyword}"
+        );
+    }
+}
+
+#[test]
+fn test_subsumption_recursive_ref() {
+    let a = compile(json!({
+        "type": "object",
+        "properties": {
+            "value": { "type": "integer" },
+            "next": { "$ref": "#" }
+        }
+    }));
+    let b = compile(json!({
+        "type": "object",
+        "properties": {
+            "value": { "type": "number" },
+            "next": { "$ref": "#" }
+        }
+    }));
+    assert_eq!(is_subschema(&a, &b), Subsumption::Yes);
+    let Subsumption::No(counterexample) = is_subschema(&b, &a) else {
+        panic!("expected a counterexample");
+    };
+    assert_eq!(counterexample.instance_location, "/value");
+    assert_eq!(counterexample.value, json!(0.5));
+}
@@ -0,0 +1,56 @@
+use super::compile;
+use crate::value::{Instance, Location, ValueArena};
+use serde_json::json;
+
+#[test]
+fn test_value_arena() {
+    let document = json!({
+        "name": "a",
+        "tags": ["x", "y/z"],
+        "nested": { "a~b": [null, { "deep": 1.5 }] }
+    });
+    let arena = ValueArena::from(document.clone());
+    assert_eq!(serde_json::Value::from(&arena), document);
+    assert_eq!(ValueArena::new().document().pack(), json!(null));
+
+    let deep = arena.pointer("/nested/a~0b/1/deep").unwrap();
+    assert_eq!(deep.pack(), json!(1.5));
+    assert_eq!(deep.location(), Some(Location::Key("deep")));
+    assert_eq!(deep.pointer_from_document(), "/nested/a~0b/1/deep");
+
+    let item = deep.parent().unwrap();
+    assert_eq!(item.location(), Some(Location::Index(1)));
+    let list = item.parent().unwrap();
+    assert_eq!(list.pointer("/0").unwrap().pack(), json!(null));
+    assert!(list.pointer("/01").is_none());
+    assert!(list.parent().unwrap().parent().unwrap().parent().is_none());
+    assert_eq!(arena.document().location(), None);
+    assert_eq!(arena.document().len(), 3);
+}
+
+#[test]
+fn test_validate_arena() {
+    let schema = compile(json!({
+        "type": "object",
+        "properties": {
+            "tags": { "type": "array", "items": { "type": "string" }, "uniqueItems": true },
+            "size": { "enum": [1, 2.5] }
+        },
+        "unevaluatedProperties": false
+    }));
+    for document in [
+        json!({ "tags": ["a", "b"], "size": 1.0 }),
+        json!({ "tags": ["a", 1, "a"], "size": 3, "other": true }),
+        json!([]),
+    ] {
+        let arena = ValueArena::from(document.clone());
+        assert_eq!(
+            schema.validate(arena.document()),
+            schema.validate(&document)
+        );
+        assert_eq!(
+            schema.is_valid(arena.document()),
+            schema.is_valid(&document)
+        );
+    }
+}
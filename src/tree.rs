@@ -0,0 +1,372 @@
+//! Schemas lowered into a compact evaluation tree.
+//!
+//! Every schema of a [`Context`] becomes a [`Node`] holding only the keywords that are present as
+//! [`Op`]s. Subschemas are referenced by their index in the tree instead of their url and patterns
+//! are compiled once, so validating against the tree does not need to hash or allocate unless it
+//! reports an error.
+
+use crate::{
+    context::Context,
+    keywords::{Data, Keywords},
+    pointer::JsonPointerFragment,
+    schema::{JsonSchema, ResolvedJsonSchema},
+};
+use regex::Regex;
+use serde_json::{Number, Value};
+use std::collections::HashMap;
+use url::Url;
+
+pub const NULL: u8 = 1 << 0;
+pub const BOOLEAN: u8 = 1 << 1;
+pub const INTEGER: u8 = 1 << 2;
+pub const NUMBER: u8 = 1 << 3;
+pub const STRING: u8 = 1 << 4;
+pub const ARRAY: u8 = 1 << 5;
+pub const OBJECT: u8 = 1 << 6;
+
+pub const TYPES: [(&str, u8); 7] = [
+    ("null", NULL),
+    ("boolean", BOOLEAN),
+    ("integer", INTEGER),
+    ("number", NUMBER),
+    ("string", STRING),
+    ("array", ARRAY),
+    ("object", OBJECT),
+];
+
+/// The bits of a type name as used by the `type` keyword, unknown names match nothing.
+pub fn type_mask(name: &str) -> u8 {
+    match name {
+        "null" => NULL,
+        "boolean" => BOOLEAN,
+        "integer" => INTEGER,
+        // every integer is also a number
+        "number" => NUMBER | INTEGER,
+        "string" => STRING,
+        "array" => ARRAY,
+        "object" => OBJECT,
+        _ => 0,
+    }
+}
+
+/// A keyword lowered for evaluation. Subschemas are indices of nodes in the tree.
+pub enum Op {
+    /// the `false` schema.
+    False,
+    /// a schema that could not be resolved when the tree was built.
+    Unresolved(Url),
+    /// a keyword that could not be lowered, e.g. a pattern that is not a valid regex.
+    Invalid(&'static str, String),
+    Type(u8),
+    Const(Value),
+    Enum(Box<[Value]>),
+    MultipleOf(Number),
+    Minimum(Number),
+    Maximum(Number),
+    ExclusiveMinimum(Number),
+    ExclusiveMaximum(Number),
+    MinLength(u64),
+    MaxLength(u64),
+    Pattern(Regex),
+    MinItems(u64),
+    MaxItems(u64),
+    UniqueItems,
+    MinProperties(u64),
+    MaxProperties(u64),
+    Required(Box<[String]>),
+    /// sorted by property name, the properties required if that property is present.
+    DependentRequired(Box<[(String, Box<[String]>)]>),
+    /// sorted by property name.
+    DependentSchemas(Box<[(String, usize)]>),
+    /// sorted by property name.
+    Properties(Box<[(String, usize)]>),
+    PatternProperties(Box<[(Regex, usize)]>),
+    AdditionalProperties {
+        node: usize,
+        /// the sorted names of the sibling `properties`.
+        properties: Box<[String]>,
+        /// the sibling `patternProperties`.
+        patterns: Box<[Regex]>,
+    },
+    PropertyNames(usize),
+    PrefixItems(Box<[usize]>),
+    Items {
+        node: usize,
+        /// the number of items covered by a sibling `prefixItems`.
+        skip: usize,
+    },
+    Contains {
+        node: usize,
+        min: u64,
+        max: Option<u64>,
+    },
+    AllOf(Box<[usize]>),
+    AnyOf(Box<[usize]>),
+    OneOf(Box<[usize]>),
+    Not(usize),
+    IfThenElse {
+        if_: usize,
+        then: Option<usize>,
+        else_: Option<usize>,
+    },
+    DynamicRef(usize),
+    UnevaluatedItems(usize),
+    UnevaluatedProperties(usize),
+    /// a value keyword whose value is read from the instance by a `$data` reference.
+    Data {
+        keyword: &'static str,
+        pointer: JsonPointerFragment,
+    },
+}
+
+impl Op {
+    /// The op of a value keyword for a value read by a `$data` reference, the message of the
+    /// error if the value is not valid for the keyword.
+    pub fn data(keyword: &'static str, value: Value) -> Result<Op, String> {
+        let invalid = |value: &Value| format!("{value} is not a valid value of {keyword}");
+        let number = |value: &Value| value.as_number().cloned().ok_or_else(|| invalid(value));
+        let limit = |value: &Value| value.as_u64().ok_or_else(|| invalid(value));
+        Ok(match keyword {
+            "const" => Op::Const(value),
+            "enum" => match value {
+                Value::Array(values) => Op::Enum(values.into_boxed_slice()),
+                value => return Err(invalid(&value)),
+            },
+            "multipleOf" => match value.as_f64() {
+                Some(divisor) if divisor > 0.0 => Op::MultipleOf(number(&value)?),
+                _ => return Err(invalid(&value)),
+            },
+            "minimum" => Op::Minimum(number(&value)?),
+            "maximum" => Op::Maximum(number(&value)?),
+            "exclusiveMinimum" => Op::ExclusiveMinimum(number(&value)?),
+            "exclusiveMaximum" => Op::ExclusiveMaximum(number(&value)?),
+            "minLength" => Op::MinLength(limit(&value)?),
+            "maxLength" => Op::MaxLength(limit(&value)?),
+            "minItems" => Op::MinItems(limit(&value)?),
+            "maxItems" => Op::MaxItems(limit(&value)?),
+            "minProperties" => Op::MinProperties(limit(&value)?),
+            "maxProperties" => Op::MaxProperties(limit(&value)?),
+            "pattern" => match value.as_str().map(Regex::new) {
+                Some(Ok(regex)) => Op::Pattern(regex),
+                _ => return Err(format!("{value} is not a valid pattern")),
+            },
+            _ => return Err(format!("{keyword} does not support $data")),
+        })
+    }
+}
+
+pub struct Node {
+    pub ops: Box<[Op]>,
+    /// whether the node needs to know which properties and items were evaluated.
+    pub collects: bool,
+}
+
+pub struct SchemaTree {
+    nodes: Vec<Node>,
+    index: HashMap<Url, usize>,
+    /// whether any node reads a keyword value from the instance.
+    data: bool,
+}
+
+impl SchemaTree {
+    /// Lower every schema of the context.
+    pub fn new(context: &Context) -> Self {
+        let mut urls: Vec<&Url> = context.schemas.keys().collect();
+        urls.sort();
+        let mut builder = TreeBuilder {
+            nodes: Vec::with_capacity(urls.len()),
+            index: urls
+                .iter()
+                .enumerate()
+                .map(|(i, url)| ((*url).clone(), i))
+                .collect(),
+            data: context.data_references,
+        };
+        // reserve the slots of the compiled schemas so references can point at them up front
+        for _ in &urls {
+            builder.nodes.push(Node {
+                ops: Box::new([]),
+                collects: false,
+            });
+        }
+        for (i, url) in urls.into_iter().enumerate() {
+            builder.nodes[i] = match &context.schemas[url] {
+                ResolvedJsonSchema::Bool(bool) => bool_node(*bool),
+                ResolvedJsonSchema::Object(keywords) => builder.lower_keywords(keywords),
+            };
+        }
+        let data = builder
+            .nodes
+            .iter()
+            .any(|node| node.ops.iter().any(|op| matches!(op, Op::Data { .. })));
+        Self {
+            nodes: builder.nodes,
+            index: builder.index,
+            data,
+        }
+    }
+
+    /// The index of the node a compiled schema was lowered to.
+    pub fn node_index(&self, url: &Url) -> Option<usize> {
+        self.index.get(url).copied()
+    }
+
+    pub fn node(&self, index: usize) -> &Node {
+        &self.nodes[index]
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Whether a keyword reads its value from the instance with `$data`, such values can be
+    /// anywhere in the document so it can not be validated piece by piece.
+    pub fn has_data(&self) -> bool {
+        self.data
+    }
+}
+
+fn bool_node(bool: bool) -> Node {
+    let ops: Box<[Op]> = if bool {
+        Box::new([])
+    } else {
+        Box::new([Op::False])
+    };
+    Node {
+        ops,
+        collects: false,
+    }
+}
+
+struct TreeBuilder {
+    nodes: Vec<Node>,
+    index: HashMap<Url, usize>,
+    /// whether `$data` references are followed.
+    data: bool,
+}
+
+impl TreeBuilder {
+    fn push(&mut self, node: Node) -> usize {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    fn lower_keywords(&mut self, keywords: &Keywords) -> Node {
+        let mut lowering = Lowering {
+            builder: self,
+            keywords,
+            ops: vec![],
+        };
+        keywords.lower(&mut lowering);
+        let collects = lowering
+            .ops
+            .iter()
+            .any(|op| matches!(op, Op::UnevaluatedItems(_) | Op::UnevaluatedProperties(_)));
+        Node {
+            ops: lowering.ops.into_boxed_slice(),
+            collects,
+        }
+    }
+}
+
+/// Collects the ops of a single schema, keywords lower themselves through [`Keyword::lower`].
+///
+/// [`Keyword::lower`]: crate::keywords::Keyword::lower
+pub struct Lowering<'l> {
+    builder: &'l mut TreeBuilder,
+    /// the keywords of the schema being lowered, for keywords that depend on their siblings.
+    pub keywords: &'l Keywords,
+    ops: Vec<Op>,
+}
+
+impl<'l> Lowering<'l> {
+    pub fn push(&mut self, op: Op) {
+        self.ops.push(op);
+    }
+
+    /// The node of a subschema, inline schemas that were not compiled are lowered on the spot.
+    pub fn subschema(&mut self, schema: &JsonSchema) -> usize {
+        match schema {
+            JsonSchema::Resolved(url) => self.url(url),
+            JsonSchema::Bool(bool) => self.builder.push(bool_node(*bool)),
+            JsonSchema::Object(keywords) => {
+                let node = self.builder.lower_keywords(keywords);
+                self.builder.push(node)
+            }
+            JsonSchema::Ref { ref_ } | JsonSchema::Mod { mod_: ref_ } => {
+                let op = Op::Invalid("$ref", format!("{ref_:?} has not been compiled"));
+                self.builder.push(Node {
+                    ops: Box::new([op]),
+                    collects: false,
+                })
+            }
+        }
+    }
+
+    /// The node of a compiled schema, a node that always fails is created if it does not exist.
+    pub fn url(&mut self, url: &Url) -> usize {
+        if let Some(index) = self.builder.index.get(url) {
+            return *index;
+        }
+        let index = self.builder.push(Node {
+            ops: Box::new([Op::Unresolved(url.clone())]),
+            collects: false,
+        });
+        self.builder.index.insert(url.clone(), index);
+        index
+    }
+
+    pub fn subschemas<'s>(
+        &mut self,
+        schemas: impl IntoIterator<Item = &'s JsonSchema>,
+    ) -> Box<[usize]> {
+        schemas
+            .into_iter()
+            .map(|schema| self.subschema(schema))
+            .collect()
+    }
+
+    /// Whether `$data` references are followed, see [`Context::data_references`].
+    pub fn data_references(&self) -> bool {
+        self.builder.data
+    }
+
+    /// The literal value of a keyword that may be a `$data` reference. A reference is lowered to
+    /// [`Op::Data`] when references are followed and to an op that always fails otherwise.
+    pub fn data<'d, T>(&mut self, keyword: &'static str, data: &'d Data<T>) -> Option<&'d T> {
+        let reference = match data {
+            Data::Value(value) => return Some(value),
+            Data::Reference(reference) => reference,
+        };
+        let op = match reference.pointer.parse() {
+            _ if !self.builder.data => {
+                Op::Invalid(keyword, "$data references are not enabled".to_owned())
+            }
+            Ok(pointer) => Op::Data { keyword, pointer },
+            Err(error) => Op::Invalid(
+                keyword,
+                format!("{} is not a valid json pointer: {error}", reference.pointer),
+            ),
+        };
+        self.push(op);
+        None
+    }
+
+    /// Compile a pattern, pushing an op that always fails if it is invalid.
+    pub fn regex(&mut self, keyword: &'static str, pattern: &str) -> Option<Regex> {
+        match Regex::new(pattern) {
+            Ok(regex) => Some(regex),
+            Err(_) => {
+                self.push(Op::Invalid(
+                    keyword,
+                    format!("{pattern} is not a valid pattern"),
+                ));
+                None
+            }
+        }
+    }
+}
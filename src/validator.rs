@@ -1,26 +1,951 @@
-use crate::{context::Context, schema::JsonSchema};
+use crate::{
+    pointer::{AbsoluteJsonPointer, JsonPointerFragment, PointerError},
+    tree::{type_mask, Op, SchemaTree, NUMBER, TYPES},
+    value::{Instance, Kind},
+};
+use serde::Serialize;
+use serde_json::{Number, Value};
+use std::{borrow::Cow, cmp::Ordering, fmt::Write};
 
-#[derive(PartialEq)]
-pub enum ValidatorState {
-    Pass,
-    Fail(String),
-    Defer(String),
-    NA,
+/// A keyword that failed, following the basic output format of the specification.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationError {
+    /// json pointer to the value in the instance that failed.
+    pub instance_location: String,
+    /// the keywords followed from the root schema to reach the failing keyword.
+    pub keyword_location: String,
+    pub message: String,
 }
 
-pub struct ValidationError {
-    message: &'static str,
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({}): {}",
+            self.instance_location, self.keyword_location, self.message
+        )
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// A location in the instance or the schema, kept on the stack while validating so the json
+/// pointer is only written out when an error is reported.
+#[derive(Clone, Copy)]
+pub struct Path<'p> {
+    parent: Option<&'p Path<'p>>,
+    segment: Segment<'p>,
+}
+
+#[derive(Clone, Copy)]
+pub enum Segment<'p> {
+    Root,
+    Key(&'p str),
+    Index(usize),
+    Keyword(&'static str),
+    /// a json pointer that is already escaped, used to start from a location other than the root.
+    Pointer(&'p str),
 }
 
+impl<'p> Path<'p> {
+    pub fn root() -> Self {
+        Self {
+            parent: None,
+            segment: Segment::Root,
+        }
+    }
+
+    pub fn push<'q>(&'q self, segment: Segment<'q>) -> Path<'q> {
+        Path {
+            parent: Some(self),
+            segment,
+        }
+    }
+
+    pub fn key<'q>(&'q self, key: &'q str) -> Path<'q> {
+        self.push(Segment::Key(key))
+    }
+
+    pub fn index(&self, index: usize) -> Path<'_> {
+        self.push(Segment::Index(index))
+    }
+
+    pub fn keyword(&self, keyword: &'static str) -> Path<'_> {
+        self.push(Segment::Keyword(keyword))
+    }
+
+    /// The keys and indices of the path, locations given as [`Segment::Pointer`] are skipped so
+    /// the pointer is relative to the value validation started at.
+    pub fn location(&self) -> AbsoluteJsonPointer {
+        let mut location = match self.parent {
+            Some(parent) => parent.location(),
+            None => AbsoluteJsonPointer::root(),
+        };
+        match self.segment {
+            Segment::Key(key) => location.push(key),
+            Segment::Index(index) => location.push(index.to_string()),
+            Segment::Root | Segment::Keyword(_) | Segment::Pointer(_) => {}
+        }
+        location
+    }
+
+    fn write(&self, pointer: &mut String) {
+        if let Some(parent) = self.parent {
+            parent.write(pointer);
+        }
+        match self.segment {
+            Segment::Root => {}
+            Segment::Key(key) => {
+                pointer.push('/');
+                pointer.push_str(&escape(key));
+            }
+            Segment::Index(index) => {
+                let _ = write!(pointer, "/{index}");
+            }
+            Segment::Keyword(keyword) => {
+                pointer.push('/');
+                pointer.push_str(keyword);
+            }
+            Segment::Pointer(prefix) => pointer.push_str(prefix),
+        }
+    }
+}
+
+impl std::fmt::Display for Path<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut pointer = String::new();
+        self.write(&mut pointer);
+        f.write_str(&pointer)
+    }
+}
+
+/// Properties and items of the current value that were evaluated by successful subschemas,
+/// collected for `unevaluatedProperties` and `unevaluatedItems`. Properties are identified by
+/// their position in the object.
+#[derive(Default)]
+pub struct Evaluated {
+    pub properties: Vec<bool>,
+    pub items: Vec<bool>,
+}
+
+impl Evaluated {
+    fn mark(list: &mut Vec<bool>, i: usize) {
+        if list.len() <= i {
+            list.resize(i + 1, false);
+        }
+        list[i] = true;
+    }
+
+    pub fn property(&mut self, i: usize) {
+        Self::mark(&mut self.properties, i);
+    }
+
+    pub fn item(&mut self, i: usize) {
+        Self::mark(&mut self.items, i);
+    }
+
+    pub fn merge(&mut self, other: Evaluated) {
+        for (list, other) in [
+            (&mut self.properties, other.properties),
+            (&mut self.items, other.items),
+        ] {
+            if list.len() < other.len() {
+                list.resize(other.len(), false);
+            }
+            for (l, r) in list.iter_mut().zip(other) {
+                *l |= r;
+            }
+        }
+    }
+
+    fn is_property(&self, i: usize) -> bool {
+        self.properties.get(i).copied().unwrap_or(false)
+    }
+
+    fn is_item(&self, i: usize) -> bool {
+        self.items.get(i).copied().unwrap_or(false)
+    }
+}
+
+/// Validates values against the nodes of a [`SchemaTree`].
+///
+/// Subschemas whose failure does not necessarily fail the schema (`anyOf`, `oneOf`, `not`, `if`
+/// and `contains`) are first probed quietly, their errors are only collected when the keyword
+/// applying them fails.
 pub struct Validator<'a> {
-    pub parent: Option<&'a Validator<'a>>,
+    pub tree: &'a SchemaTree,
     pub errors: Vec<ValidationError>,
-    pub state: ValidatorState,
-    pub schema: &'a JsonSchema,
-    pub root: &'a mut serde_json::Value,
-    pub segment: &'a str,
-    pub value: Option<&'a mut serde_json::Value>,
-    pub context: &'a mut Context,
+    /// when quiet no errors are recorded and evaluation stops at the first failure.
+    quiet: bool,
+}
+
+impl<'a> Validator<'a> {
+    pub fn new(tree: &'a SchemaTree) -> Self {
+        Self {
+            tree,
+            errors: vec![],
+            quiet: false,
+        }
+    }
+
+    /// A validator that only answers whether a value is valid. It records no errors, stops at the
+    /// first failing keyword and only collects annotations for nodes with `unevaluated*` keywords.
+    pub fn short_circuit(tree: &'a SchemaTree) -> Self {
+        Self {
+            tree,
+            errors: vec![],
+            quiet: true,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Validate a value against a node of the tree, returns whether the value is valid. The value
+    /// is either a `&serde_json::Value` or a node of a [`ValueArena`](crate::value::ValueArena).
+    pub fn validate<'v>(&mut self, node: usize, value: impl Instance<'v>) -> bool {
+        let path = Path::root();
+        self.validate_node(node, value, value, &path, &path, None)
+    }
+
+    /// Validate a value found at `instance` against a node found at `keyword`, both are json
+    /// pointers that prefix the locations of the errors.
+    pub fn validate_at<'v>(
+        &mut self,
+        node: usize,
+        value: impl Instance<'v>,
+        instance: &str,
+        keyword: &str,
+    ) -> bool {
+        let instance = Path {
+            parent: None,
+            segment: Segment::Pointer(instance),
+        };
+        let keyword = Path {
+            parent: None,
+            segment: Segment::Pointer(keyword),
+        };
+        self.validate_node(node, value, value, &instance, &keyword, None)
+    }
+
+    fn validate_node<'v, I: Instance<'v>>(
+        &mut self,
+        node: usize,
+        value: I,
+        root: I,
+        instance: &Path,
+        keyword: &Path,
+        mut evaluated: Option<&mut Evaluated>,
+    ) -> bool {
+        let node = self.tree.node(node);
+        let mut collected = node.collects.then(Evaluated::default);
+        let mut current = match collected.as_mut() {
+            Some(collected) => Some(collected),
+            None => evaluated.as_deref_mut(),
+        };
+        let mut valid = true;
+        for op in node.ops.iter() {
+            if !self.validate_op(op, value, root, instance, keyword, current.as_deref_mut()) {
+                valid = false;
+                if self.quiet {
+                    break;
+                }
+            }
+        }
+        // what a valid node evaluated is also evaluated by the schema it is a subschema of
+        if let (true, Some(collected), Some(evaluated)) = (valid, collected, evaluated) {
+            evaluated.merge(collected);
+        }
+        valid
+    }
+
+    /// Validate a subschema without recording errors.
+    fn probe<'v, I: Instance<'v>>(
+        &mut self,
+        node: usize,
+        value: I,
+        root: I,
+        instance: &Path,
+        keyword: &Path,
+        evaluated: Option<&mut Evaluated>,
+    ) -> bool {
+        let quiet = std::mem::replace(&mut self.quiet, true);
+        let valid = self.validate_node(node, value, root, instance, keyword, evaluated);
+        self.quiet = quiet;
+        valid
+    }
+
+    fn fail(
+        &mut self,
+        instance: &Path,
+        keyword: &Path,
+        name: &'static str,
+        message: impl FnOnce() -> String,
+    ) -> bool {
+        if !self.quiet {
+            self.errors.push(ValidationError {
+                instance_location: instance.to_string(),
+                keyword_location: match name {
+                    "" => keyword.to_string(),
+                    name => keyword.keyword(name).to_string(),
+                },
+                message: message(),
+            });
+        }
+        false
+    }
+
+    fn validate_op<'v, I: Instance<'v>>(
+        &mut self,
+        op: &Op,
+        value: I,
+        root: I,
+        instance: &Path,
+        keyword: &Path,
+        mut evaluated: Option<&mut Evaluated>,
+    ) -> bool {
+        match (op, value.kind()) {
+            (Op::False, _) => self.fail(instance, keyword, "", || {
+                "false schema does not allow any value".to_owned()
+            }),
+            (Op::Unresolved(url), _) => self.fail(instance, keyword, "$ref", || {
+                format!("{url} could not be resolved")
+            }),
+            (Op::Invalid(name, message), _) => {
+                self.fail(instance, keyword, name, || message.clone())
+            }
+            (Op::Type(mask), _) => {
+                let actual = type_name(value);
+                // the mask of "number" includes integers, a value is only ever one of the two
+                let bit = match actual {
+                    "number" => NUMBER,
+                    actual => type_mask(actual),
+                };
+                bit & mask != 0
+                    || self.fail(instance, keyword, "type", || type_message(actual, *mask))
+            }
+            (Op::Const(constant), _) => {
+                json_eq(value, constant)
+                    || self.fail(instance, keyword, "const", || {
+                        format!("value must be {constant}")
+                    })
+            }
+            (Op::Enum(values), _) => {
+                values.iter().any(|v| json_eq(value, v))
+                    || self.fail(instance, keyword, "enum", || {
+                        "value is not one of the enumerated values".to_owned()
+                    })
+            }
+            (Op::MultipleOf(divisor), Kind::Number(number)) => {
+                is_multiple_of(number, divisor)
+                    || self.fail(instance, keyword, "multipleOf", || {
+                        format!("{number} is not a multiple of {divisor}")
+                    })
+            }
+            (Op::Minimum(limit), Kind::Number(number)) => {
+                matches!(
+                    compare_numbers(number, limit),
+                    Some(Ordering::Greater | Ordering::Equal)
+                ) || self.fail(instance, keyword, "minimum", || {
+                    format!("{number} must be greater than or equal to {limit}")
+                })
+            }
+            (Op::Maximum(limit), Kind::Number(number)) => {
+                matches!(
+                    compare_numbers(number, limit),
+                    Some(Ordering::Less | Ordering::Equal)
+                ) || self.fail(instance, keyword, "maximum", || {
+                    format!("{number} must be less than or equal to {limit}")
+                })
+            }
+            (Op::ExclusiveMinimum(limit), Kind::Number(number)) => {
+                compare_numbers(number, limit) == Some(Ordering::Greater)
+                    || self.fail(instance, keyword, "exclusiveMinimum", || {
+                        format!("{number} must be greater than {limit}")
+                    })
+            }
+            (Op::ExclusiveMaximum(limit), Kind::Number(number)) => {
+                compare_numbers(number, limit) == Some(Ordering::Less)
+                    || self.fail(instance, keyword, "exclusiveMaximum", || {
+                        format!("{number} must be less than {limit}")
+                    })
+            }
+            (Op::MinLength(limit), Kind::String(string)) => {
+                let len = string.chars().count() as u64;
+                len >= *limit
+                    || self.fail(instance, keyword, "minLength", || {
+                        format!("string has {len} characters, limit is {limit}")
+                    })
+            }
+            (Op::MaxLength(limit), Kind::String(string)) => {
+                let len = string.chars().count() as u64;
+                len <= *limit
+                    || self.fail(instance, keyword, "maxLength", || {
+                        format!("string has {len} characters, limit is {limit}")
+                    })
+            }
+            (Op::Pattern(regex), Kind::String(string)) => {
+                regex.is_match(string)
+                    || self.fail(instance, keyword, "pattern", || {
+                        format!("{string} does not match {}", regex.as_str())
+                    })
+            }
+            (Op::MinItems(limit), Kind::Array) => {
+                let len = value.len();
+                len as u64 >= *limit
+                    || self.fail(instance, keyword, "minItems", || {
+                        format!("array has {len} items, limit is {limit}")
+                    })
+            }
+            (Op::MaxItems(limit), Kind::Array) => {
+                let len = value.len();
+                len as u64 <= *limit
+                    || self.fail(instance, keyword, "maxItems", || {
+                        format!("array has {len} items, limit is {limit}")
+                    })
+            }
+            (Op::UniqueItems, Kind::Array) => {
+                let duplicate = value
+                    .items()
+                    .enumerate()
+                    .any(|(i, l)| value.items().skip(i + 1).any(|r| json_eq(l, r)));
+                !duplicate
+                    || self.fail(instance, keyword, "uniqueItems", || {
+                        "array items are not unique".to_owned()
+                    })
+            }
+            (Op::MinProperties(limit), Kind::Object) => {
+                let len = value.len();
+                len as u64 >= *limit
+                    || self.fail(instance, keyword, "minProperties", || {
+                        format!("object has {len} properties, limit is {limit}")
+                    })
+            }
+            (Op::MaxProperties(limit), Kind::Object) => {
+                let len = value.len();
+                len as u64 <= *limit
+                    || self.fail(instance, keyword, "maxProperties", || {
+                        format!("object has {len} properties, limit is {limit}")
+                    })
+            }
+            (Op::Required(properties), Kind::Object) => {
+                let mut valid = true;
+                for property in properties.iter() {
+                    if value.get(property).is_none() {
+                        valid = self.fail(instance, keyword, "required", || {
+                            format!("missing required property {property}")
+                        });
+                        if self.quiet {
+                            break;
+                        }
+                    }
+                }
+                valid
+            }
+            (Op::DependentRequired(dependencies), Kind::Object) => {
+                let mut valid = true;
+                for (name, required) in dependencies.iter() {
+                    if value.get(name).is_none() {
+                        continue;
+                    }
+                    for property in required.iter() {
+                        if value.get(property).is_none() {
+                            valid = self.fail(instance, keyword, "dependentRequired", || {
+                                format!("missing property {property} required by {name}")
+                            });
+                            if self.quiet {
+                                return false;
+                            }
+                        }
+                    }
+                }
+                valid
+            }
+            (Op::DependentSchemas(dependencies), Kind::Object) => {
+                let keyword = keyword.keyword("dependentSchemas");
+                let mut valid = true;
+                for (name, node) in dependencies.iter() {
+                    if value.get(name).is_none() {
+                        continue;
+                    }
+                    valid &= self.validate_node(
+                        *node,
+                        value,
+                        root,
+                        instance,
+                        &keyword.key(name),
+                        evaluated.as_deref_mut(),
+                    );
+                    if !valid && self.quiet {
+                        break;
+                    }
+                }
+                valid
+            }
+            (Op::Properties(properties), Kind::Object) => {
+                let keyword = keyword.keyword("properties");
+                let mut valid = true;
+                for (i, (k, v)) in value.entries().enumerate() {
+                    let Ok(j) = properties.binary_search_by(|(name, _)| name.as_str().cmp(k))
+                    else {
+                        continue;
+                    };
+                    let (name, node) = &properties[j];
+                    valid &= self.validate_node(
+                        *node,
+                        v,
+                        root,
+                        &instance.key(k),
+                        &keyword.key(name),
+                        None,
+                    );
+                    if let Some(evaluated) = evaluated.as_deref_mut() {
+                        evaluated.property(i);
+                    }
+                    if !valid && self.quiet {
+                        break;
+                    }
+                }
+                valid
+            }
+            (Op::PatternProperties(patterns), Kind::Object) => {
+                let keyword = keyword.keyword("patternProperties");
+                let mut valid = true;
+                for (regex, node) in patterns.iter() {
+                    for (i, (k, v)) in value.entries().enumerate() {
+                        if !regex.is_match(k) {
+                            continue;
+                        }
+                        let keyword = keyword.key(regex.as_str());
+                        valid &=
+                            self.validate_node(*node, v, root, &instance.key(k), &keyword, None);
+                        if let Some(evaluated) = evaluated.as_deref_mut() {
+                            evaluated.property(i);
+                        }
+                        if !valid && self.quiet {
+                            return false;
+                        }
+                    }
+                }
+                valid
+            }
+            (
+                Op::AdditionalProperties {
+                    node,
+                    properties,
+                    patterns,
+                },
+                Kind::Object,
+            ) => {
+                let keyword = keyword.keyword("additionalProperties");
+                let mut valid = true;
+                for (i, (k, v)) in value.entries().enumerate() {
+                    let in_props = properties
+                        .binary_search_by(|name| name.as_str().cmp(k))
+                        .is_ok();
+                    if in_props || patterns.iter().any(|regex| regex.is_match(k)) {
+                        continue;
+                    }
+                    valid &= self.validate_node(*node, v, root, &instance.key(k), &keyword, None);
+                    if let Some(evaluated) = evaluated.as_deref_mut() {
+                        evaluated.property(i);
+                    }
+                    if !valid && self.quiet {
+                        break;
+                    }
+                }
+                valid
+            }
+            (Op::PropertyNames(node), Kind::Object) => {
+                let keyword = keyword.keyword("propertyNames");
+                let mut valid = true;
+                for (k, _) in value.entries() {
+                    // a name is validated on its own, `$data` references resolve within it
+                    let name = Value::String(k.to_owned());
+                    valid &=
+                        self.validate_node(*node, &name, &name, &instance.key(k), &keyword, None);
+                    if !valid && self.quiet {
+                        break;
+                    }
+                }
+                valid
+            }
+            (Op::PrefixItems(nodes), Kind::Array) => {
+                let keyword = keyword.keyword("prefixItems");
+                let mut valid = true;
+                for (i, (node, item)) in nodes.iter().zip(value.items()).enumerate() {
+                    valid &= self.validate_node(
+                        *node,
+                        item,
+                        root,
+                        &instance.index(i),
+                        &keyword.index(i),
+                        None,
+                    );
+                    if let Some(evaluated) = evaluated.as_deref_mut() {
+                        evaluated.item(i);
+                    }
+                    if !valid && self.quiet {
+                        break;
+                    }
+                }
+                valid
+            }
+            (Op::Items { node, skip }, Kind::Array) => {
+                let keyword = keyword.keyword("items");
+                let mut valid = true;
+                for (i, item) in value.items().enumerate().skip(*skip) {
+                    valid &=
+                        self.validate_node(*node, item, root, &instance.index(i), &keyword, None);
+                    if let Some(evaluated) = evaluated.as_deref_mut() {
+                        evaluated.item(i);
+                    }
+                    if !valid && self.quiet {
+                        break;
+                    }
+                }
+                valid
+            }
+            (Op::Contains { node, min, max }, Kind::Array) => {
+                let contains = keyword.keyword("contains");
+                let mut count = 0;
+                for (i, item) in value.items().enumerate() {
+                    if self.probe(*node, item, root, &instance.index(i), &contains, None) {
+                        count += 1;
+                        match evaluated.as_deref_mut() {
+                            Some(evaluated) => evaluated.item(i),
+                            // without annotations to collect counting can stop once the outcome is known
+                            None if self.quiet && max.map_or(count >= *min, |max| count > max) => {
+                                break
+                            }
+                            None => {}
+                        }
+                    }
+                }
+                if count < *min {
+                    return self.fail(instance, keyword, "contains", || {
+                        format!("array contains {count} matching items, expected at least {min}")
+                    });
+                }
+                match max {
+                    Some(max) if count > *max => {
+                        self.fail(instance, keyword, "maxContains", || {
+                            format!("array contains {count} matching items, expected at most {max}")
+                        })
+                    }
+                    _ => true,
+                }
+            }
+            (Op::AllOf(nodes), _) => {
+                let keyword = keyword.keyword("allOf");
+                let mut valid = true;
+                for (i, node) in nodes.iter().enumerate() {
+                    valid &= self.validate_node(
+                        *node,
+                        value,
+                        root,
+                        instance,
+                        &keyword.index(i),
+                        evaluated.as_deref_mut(),
+                    );
+                    if !valid && self.quiet {
+                        break;
+                    }
+                }
+                valid
+            }
+            (Op::AnyOf(nodes), _) => {
+                let keyword = keyword.keyword("anyOf");
+                let mut valid = false;
+                for (i, node) in nodes.iter().enumerate() {
+                    // annotations of every passing schema are kept, otherwise the first match is enough
+                    let mut branch = evaluated.is_some().then(Evaluated::default);
+                    if self.probe(
+                        *node,
+                        value,
+                        root,
+                        instance,
+                        &keyword.index(i),
+                        branch.as_mut(),
+                    ) {
+                        valid = true;
+                        match (evaluated.as_deref_mut(), branch) {
+                            (Some(evaluated), Some(branch)) => evaluated.merge(branch),
+                            _ => break,
+                        }
+                    }
+                }
+                if !valid && !self.quiet {
+                    self.fail(instance, &keyword, "", || {
+                        "value is not valid against any schema".to_owned()
+                    });
+                    for (i, node) in nodes.iter().enumerate() {
+                        self.validate_node(*node, value, root, instance, &keyword.index(i), None);
+                    }
+                }
+                valid
+            }
+            (Op::OneOf(nodes), _) => {
+                let keyword = keyword.keyword("oneOf");
+                let mut passed = vec![];
+                let mut matched = None;
+                for (i, node) in nodes.iter().enumerate() {
+                    let mut branch = evaluated.is_some().then(Evaluated::default);
+                    if self.probe(
+                        *node,
+                        value,
+                        root,
+                        instance,
+                        &keyword.index(i),
+                        branch.as_mut(),
+                    ) {
+                        if matched.is_some() {
+                            // a second match is enough to know the keyword fails
+                            passed.push(i);
+                            break;
+                        }
+                        matched = Some((i, branch));
+                    }
+                }
+                match matched {
+                    Some((_, branch)) if passed.is_empty() => {
+                        if let (Some(evaluated), Some(branch)) = (evaluated, branch) {
+                            evaluated.merge(branch);
+                        }
+                        true
+                    }
+                    Some((first, _)) => self.fail(instance, &keyword, "", || {
+                        format!(
+                            "value is valid against schemas {first} and {}, expected exactly one",
+                            passed[0]
+                        )
+                    }),
+                    None => {
+                        if !self.quiet {
+                            self.fail(instance, &keyword, "", || {
+                                "value is not valid against any schema".to_owned()
+                            });
+                            for (i, node) in nodes.iter().enumerate() {
+                                self.validate_node(
+                                    *node,
+                                    value,
+                                    root,
+                                    instance,
+                                    &keyword.index(i),
+                                    None,
+                                );
+                            }
+                        }
+                        false
+                    }
+                }
+            }
+            (Op::Not(node), _) => {
+                let keyword = keyword.keyword("not");
+                !self.probe(*node, value, root, instance, &keyword, None)
+                    || self.fail(instance, &keyword, "", || {
+                        "value must not be valid against the schema".to_owned()
+                    })
+            }
+            (Op::IfThenElse { if_, then, else_ }, _) => {
+                let mut condition = evaluated.is_some().then(Evaluated::default);
+                let (name, branch) = if self.probe(
+                    *if_,
+                    value,
+                    root,
+                    instance,
+                    &keyword.keyword("if"),
+                    condition.as_mut(),
+                ) {
+                    if let (Some(evaluated), Some(condition)) =
+                        (evaluated.as_deref_mut(), condition)
+                    {
+                        evaluated.merge(condition);
+                    }
+                    ("then", then)
+                } else {
+                    ("else", else_)
+                };
+                match branch {
+                    Some(node) => self.validate_node(
+                        *node,
+                        value,
+                        root,
+                        instance,
+                        &keyword.keyword(name),
+                        evaluated,
+                    ),
+                    None => true,
+                }
+            }
+            (Op::DynamicRef(node), _) => {
+                // dynamic scopes are not tracked, the reference is followed to its initial target
+                let keyword = keyword.keyword("$dynamicRef");
+                self.validate_node(*node, value, root, instance, &keyword, evaluated)
+            }
+            (Op::UnevaluatedItems(node), Kind::Array) => {
+                let keyword = keyword.keyword("unevaluatedItems");
+                let mut valid = true;
+                for (i, item) in value.items().enumerate() {
+                    if evaluated.as_deref().map(|e| e.is_item(i)).unwrap_or(false) {
+                        continue;
+                    }
+                    valid &=
+                        self.validate_node(*node, item, root, &instance.index(i), &keyword, None);
+                    if !valid && self.quiet {
+                        return false;
+                    }
+                }
+                if let Some(evaluated) = evaluated {
+                    for i in 0..value.len() {
+                        evaluated.item(i);
+                    }
+                }
+                valid
+            }
+            (Op::UnevaluatedProperties(node), Kind::Object) => {
+                let keyword = keyword.keyword("unevaluatedProperties");
+                let mut valid = true;
+                for (i, (k, v)) in value.entries().enumerate() {
+                    if evaluated
+                        .as_deref()
+                        .map(|e| e.is_property(i))
+                        .unwrap_or(false)
+                    {
+                        continue;
+                    }
+                    valid &= self.validate_node(*node, v, root, &instance.key(k), &keyword, None);
+                    if !valid && self.quiet {
+                        return false;
+                    }
+                }
+                if let Some(evaluated) = evaluated {
+                    for i in 0..value.len() {
+                        evaluated.property(i);
+                    }
+                }
+                valid
+            }
+            (
+                Op::Data {
+                    keyword: name,
+                    pointer,
+                },
+                _,
+            ) => {
+                let data = match pointer {
+                    JsonPointerFragment::Absolute(pointer) => {
+                        pointer.resolve(root).map(Instance::to_value)
+                    }
+                    JsonPointerFragment::Relative(pointer) => {
+                        value.relative(pointer, root, || instance.location())
+                    }
+                };
+                let data = match data {
+                    Ok(data) => data,
+                    // a keyword referring to a value that is not present does not apply
+                    Err(PointerError::NotFound(_)) => return true,
+                    Err(error) => {
+                        return self.fail(instance, keyword, name, || {
+                            format!("$data {pointer} could not be resolved: {error}")
+                        })
+                    }
+                };
+                match Op::data(name, data) {
+                    Ok(op) => self.validate_op(&op, value, root, instance, keyword, evaluated),
+                    Err(message) => self.fail(instance, keyword, name, || message),
+                }
+            }
+            // keywords that do not apply to the type of the value
+            _ => true,
+        }
+    }
+}
+
+/// Whether `number` is an integer multiple of `divisor`, allowing for floating point error.
+pub fn is_multiple_of(number: &Number, divisor: &Number) -> bool {
+    match (number.as_i64(), divisor.as_i64()) {
+        // `i64::MIN % -1` overflows
+        (Some(value), Some(divisor)) if divisor != 0 => value.checked_rem(divisor) == Some(0),
+        _ => match (number.as_f64(), divisor.as_f64()) {
+            (Some(value), Some(divisor)) => {
+                // allow for the rounding error of the division
+                let quotient = value / divisor;
+                quotient.is_finite()
+                    && (quotient - quotient.round()).abs() <= f64::EPSILON * quotient.abs().max(1.0)
+            }
+            _ => false,
+        },
+    }
+}
+
+/// The message of a failed `type` keyword.
+pub fn type_message(actual: &str, mask: u8) -> String {
+    let mut expected: Vec<_> = TYPES
+        .iter()
+        .filter(|(_, bit)| bit & mask != 0)
+        .map(|(name, _)| *name)
+        .collect();
+    if mask & NUMBER != 0 {
+        expected.retain(|name| *name != "integer");
+    }
+    format!("{actual} is not of type {}", expected.join(", "))
+}
+
+/// Escape a segment of a json pointer.
+pub fn escape(segment: &str) -> Cow<'_, str> {
+    if segment.contains(['~', '/']) {
+        Cow::Owned(segment.replace('~', "~0").replace('/', "~1"))
+    } else {
+        Cow::Borrowed(segment)
+    }
+}
+
+/// The name of the json type of a value as used by the `type` keyword.
+pub fn type_name<'v>(value: impl Instance<'v>) -> &'static str {
+    match value.kind() {
+        Kind::Null => "null",
+        Kind::Bool(_) => "boolean",
+        Kind::Number(number) if is_integer(number) => "integer",
+        Kind::Number(_) => "number",
+        Kind::String(_) => "string",
+        Kind::Array => "array",
+        Kind::Object => "object",
+    }
+}
+
+/// Numbers with a zero fractional part are integers, i.e. `1.0` is an integer.
+pub fn is_integer(number: &Number) -> bool {
+    number.is_i64() || number.is_u64() || number.as_f64().map(|f| f.fract() == 0.0) == Some(true)
 }
 
-impl<'a> Validator<'a> {}
+pub fn compare_numbers(l: &Number, r: &Number) -> Option<Ordering> {
+    match (l.as_i64(), r.as_i64()) {
+        (Some(l), Some(r)) => Some(l.cmp(&r)),
+        _ => match (l.as_u64(), r.as_u64()) {
+            (Some(l), Some(r)) => Some(l.cmp(&r)),
+            _ => l.as_f64()?.partial_cmp(&r.as_f64()?),
+        },
+    }
+}
+
+/// Equality of json values where numbers are compared by value, so `1` equals `1.0`.
+pub fn json_eq<'l, 'r>(l: impl Instance<'l>, r: impl Instance<'r>) -> bool {
+    match (l.kind(), r.kind()) {
+        (Kind::Null, Kind::Null) => true,
+        (Kind::Bool(l), Kind::Bool(r)) => l == r,
+        (Kind::Number(l), Kind::Number(r)) => compare_numbers(l, r) == Some(Ordering::Equal),
+        (Kind::String(l), Kind::String(r)) => l == r,
+        (Kind::Array, Kind::Array) => {
+            l.len() == r.len() && l.items().zip(r.items()).all(|(l, r)| json_eq(l, r))
+        }
+        (Kind::Object, Kind::Object) => {
+            l.len() == r.len()
+                && l.entries()
+                    .all(|(k, l)| r.get(k).map(|r| json_eq(l, r)).unwrap_or(false))
+        }
+        _ => false,
+    }
+}
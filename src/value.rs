@@ -1,84 +1,450 @@
-use indextree::{Arena, NodeId};
+//! Representations of json documents the validator can evaluate.
+//!
+//! [`ValueArena`] stores a document as a tree of nodes so every value knows its parent and the
+//! key or index it is found at. The validator is generic over [`Instance`], which is implemented
+//! for both `&serde_json::Value` and [`ArenaNode`].
+
+use crate::pointer::{AbsoluteJsonPointer, PointerError, RelativeJsonPointer, RelativeValue};
+use indextree::{Arena, Children, NodeId};
+use serde_json::{Map, Number};
+use std::fmt::Write;
 
 pub enum Value {
     Null,
     Bool(bool),
     Number(serde_json::Number),
     String(String),
+    /// a property of an object, its only child is the value of the property.
     Key(String),
     Object,
     Array,
+    /// the parent of the document, its only child is the document itself.
     Root,
 }
 
+/// A value of the arena and its position among the children of its parent, which makes the
+/// index of an item and the length of an array or object known without counting.
+struct Slot {
+    value: Value,
+    index: usize,
+}
+
 pub struct ValueArena {
-    arena: Arena<Value>,
+    arena: Arena<Slot>,
+    root: NodeId,
+}
+
+impl Default for ValueArena {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ValueArena {
+    /// An arena holding a `null` document.
     pub fn new() -> Self {
-        Self {
-            arena: Arena::new(),
-        }
+        serde_json::Value::Null.into()
+    }
+
+    /// The top level value of the document.
+    pub fn document(&self) -> ArenaNode<'_> {
+        let id = self
+            .root
+            .children(&self.arena)
+            .next()
+            .expect("root has a document");
+        self.node(id)
+    }
+
+    /// The node with the given id, the id must belong to this arena.
+    pub fn node(&self, id: NodeId) -> ArenaNode<'_> {
+        ArenaNode { arena: self, id }
+    }
+
+    /// Resolve a json pointer against the document, e.g. `/items/0`.
+    pub fn pointer(&self, pointer: &str) -> Option<ArenaNode<'_>> {
+        self.document().pointer(pointer)
     }
 }
 
 impl From<serde_json::Value> for ValueArena {
     fn from(value: serde_json::Value) -> Self {
         let mut arena = Arena::new();
-        unpack(&mut arena, value, None);
-        Self { arena }
+        let root = arena.new_node(Slot {
+            value: Value::Root,
+            index: 0,
+        });
+        unpack(&mut arena, value, root, 0);
+        Self { arena, root }
     }
 }
 
-impl Into<serde_json::Value> for ValueArena {
-    fn into(self) -> serde_json::Value {
-        serde_json::Value::Null
+impl From<&ValueArena> for serde_json::Value {
+    fn from(arena: &ValueArena) -> Self {
+        arena.document().pack()
     }
 }
 
-fn unpack(values: &mut Arena<Value>, value: serde_json::Value, parent: Option<NodeId>) -> NodeId {
-    let parent = parent.unwrap_or(values.new_node(Value::Root));
-    match value {
-        serde_json::Value::Null => {
-            let id = values.new_node(Value::Null);
-            parent.append(id, values);
-            id
-        }
-        serde_json::Value::Bool(bool) => {
-            let id = values.new_node(Value::Bool(bool));
-            parent.append(id, values);
-            id
-        }
-        serde_json::Value::Number(num) => {
-            let id = values.new_node(Value::Number(num));
-            parent.append(id, values);
-            id
-        }
-        serde_json::Value::String(str) => {
-            let id = values.new_node(Value::String(str));
-            parent.append(id, values);
-            id
-        }
+impl From<ValueArena> for serde_json::Value {
+    fn from(arena: ValueArena) -> Self {
+        (&arena).into()
+    }
+}
+
+fn unpack(
+    values: &mut Arena<Slot>,
+    value: serde_json::Value,
+    parent: NodeId,
+    index: usize,
+) -> NodeId {
+    let slot = |value| Slot { value, index };
+    let id = match value {
+        serde_json::Value::Null => values.new_node(slot(Value::Null)),
+        serde_json::Value::Bool(bool) => values.new_node(slot(Value::Bool(bool))),
+        serde_json::Value::Number(num) => values.new_node(slot(Value::Number(num))),
+        serde_json::Value::String(str) => values.new_node(slot(Value::String(str))),
         serde_json::Value::Array(arr) => {
-            let pid = values.new_node(Value::Array);
-            parent.append(pid, values);
-            for v in arr {
-                unpack(values, v, Some(pid));
+            let pid = values.new_node(slot(Value::Array));
+            for (i, v) in arr.into_iter().enumerate() {
+                unpack(values, v, pid, i);
             }
             pid
         }
         serde_json::Value::Object(map) => {
-            let pid = values.new_node(Value::Object);
-            parent.append(pid, values);
-            for (k, v) in map {
-                let kid = values.new_node(Value::Key(k));
+            let pid = values.new_node(slot(Value::Object));
+            for (i, (k, v)) in map.into_iter().enumerate() {
+                let kid = values.new_node(Slot {
+                    value: Value::Key(k),
+                    index: i,
+                });
                 pid.append(kid, values);
-                unpack(values, v, Some(kid));
+                unpack(values, v, kid, 0);
             }
             pid
         }
+    };
+    parent.append(id, values);
+    id
+}
+
+/// Where a value is found in its parent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Location<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+/// A value of a [`ValueArena`].
+#[derive(Clone, Copy)]
+pub struct ArenaNode<'a> {
+    arena: &'a ValueArena,
+    id: NodeId,
+}
+
+impl<'a> ArenaNode<'a> {
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    pub fn value(&self) -> &'a Value {
+        &self.arena.arena[self.id].get().value
+    }
+
+    fn slot(&self, id: NodeId) -> &'a Slot {
+        self.arena.arena[id].get()
+    }
+
+    fn parent_id(&self, id: NodeId) -> Option<NodeId> {
+        self.arena.arena[id].parent()
+    }
+
+    /// The object or array containing this value, `None` for the document.
+    pub fn parent(&self) -> Option<ArenaNode<'a>> {
+        let mut parent = self.parent_id(self.id)?;
+        if let Value::Key(_) = self.slot(parent).value {
+            parent = self.parent_id(parent)?;
+        }
+        match self.slot(parent).value {
+            Value::Root => None,
+            _ => Some(self.arena.node(parent)),
+        }
+    }
+
+    /// The key or index of this value in its parent, `None` for the document.
+    pub fn location(&self) -> Option<Location<'a>> {
+        let parent = self.parent_id(self.id)?;
+        match &self.slot(parent).value {
+            Value::Key(key) => Some(Location::Key(key)),
+            Value::Array => Some(Location::Index(self.slot(self.id).index)),
+            _ => None,
+        }
+    }
+
+    /// The json pointer of this value from the document.
+    pub fn pointer_from_document(&self) -> String {
+        let mut locations = vec![];
+        let mut node = *self;
+        while let Some(location) = node.location() {
+            locations.push(location);
+            node = node.parent().expect("a value with a location has a parent");
+        }
+        let mut pointer = String::new();
+        for location in locations.iter().rev() {
+            match location {
+                Location::Key(key) => {
+                    pointer.push('/');
+                    pointer.push_str(&crate::validator::escape(key));
+                }
+                Location::Index(index) => {
+                    let _ = write!(pointer, "/{index}");
+                }
+            }
+        }
+        pointer
+    }
+
+    /// Resolve a json pointer relative to this value.
+    pub fn pointer(&self, pointer: &str) -> Option<ArenaNode<'a>> {
+        AbsoluteJsonPointer::parse(pointer)
+            .ok()?
+            .resolve(*self)
+            .ok()
+    }
+
+    /// The value of a property of an object.
+    pub fn get(&self, key: &str) -> Option<ArenaNode<'a>> {
+        self.entries().find(|(k, _)| *k == key).map(|(_, v)| v)
+    }
+
+    /// The items of an array, empty for other values.
+    pub fn items(&self) -> ArenaItems<'a> {
+        ArenaItems {
+            arena: self.arena,
+            children: match self.value() {
+                Value::Array => Some(self.id.children(&self.arena.arena)),
+                _ => None,
+            },
+        }
+    }
+
+    /// The properties of an object, empty for other values.
+    pub fn entries(&self) -> ArenaEntries<'a> {
+        ArenaEntries {
+            arena: self.arena,
+            children: match self.value() {
+                Value::Object => Some(self.id.children(&self.arena.arena)),
+                _ => None,
+            },
+        }
+    }
+
+    /// Convert the value and everything below it back to a `serde_json::Value`.
+    pub fn pack(&self) -> serde_json::Value {
+        match self.value() {
+            Value::Null => serde_json::Value::Null,
+            Value::Bool(bool) => serde_json::Value::Bool(*bool),
+            Value::Number(num) => serde_json::Value::Number(num.clone()),
+            Value::String(str) => serde_json::Value::String(str.clone()),
+            Value::Array => serde_json::Value::Array(self.items().map(|v| v.pack()).collect()),
+            Value::Object => serde_json::Value::Object(
+                self.entries()
+                    .map(|(k, v)| (k.to_owned(), v.pack()))
+                    .collect::<Map<_, _>>(),
+            ),
+            Value::Key(_) | Value::Root => match self.id.children(&self.arena.arena).next() {
+                Some(id) => self.arena.node(id).pack(),
+                None => serde_json::Value::Null,
+            },
+        }
+    }
+}
+
+pub struct ArenaItems<'a> {
+    arena: &'a ValueArena,
+    children: Option<Children<'a, Slot>>,
+}
+
+impl<'a> Iterator for ArenaItems<'a> {
+    type Item = ArenaNode<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.children.as_mut()?.next()?;
+        Some(self.arena.node(id))
+    }
+}
+
+pub struct ArenaEntries<'a> {
+    arena: &'a ValueArena,
+    children: Option<Children<'a, Slot>>,
+}
+
+impl<'a> Iterator for ArenaEntries<'a> {
+    type Item = (&'a str, ArenaNode<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let key = self.children.as_mut()?.next()?;
+        let Value::Key(name) = &self.arena.arena[key].get().value else {
+            return None;
+        };
+        let value = key.children(&self.arena.arena).next()?;
+        Some((name, self.arena.node(value)))
+    }
+}
+
+/// The json type of an instance, objects and arrays are accessed through [`Instance`].
+#[derive(Clone, Copy)]
+pub enum Kind<'v> {
+    Null,
+    Bool(bool),
+    Number(&'v Number),
+    String(&'v str),
+    Array,
+    Object,
+}
+
+/// A json value the validator can evaluate.
+pub trait Instance<'v>: Copy {
+    type Items: Iterator<Item = Self>;
+    type Entries: Iterator<Item = (&'v str, Self)>;
+
+    fn kind(self) -> Kind<'v>;
+    /// The number of items or properties, 0 for scalars.
+    fn len(self) -> usize;
+    fn is_empty(self) -> bool {
+        self.len() == 0
+    }
+    /// The items of an array, empty for other values.
+    fn items(self) -> Self::Items;
+    /// The properties of an object, empty for other values.
+    fn entries(self) -> Self::Entries;
+    /// The value of a property of an object.
+    fn get(self, key: &str) -> Option<Self>;
+    /// The item of an array at an index.
+    fn item(self, index: usize) -> Option<Self> {
+        self.items().nth(index)
+    }
+    /// A copy of the value and everything below it.
+    fn to_value(self) -> serde_json::Value;
+    /// Evaluate a relative json pointer from this value, found at `location` in `root`. A key or
+    /// index the pointer refers to is returned as a json string or number.
+    fn relative(
+        self,
+        pointer: &RelativeJsonPointer,
+        root: Self,
+        location: impl FnOnce() -> AbsoluteJsonPointer,
+    ) -> Result<serde_json::Value, PointerError> {
+        let location = location();
+        pointer
+            .evaluate_at(root, &location)
+            .map(RelativeValue::to_value)
+    }
+}
+
+pub struct JsonEntries<'v>(Option<serde_json::map::Iter<'v>>);
+
+impl<'v> Iterator for JsonEntries<'v> {
+    type Item = (&'v str, &'v serde_json::Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (k, v) = self.0.as_mut()?.next()?;
+        Some((k, v))
+    }
+}
+
+impl<'v> Instance<'v> for &'v serde_json::Value {
+    type Items = std::slice::Iter<'v, serde_json::Value>;
+    type Entries = JsonEntries<'v>;
+
+    fn kind(self) -> Kind<'v> {
+        match self {
+            serde_json::Value::Null => Kind::Null,
+            serde_json::Value::Bool(bool) => Kind::Bool(*bool),
+            serde_json::Value::Number(number) => Kind::Number(number),
+            serde_json::Value::String(string) => Kind::String(string),
+            serde_json::Value::Array(_) => Kind::Array,
+            serde_json::Value::Object(_) => Kind::Object,
+        }
+    }
+
+    fn len(self) -> usize {
+        match self {
+            serde_json::Value::Array(items) => items.len(),
+            serde_json::Value::Object(map) => map.len(),
+            _ => 0,
+        }
+    }
+
+    fn items(self) -> Self::Items {
+        match self {
+            serde_json::Value::Array(items) => items.iter(),
+            _ => [].iter(),
+        }
+    }
+
+    fn entries(self) -> Self::Entries {
+        JsonEntries(self.as_object().map(|map| map.iter()))
+    }
+
+    fn get(self, key: &str) -> Option<Self> {
+        self.as_object()?.get(key)
+    }
+
+    fn item(self, index: usize) -> Option<Self> {
+        self.as_array()?.get(index)
+    }
+
+    fn to_value(self) -> serde_json::Value {
+        self.clone()
     }
 }
 
-fn pack() {}
+impl<'a> Instance<'a> for ArenaNode<'a> {
+    type Items = ArenaItems<'a>;
+    type Entries = ArenaEntries<'a>;
+
+    fn kind(self) -> Kind<'a> {
+        match self.value() {
+            Value::Null => Kind::Null,
+            Value::Bool(bool) => Kind::Bool(*bool),
+            Value::Number(number) => Kind::Number(number),
+            Value::String(string) => Kind::String(string),
+            Value::Array => Kind::Array,
+            Value::Object => Kind::Object,
+            // a key or the root are never handed out as values
+            Value::Key(_) | Value::Root => Kind::Null,
+        }
+    }
+
+    fn len(self) -> usize {
+        match (self.value(), self.arena.arena[self.id].last_child()) {
+            (Value::Array | Value::Object, Some(last)) => self.slot(last).index + 1,
+            _ => 0,
+        }
+    }
+
+    fn items(self) -> Self::Items {
+        ArenaNode::items(&self)
+    }
+
+    fn entries(self) -> Self::Entries {
+        ArenaNode::entries(&self)
+    }
+
+    fn get(self, key: &str) -> Option<Self> {
+        ArenaNode::get(&self, key)
+    }
+
+    fn to_value(self) -> serde_json::Value {
+        self.pack()
+    }
+
+    fn relative(
+        self,
+        pointer: &RelativeJsonPointer,
+        _: Self,
+        _: impl FnOnce() -> AbsoluteJsonPointer,
+    ) -> Result<serde_json::Value, PointerError> {
+        // the parents are known, the path from the root is not replayed
+        pointer.evaluate(self).map(RelativeValue::to_value)
+    }
+}